@@ -0,0 +1,162 @@
+//! Benchmarks of the work done by Robyn for every request, outside of the python handlers: reading
+//! the actix request, matching its route and writing the response. The request to a const route
+//! goes through the whole of `index` without the interpreter, as its response is computed once
+//! when the route is added.
+//!
+//! Run them with `cargo bench --no-default-features`, which links the interpreter as the python
+//! module doesn't. To compare a branch to the one it is based on, pass `-- --save-baseline main`
+//! on the base branch, then `-- --baseline main` on the branch.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+
+use actix_web::{body, http::Method, test, Responder};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use dashmap::DashMap;
+use robyn::bench::{HttpMethod, Request, Response, RouteTable, Server};
+use tokio::runtime::Runtime;
+
+const URI: &str = "/users/42?page=2&sort=name";
+
+// the headers sent by a browser with a small GET
+const HEADERS: [(&str, &str); 8] = [
+    ("Host", "localhost:8080"),
+    (
+        "User-Agent",
+        "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0",
+    ),
+    (
+        "Accept",
+        "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+    ),
+    ("Accept-Language", "en-US,en;q=0.5"),
+    ("Accept-Encoding", "gzip, deflate, br"),
+    ("Connection", "keep-alive"),
+    ("Cookie", "session=8f14e45fceea167a5a36dedd4bea2543"),
+    ("Cache-Control", "max-age=0"),
+];
+
+fn get_request() -> test::TestRequest {
+    HEADERS.iter().fold(
+        test::TestRequest::get()
+            .uri(URI)
+            .peer_addr(SocketAddr::from(([127, 0, 0, 1], 54321))),
+        |request, header| request.insert_header(*header),
+    )
+}
+
+fn text_response(body: &str) -> Response {
+    Response {
+        status_code: 200,
+        response_type: "text".to_string(),
+        headers: HashMap::from([("Content-Type".to_string(), "text/plain".to_string())]),
+        body: body.as_bytes().to_vec(),
+        file_path: None,
+        stream: None,
+        trailers: None,
+    }
+}
+
+fn request_parsing(c: &mut Criterion) {
+    let req = get_request().to_http_request();
+    let global_headers = DashMap::new();
+
+    // the routes of a small REST API
+    let mut routes = RouteTable::new();
+    for resource in ["users", "posts", "comments", "tags", "files"] {
+        routes.insert(&format!("/{resource}"), ()).unwrap();
+        routes.insert(&format!("/{resource}/:id"), ()).unwrap();
+        routes.insert(&format!("/{resource}/:id/edit"), ()).unwrap();
+    }
+
+    let mut group = c.benchmark_group("request_parsing");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("from_actix_request", |b| {
+        b.iter(|| Request::from_actix_request(black_box(&req), &global_headers, false))
+    });
+    group.bench_function("route_matching", |b| {
+        b.iter(|| routes.at(black_box("/users/42")).unwrap().value)
+    });
+    group.finish();
+}
+
+fn response_serialization(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let req = &test::TestRequest::get().to_http_request();
+
+    let mut group = c.benchmark_group("response_serialization");
+    for size in [64, 16 * 1024] {
+        let response = text_response(&"a".repeat(size));
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &response,
+            |b, response| {
+                b.to_async(&runtime).iter_batched(
+                    || response.clone(),
+                    |response| async move {
+                        body::to_bytes(response.respond_to(req).into_body())
+                            .await
+                            .unwrap()
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    // a body of an unknown length, sent in chunks. Its reader is held by a python object, like
+    // the chunks of a streamed file.
+    pyo3::prepare_freethreaded_python();
+    let size = 256 * 1024;
+    group.throughput(Throughput::Bytes(size as u64));
+    group.bench_function("stream", |b| {
+        b.to_async(&runtime).iter_batched(
+            || Response::from_stream(io::Cursor::new(vec![b'a'; size])).unwrap(),
+            |response| async move {
+                body::to_bytes(response.respond_to(req).into_body())
+                    .await
+                    .unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn small_get(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let server = &Server::new(None).unwrap();
+    server
+        .add_const_response(
+            &HttpMethod::GET,
+            "/users/:id",
+            text_response("Hello, world!"),
+        )
+        .unwrap();
+    let headers: HashMap<String, String> = HEADERS
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let req = &test::TestRequest::get().to_http_request();
+
+    let mut group = c.benchmark_group("small_get");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("const_route", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let response = server
+                .answer_test_request(Method::GET, URI, headers.clone(), vec![])
+                .await
+                .unwrap();
+            body::to_bytes(response.respond_to(req).into_body())
+                .await
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, request_parsing, response_serialization, small_get);
+criterion_main!(benches);
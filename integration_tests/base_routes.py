@@ -1,15 +1,34 @@
+import asyncio
+import hashlib
+import json
 import os
+import sys
+import threading
+import time
 
 import pathlib
 from collections import defaultdict
 from typing import Optional
 
-from robyn import WS, Robyn, Request, Response, jsonify, serve_file, serve_html
+from robyn import (
+    WS,
+    CacheControl,
+    Robyn,
+    Request,
+    Response,
+    SecurityHeaders,
+    jsonify,
+    negotiate,
+    offload,
+    serve_file,
+    serve_html,
+)
+from robyn.robyn import PanicException
 from robyn.authentication import AuthenticationHandler, BearerGetter, Identity
 from robyn.templating import JinjaTemplate
 
 from views import SyncView, AsyncView
-from subroutes import sub_router
+from subroutes import mounted_router, sub_router
 
 app = Robyn(__file__)
 websocket = WS(app, "/web_socket")
@@ -158,6 +177,57 @@ async def async_middlewares(request: Request):
     return "async middlewares"
 
 
+class RequestUser:
+    def __init__(self, name: str):
+        self.name = name
+
+
+@app.before_request("/sync/middlewares/extensions")
+def sync_before_request_extensions(request: Request):
+    request.extensions[RequestUser] = RequestUser("robyn")
+    return request
+
+
+@app.get("/sync/middlewares/extensions")
+def sync_middlewares_extensions(request: Request):
+    user = request.extensions[RequestUser]
+    return f"hello {user.name}, {len(request.extensions)} extension"
+
+
+@app.get("/sync/extensions")
+def sync_extensions(request: Request):
+    return f"{len(request.extensions)} extensions"
+
+
+@app.before_request("/sync/middlewares/matched_route/:id")
+def sync_before_request_matched_route(request: Request):
+    request.headers["before_matched_route"] = request.matched_route
+    return request
+
+
+@app.get("/sync/middlewares/matched_route/:id")
+def sync_middlewares_matched_route(request: Request):
+    return f"{request.headers['before_matched_route']} {request.matched_route}"
+
+
+# blocks its worker, which delays the timers of the worker
+@app.get("/sync/metrics/blocking")
+def sync_metrics_blocking():
+    time.sleep(0.3)
+    return "blocked"
+
+
+# the first chunk is sent at once, the last one 300ms later
+@app.get("/sync/metrics/stream")
+def sync_metrics_stream():
+    def slow_chunks():
+        yield "first chunk\n"
+        time.sleep(0.3)
+        yield "last chunk\n"
+
+    return slow_chunks()
+
+
 @app.before_request("/sync/middlewares/401")
 def sync_before_request_401():
     return Response(401, {}, "sync before request 401")
@@ -254,11 +324,26 @@ def sync_response_get():
     return Response(200, {"sync": "response"}, "sync response get")
 
 
+@app.get("/sync/response/server_header")
+def sync_response_server_header_get():
+    return Response(200, {"server": "handler"}, "sync response server header get")
+
+
 @app.get("/async/response")
 async def async_response_get():
     return Response(200, {"async": "response"}, "async response get")
 
 
+@app.get("/sync/response/large")
+def sync_response_large_get():
+    return "a large sync response " * 100
+
+
+@app.get("/sync/response/large/octet")
+def sync_response_large_octet_get():
+    return b"a large sync octet response " * 100
+
+
 @app.get("/sync/response/const", const=True)
 def sync_response_const_get():
     return Response(200, {"sync_const": "response"}, "sync response const get")
@@ -269,6 +354,159 @@ async def async_response_const_get():
     return Response(200, {"async_const": "response"}, "async response const get")
 
 
+# Content negotiation
+
+
+@app.get("/sync/negotiate")
+def sync_negotiate(request: Request):
+    return negotiate(
+        request,
+        {
+            "application/json": {"sync": "negotiate"},
+            "text/html": lambda: "<p>sync negotiate</p>",
+        },
+        default=request.queries.get("default"),
+    )
+
+
+# Tuples
+
+
+@app.get("/sync/tuple")
+def sync_tuple_get():
+    return 201, "sync tuple get"
+
+
+@app.get("/async/tuple")
+async def async_tuple_get():
+    return 201, {"async": "tuple"}, b"async tuple get"
+
+
+@app.get("/sync/tuple/content_type")
+def sync_tuple_content_type_get():
+    return 200, {"content-type": "application/json"}, '{"sync": "tuple"}'
+
+
+@app.get("/sync/tuple/const", const=True)
+def sync_tuple_const_get():
+    return 202, {"sync_const": "tuple"}, "sync tuple const get"
+
+
+@app.get("/sync/tuple/invalid")
+def sync_tuple_invalid_get():
+    return 200, "not headers", "sync tuple invalid get"
+
+
+@app.get("/async/tuple/invalid")
+async def async_tuple_invalid_get():
+    return (200,)
+
+
+# Streamed responses
+
+
+def chunks(prefix: str):
+    for i in range(3):
+        yield f"{prefix} chunk {i}\n"
+
+
+@app.get("/sync/response/stream")
+def sync_response_stream_get():
+    return chunks("sync")
+
+
+@app.get("/async/response/stream")
+async def async_response_stream_get():
+    return Response(200, {"async": "stream"}, chunks("async"))
+
+
+@app.get("/sync/response/stream/const", const=True)
+def sync_response_stream_const_get():
+    return chunks("sync const")
+
+
+@app.get("/sync/response/stream/trailers")
+def sync_response_stream_trailers_get():
+    # the checksum of the body is only known once its chunks are sent
+    def checksummed_chunks():
+        digest = hashlib.sha256()
+        for chunk in chunks("sync"):
+            digest.update(chunk.encode())
+            yield chunk
+            yield ""
+        response.trailers["Checksum"] = digest.hexdigest()
+        response.trailers["Undeclared"] = "left out"
+
+    response = Response(200, {"Trailer": "Checksum"}, checksummed_chunks())
+    return response
+
+
+@app.get("/sync/response/stream/error")
+def sync_response_stream_error_get():
+    def failing_chunks():
+        yield "before the error\n"
+        raise ValueError("the stream failed")
+
+    return failing_chunks()
+
+
+# Cancellation
+
+# how the last handlers polling their cancellation token ended, by name
+cancellations = {}
+stored_cancellations = []
+
+
+@app.get("/async/cancellation")
+async def async_cancellation_get(request: Request):
+    for _ in range(100):
+        if request.cancellation.cancelled:
+            cancellations["async"] = "cancelled"
+            return "cancelled"
+        await asyncio.sleep(0.05)
+    cancellations["async"] = "finished"
+    return "finished"
+
+
+# runs for longer than the drain timeout of the tests, unless the server shuts down
+@app.get("/async/cancellation/shutdown")
+async def async_cancellation_shutdown_get(request: Request):
+    for _ in range(100):
+        if request.cancellation.shutting_down:
+            return "stopped for the shutdown"
+        await asyncio.sleep(0.05)
+    return "finished"
+
+
+@app.get("/sync/cancellation/stream")
+def sync_cancellation_stream_get(request: Request):
+    def endless_chunks():
+        try:
+            while not request.cancellation.cancelled:
+                yield "chunk\n"
+                time.sleep(0.01)
+        finally:
+            cancellations["stream"] = "closed"
+
+    return endless_chunks()
+
+
+@app.get("/sync/cancellation/stored")
+def sync_cancellation_stored_get(request: Request):
+    stored_cancellations.append(request.cancellation)
+    return str(request.cancellation.cancelled)
+
+
+@app.get("/sync/cancellation/stored/state")
+def sync_cancellation_stored_state_get():
+    return str(stored_cancellations[-1].cancelled)
+
+
+@app.get("/sync/cancellation/state/:name")
+def sync_cancellation_state_get(request: Request):
+    return cancellations.pop(request.path_params["name"], "running")
+
+
 # Binary
 
 
@@ -381,6 +619,11 @@ async def async_http_param(request: Request):
     )
 
 
+@app.get("/sync/http/header")
+def sync_http_header(request: Request):
+    return request.headers.get("x-value", "")
+
+
 # HTML serving
 
 
@@ -396,6 +639,90 @@ async def async_serve_html():
     return serve_html(html_file)
 
 
+@app.get("/sync/file/response")
+def sync_file_response():
+    html_file = os.path.join(current_file_path, "index.html")
+    return Response.from_file(html_file)
+
+
+@app.get("/async/file/response")
+async def async_file_response():
+    html_file = os.path.join(current_file_path, "index.html")
+    return Response.from_file(html_file)
+
+
+@app.get("/sync/file/response/precompressed")
+def sync_precompressed_file_response():
+    html_file = os.path.join(current_file_path, "precompressed", "page.html")
+    return Response.from_file(html_file)
+
+
+@app.get("/sync/file/response/precompressed/vary")
+def sync_precompressed_file_response_vary():
+    html_file = os.path.join(current_file_path, "precompressed", "page.html")
+    response = Response.from_file(html_file)
+    response.add_vary("Accept-Language")
+    response.add_vary("accept-language")
+    return response
+
+
+@app.get("/sync/vary")
+def sync_vary():
+    response = Response(200, {"vary": "Accept-Language"}, "vary")
+    response.add_vary("Cookie")
+    return response
+
+
+@app.get("/sync/file/response/mime/:extension")
+def sync_file_response_mime(request: Request):
+    extension = request.path_params["extension"]
+    return Response.from_file(
+        os.path.join(current_file_path, "downloads", f"test.{extension}")
+    )
+
+
+@app.get("/sync/file/response/missing")
+def sync_missing_file_response():
+    return Response.from_file(os.path.join(current_file_path, "missing.html"))
+
+
+@app.get("/sync/file/response/sized")
+def sync_sized_file_response(request: Request):
+    return Response.from_file(request.queries["path"])
+
+
+@app.head("/sync/file/response/sized")
+def sync_sized_file_response_head(request: Request):
+    return Response.from_file(request.queries["path"])
+
+
+@app.get("/sync/file/response/sized/streamed")
+def sync_sized_file_response_streamed(request: Request):
+    # whether the file is streamed from disk rather than read in memory
+    return str(Response.from_file(request.queries["path"]).stream is not None)
+
+
+@app.get("/sync/file/response/sized/iterated")
+def sync_sized_file_response_iterated(request: Request):
+    # the chunks of the streamed file, read in python
+    stream = Response.from_file(request.queries["path"]).stream
+    return str(sum(len(chunk) for chunk in stream))
+
+
+@app.get("/sync/file/response/sized/truncated")
+def sync_sized_file_response_truncated(request: Request):
+    # the file is truncated once it is opened, before it is sent
+    response = Response.from_file(request.queries["path"])
+    with open(request.queries["path"], "r+b") as file:
+        file.truncate(100)
+    return response
+
+
+@app.get("/sync/file/download/sized")
+def sync_sized_file_download(request: Request):
+    return serve_file(request.queries["path"])
+
+
 # Template
 
 
@@ -428,6 +755,68 @@ async def file_download_async():
     return serve_file(file_path)
 
 
+# Timeouts
+
+
+@app.get("/async/timeout", timeout=0.1)
+async def async_timeout():
+    await asyncio.sleep(1)
+    return "should have timed out"
+
+
+@app.get("/async/timeout/in_time", timeout=1)
+async def async_timeout_in_time():
+    await asyncio.sleep(0.01)
+    return "in time"
+
+
+@app.get("/async/timeout/raised", timeout=1)
+async def async_timeout_raised():
+    # e.g. the timeout of a call made by the handler, before the one of the route
+    raise asyncio.TimeoutError()
+
+
+@app.get("/async/deadline")
+async def async_deadline(request: Request):
+    # the deadline of the request cancels the handler, as the timeout of a route does
+    await asyncio.sleep(0.5)
+    return str(request.deadline)
+
+
+@app.get("/sync/deadline")
+def sync_deadline(request: Request):
+    return str(request.deadline)
+
+
+@app.get("/async/slow")
+async def async_slow():
+    # still in flight when the server is asked to terminate
+    await asyncio.sleep(1)
+    return "slow"
+
+
+# Offloading
+
+
+def blocking_sum(start: int, end: int):
+    return {"sum": sum(range(start, end)), "thread": threading.get_ident()}
+
+
+@app.get("/async/offload")
+async def async_offload():
+    result = await offload(blocking_sum, 0, 1000)
+    result["loop_thread"] = threading.get_ident()
+    return jsonify(result)
+
+
+# Large responses
+
+
+@app.get("/sync/large_response")
+def sync_large_response():
+    return "a" * 5_000_000
+
+
 # Queries
 
 
@@ -509,6 +898,27 @@ async def async_dict_post():
 # Body
 
 
+@app.get("/sync/thread")
+def sync_thread():
+    # the thread running the sync handlers, its native id isn't reused as soon as it stops
+    return str(threading.get_native_id())
+
+
+@app.get("/sync/deep_recursion")
+def sync_deep_recursion():
+    # the json encoder recurses in C for every level, this overflows the default stack of 2MB
+    # and crashes the server, it is only requested with a larger worker stack size
+    nested = []
+    for _ in range(50_000):
+        nested = [nested]
+    recursion_limit = sys.getrecursionlimit()
+    sys.setrecursionlimit(100_000)
+    try:
+        return str(len(json.dumps(nested)))
+    finally:
+        sys.setrecursionlimit(recursion_limit)
+
+
 @app.post("/sync/body")
 def sync_body_post(request: Request):
     return request.body
@@ -519,6 +929,78 @@ async def async_body_post(request: Request):
     return request.body
 
 
+@app.post("/sync/body/framing")
+def sync_body_framing_post(request: Request):
+    # the headers framing the body, once the server has checked them
+    length = request.headers.get("content-length")
+    encoding = request.headers.get("transfer-encoding")
+    return f"{length} {encoding} {request.body}"
+
+
+@app.post("/sync/body/small", max_body_size=64)
+def sync_body_small_post(request: Request):
+    return request.body
+
+
+@app.post("/async/body/large", max_body_size=3_000_000)
+async def async_body_large_post(request: Request):
+    return str(len(request.body))
+
+
+@app.post("/async/body/stream", stream_body=True)
+async def async_body_stream_post(request: Request):
+    size = 0
+    chunks = 0
+    async for chunk in request.stream:
+        size += len(chunk)
+        chunks += 1
+    return jsonify({"size": size, "chunks": chunks, "body": request.body})
+
+
+def uploaded_form(request: Request):
+    files = {}
+    for name, uploaded_file in request.files.items():
+        with open(uploaded_file.path, "rb") as file:
+            content = file.read()
+        files[name] = {
+            "filename": uploaded_file.filename,
+            "content_type": uploaded_file.content_type,
+            "path": uploaded_file.path,
+            "size": uploaded_file.size,
+            "sha256": hashlib.sha256(content).hexdigest(),
+        }
+    return jsonify({"fields": request.form_data, "files": files, "body": request.body})
+
+
+@app.post("/sync/form/files", stream_files=True)
+def sync_form_files_post(request: Request):
+    return uploaded_form(request)
+
+
+@app.post("/async/form/files", stream_files=True)
+async def async_form_files_post(request: Request):
+    return uploaded_form(request)
+
+
+# Params
+
+
+def request_params(request: Request):
+    names = ["id", "page", "name", "missing"]
+    params = {name: request.param(name, "default") for name in names}
+    return jsonify({"params": params, "form": request.form()})
+
+
+@app.post("/sync/params/:id", stream_files=True)
+def sync_params_post(request: Request):
+    return request_params(request)
+
+
+@app.post("/async/params/:id")
+async def async_params_post(request: Request):
+    return request_params(request)
+
+
 # --- PUT ---
 
 # dict
@@ -633,6 +1115,34 @@ async def async_body_patch(request: Request):
     return request.body
 
 
+# Cache-Control
+
+
+@app.get("/sync/cache_control", cache_control=CacheControl.NO_STORE)
+def sync_cache_control_get():
+    return "not stored"
+
+
+@app.get("/async/cache_control", cache_control=CacheControl.NO_STORE)
+async def async_cache_control_get():
+    return "not stored"
+
+
+@app.get("/sync/cache_control/const", const=True, cache_control=CacheControl.IMMUTABLE)
+def sync_cache_control_const_get():
+    return "immutable"
+
+
+@app.get("/sync/cache_control/override", cache_control=CacheControl.IMMUTABLE)
+def sync_cache_control_override_get():
+    return Response(200, {"Cache-Control": CacheControl.max_age(60)}, "overridden")
+
+
+@app.get("/sync/cache_control/error", cache_control=CacheControl.IMMUTABLE)
+def sync_cache_control_error_get():
+    raise Exception("not cached")
+
+
 # ===== Views =====
 
 
@@ -659,11 +1169,6 @@ def async_decorator_view():
 # ==== Exception Handling ====
 
 
-@app.exception
-def handle_exception(error):
-    return {"status_code": 500, "body": f"error msg: {error}"}
-
-
 @app.get("/sync/exception/get")
 def sync_exception_get():
     raise ValueError("value error")
@@ -694,6 +1199,25 @@ async def async_exception_post(_: Request):
     raise ValueError("value error")
 
 
+# resumes as a panic once the error reaches Rust, to test how the panics are answered
+@app.get("/sync/panic")
+def sync_panic():
+    raise PanicException("on purpose")
+
+
+# the handler covers the routes added before it
+@app.exception
+def handle_exception(error, request: Request):
+    if not isinstance(error, ValueError):
+        # falls back to the default internal server error response
+        raise error
+    return {
+        "status_code": 500,
+        "body": f"error msg: {error}",
+        "headers": {"X-Error-Path": request.url.path},
+    }
+
+
 # ===== Authentication =====
 
 
@@ -711,20 +1235,157 @@ async def async_auth(request: Request):
     return "authenticated"
 
 
+# ===== Security Headers =====
+
+
+@app.get("/sync/security_headers/override")
+def sync_security_headers_override():
+    return Response(200, {"X-Frame-Options": "SAMEORIGIN"}, "overridden")
+
+
+# ===== Preconditions =====
+
+DOCUMENT_ETAG = '"v1"'
+
+
+@app.get("/sync/preconditions/document")
+def sync_preconditions_document_get(request: Request):
+    status = request.evaluate_preconditions(DOCUMENT_ETAG)
+    if status is not None:
+        return Response(status, {"ETag": DOCUMENT_ETAG}, "")
+    return Response(200, {"ETag": DOCUMENT_ETAG}, "document")
+
+
+@app.put("/sync/preconditions/document")
+def sync_preconditions_document_put(request: Request):
+    status = request.evaluate_preconditions(DOCUMENT_ETAG)
+    if status is not None:
+        return Response(status, {}, "")
+    return "updated"
+
+
+@app.put("/async/preconditions/missing")
+async def async_preconditions_missing_put(request: Request):
+    status = request.evaluate_preconditions(None)
+    if status is not None:
+        return Response(status, {}, "")
+    return "created"
+
+
+@app.get("/sync/preconditions/tags")
+def sync_preconditions_tags_get(request: Request):
+    return jsonify(
+        {"if_match": request.if_match, "if_none_match": request.if_none_match}
+    )
+
+
+# ===== Fallbacks =====
+
+
+@app.fallback
+def sync_fallback(request: Request):
+    if request.url.path == "/sync/fallback/error":
+        raise ValueError("the fallback failed")
+    if request.url.path.startswith("/sync/fallback/"):
+        return f"sync fallback {request.url.path}"
+    return None
+
+
+@app.fallback
+async def async_fallback(request: Request):
+    if request.url.path == "/async/fallback/slow":
+        # the deadline of the request cancels the fallback, as it does a handler
+        await asyncio.sleep(0.5)
+    if request.url.path.startswith("/async/fallback/"):
+        body = f"async fallback {request.url.path}"
+        return Response(202, {"async": "fallback"}, body)
+    return None
+
+
+app.add_spa_fallback(
+    os.path.join(current_file_path, "build", "index.html"), prefix="/spa"
+)
+
+
+# ===== Redirects =====
+
+
+@app.get("/sync/redirect/relative")
+def sync_redirect_relative():
+    return Response.redirect("/sync/str")
+
+
+@app.get("/sync/redirect/absolute")
+def sync_redirect_absolute(request: Request):
+    return Response.redirect(request.url.absolute(request.queries["to"]), 303)
+
+
+# ===== Tunnel =====
+
+
+@app.tunnel
+async def echo_tunnel(request: Request, tunnel):
+    await tunnel.write(f"tunnel to {request.url.host}\n")
+    while chunk := await tunnel.read():
+        await tunnel.write(chunk.upper())
+
+
 # ===== Main =====
 
 
 if __name__ == "__main__":
+    os.environ["ROBYN_METRICS_PATH"] = "/metrics"
     app.add_response_header("server", "robyn")
+    app.add_security_headers(
+        SecurityHeaders(content_security_policy=None, cross_origin_isolation=True)
+    )
+    app.add_error_page(500, os.path.join(current_file_path, "errors", "500.html"))
+    app.add_mime_type("robyn", "text/x-robyn")
+    app.add_mime_type(".TXT", "text/plain; charset=utf-8")
+    # a missing page falls back to the default error response
+    app.add_error_page(404, os.path.join(current_file_path, "errors", "404.html"))
     app.add_directory(
         route="/test_dir",
         directory_path=os.path.join(current_file_path, "build"),
         index_file="index.html",
     )
+    app.add_directory(
+        route="/static_site",
+        directory_path=os.path.join(current_file_path, "static_site"),
+        index_file=["index.html", "index.htm"],
+    )
+    app.add_directory(
+        route="/static_site_forbidden",
+        directory_path=os.path.join(current_file_path, "static_site"),
+        missing_index_status=403,
+    )
+    app.add_directory(
+        route="/listed_site",
+        directory_path=os.path.join(current_file_path, "static_site"),
+        index_file=None,
+        show_files_listing=True,
+    )
+    app.add_directory(
+        route="/listed_site_hidden",
+        directory_path=os.path.join(current_file_path, "static_site"),
+        index_file=None,
+        show_files_listing=True,
+        show_hidden_files=True,
+    )
+    app.add_directory(
+        route="/downloads",
+        directory_path=os.path.join(current_file_path, "downloads"),
+    )
+    app.add_directory(
+        route="/cached_site",
+        directory_path=os.path.join(current_file_path, "static_site"),
+        cache_control=CacheControl.IMMUTABLE,
+    )
     app.startup_handler(startup_handler)
     app.add_view("/sync/view", SyncView)
     app.add_view("/async/view", AsyncView)
     app.include_router(sub_router)
+    app.mount("/mounted/", mounted_router)
 
     class BasicAuthHandler(AuthenticationHandler):
         def authenticate(self, request: Request) -> Optional[Identity]:
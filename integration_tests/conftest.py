@@ -1,24 +1,29 @@
 import os
 import pathlib
+import re
 import signal
 import socket
 import subprocess
+import tempfile
 import time
-from typing import List
+from typing import IO, Dict, Iterator, List, NamedTuple, Optional, Tuple
 import platform
 
 import pytest
 from helpers.network_helpers import get_network_host
 
 
-def spawn_process(command: List[str]) -> subprocess.Popen:
+def spawn_process(command: List[str], **kwargs) -> subprocess.Popen:
     if platform.system() == "Windows":
         command[0] = "python"
         process = subprocess.Popen(
-            command, shell=True, creationflags=subprocess.CREATE_NEW_PROCESS_GROUP
+            command,
+            shell=True,
+            creationflags=subprocess.CREATE_NEW_PROCESS_GROUP,
+            **kwargs,
         )
         return process
-    process = subprocess.Popen(command, preexec_fn=os.setsid)
+    process = subprocess.Popen(command, preexec_fn=os.setsid, **kwargs)
     return process
 
 
@@ -64,6 +69,46 @@ def start_server(domain: str, port: int, is_dev: bool = False) -> subprocess.Pop
     return process
 
 
+def start_ephemeral_server(
+    domain: str,
+    env: Optional[Dict[str, str]] = None,
+    app: str = "base_routes.py",
+    logs: Optional[IO[bytes]] = None,
+    args: Optional[List[str]] = None,
+) -> Tuple[subprocess.Popen, int]:
+    """
+    Starts the server of the app on a port picked by the OS and returns it with the port.
+    The environment variables in env are set for the server, its logs are written to logs.
+    The app is given the command line arguments in args.
+    """
+    current_file_path = pathlib.Path(__file__).parent.resolve()
+    app_path = os.path.join(current_file_path, app)
+    env = dict(os.environ, **(env or {}), ROBYN_URL=domain, ROBYN_PORT="0")
+    # the resolved port is only known from the logs of the server
+    if logs is None:
+        logs = tempfile.TemporaryFile()
+    process = spawn_process(["python3", app_path, *(args or [])], env=env, stderr=logs)
+
+    timeout = 5
+    start_time = time.time()
+    pattern = re.compile(rf"Starting server at {re.escape(domain)}:(\d+)".encode())
+    while True:
+        if time.time() - start_time > timeout:
+            kill_process(process)
+            raise ConnectionError("Could not reach Robyn server")
+        logs.seek(0)
+        match = pattern.search(logs.read())
+        if match is not None:
+            port = int(match.group(1))
+            try:
+                sock = socket.create_connection((domain, port), timeout=5)
+                sock.close()
+                return process, port
+            except Exception:
+                pass
+        time.sleep(0.1)
+
+
 @pytest.fixture(scope="session")
 def session():
     domain = "127.0.0.1"
@@ -116,6 +161,36 @@ def test_session():
     kill_process(process)
 
 
+class EphemeralServer(NamedTuple):
+    process: subprocess.Popen
+    port: int
+    logs: IO[bytes]
+
+
+@pytest.fixture(scope="module")
+def ephemeral_server(request) -> Iterator[EphemeralServer]:
+    """
+    Starts a server on a port picked by the OS with the keyword arguments of
+    start_ephemeral_server given as the param of the fixture, see with_ephemeral_server,
+    and yields it with its logs. The tests of a module given the same server share it.
+    """
+    logs = tempfile.TemporaryFile()
+    process, port = start_ephemeral_server(
+        "127.0.0.1", logs=logs, **getattr(request, "param", {})
+    )
+    yield EphemeralServer(process, port, logs)
+    kill_process(process)
+
+
+@pytest.fixture
+def drain_server():
+    # each test terminates its server
+    env = {"ROBYN_DRAIN_TIMEOUT": "3000", "ROBYN_MAX_CONNECTIONS": "2"}
+    process, port = start_ephemeral_server("127.0.0.1", env)
+    yield process, port
+    kill_process(process)
+
+
 # create robyn.env before test and delete it after test
 @pytest.fixture
 def env_file():
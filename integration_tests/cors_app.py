@@ -0,0 +1,47 @@
+from robyn import ALLOW_CORS, Request, Response, Robyn
+
+app = Robyn(__file__)
+ALLOW_CORS(app, ["*"])
+
+
+@app.get("/items")
+def items():
+    return "items"
+
+
+@app.post("/items")
+def add_item(request: Request):
+    return request.body
+
+
+@app.delete("/items/:id")
+def delete_item():
+    return "deleted"
+
+
+@app.get("/items/:id", const=True)
+def item():
+    return "item"
+
+
+@app.get("/allowed_origin")
+def allowed_origin(request: Request):
+    # the headers added by ALLOW_CORS are added to the requests
+    return request.headers.get("Access-Control-Allow-Origin", "")
+
+
+@app.options("/custom")
+def custom_options():
+    return Response(200, {"custom": "options"}, "")
+
+
+# the preflight requests are answered before the middlewares, they carry no credentials
+@app.before_request()
+def reject_anonymous(request: Request):
+    if request.method != "OPTIONS" and "authorization" not in request.headers:
+        return Response(401, {}, "Unauthorized")
+    return request
+
+
+if __name__ == "__main__":
+    app.start()
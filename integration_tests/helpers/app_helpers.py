@@ -0,0 +1,23 @@
+from robyn import Robyn
+from robyn.processpool import init_server
+
+
+def build_server(app: Robyn):
+    """
+    Builds the server of the app in the process, as the processes of the app do, so it can be
+    tested with a TestClient without starting it.
+    """
+    return init_server(
+        app.directories,
+        app.request_headers,
+        app.router.get_routes(),
+        app.router.get_fallbacks(),
+        app.middleware_router.get_global_middlewares(),
+        app.middleware_router.get_route_middlewares(),
+        app.web_socket_router.get_routes(),
+        app.event_handlers,
+        app.response_headers,
+        app.default_response_headers,
+        app.error_pages,
+        app.mime_types,
+    )
@@ -11,3 +11,23 @@ def get_network_host():
         return ip_address
     else:
         return "0.0.0.0"
+
+
+def send_raw_request(data: bytes, host: str = "127.0.0.1", port: int = 8080) -> bytes:
+    """
+    Writes the raw bytes to a new connection and returns everything the server sends back
+    until it closes the connection or stops answering.
+    """
+    output = b""
+    with socket.create_connection((host, port), timeout=5) as sock:
+        sock.sendall(data)
+        sock.settimeout(2)
+        try:
+            while True:
+                chunk = sock.recv(65536)
+                if not chunk:
+                    break
+                output += chunk
+        except socket.timeout:
+            pass
+    return output
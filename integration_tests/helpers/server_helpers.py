@@ -0,0 +1,12 @@
+import pytest
+
+
+def with_ephemeral_server(name: str, **kwargs) -> pytest.MarkDecorator:
+    """
+    Gives the marked tests the ephemeral_server fixture, started with the keyword arguments
+    of start_ephemeral_server, e.g. env, app or args. The name is the id of the tests.
+    Each server is declared once in its test module, so that its tests share it.
+    """
+    return pytest.mark.parametrize(
+        "ephemeral_server", [kwargs], indirect=True, ids=[name]
+    )
@@ -0,0 +1,20 @@
+import re
+
+from robyn import Response, Robyn
+
+app = Robyn(__file__)
+
+
+@app.redact_logs
+def mask_tokens(message: str) -> str:
+    return re.sub(r"token=[^&\s]*", "token=***", message)
+
+
+@app.get("/login")
+def login():
+    headers = {"Set-Cookie": "session=cookie-secret", "X-Api-Key": "key-secret"}
+    return Response(200, headers, "logged in")
+
+
+if __name__ == "__main__":
+    app.start()
@@ -0,0 +1,24 @@
+from robyn import Request, Response, Robyn
+
+app = Robyn(__file__)
+
+
+@app.post("/body")
+def body(request: Request):
+    return request.body
+
+
+@app.get("/cached")
+def cached():
+    return Response(200, {"Cache-Control": "max-age=60"}, "cached")
+
+
+if __name__ == "__main__":
+    app.start(
+        server_config={
+            "max_payload_size": 16,
+            "server_header": "configured/1.0",
+            "default_headers": {"X-Version": "1.2", "Cache-Control": "no-cache"},
+            "favicon_fallback": False,
+        },
+    )
@@ -0,0 +1,17 @@
+from robyn import Robyn
+
+app = Robyn(__file__)
+
+
+@app.get("/")
+def index():
+    return "not served"
+
+
+async def startup_handler():
+    raise RuntimeError("the database is unreachable")
+
+
+if __name__ == "__main__":
+    app.startup_handler(startup_handler)
+    app.start(port=0)
@@ -59,3 +59,23 @@ def trace_foo(name):
 @sub_router.head("/foo")
 def head_foo(name):
     return jsonify({"message": "foo"})
+
+
+# This router is mounted under a prefix instead of having its own
+mounted_router = SubRouter(__name__)
+
+
+@mounted_router.get("/")
+def get_mounted_index():
+    return "mounted index"
+
+
+@mounted_router.get("/users/:id")
+def get_mounted_user(request):
+    return request.path_params["id"]
+
+
+@mounted_router.after_request("/users/:id")
+def mounted_after_request(response):
+    response.headers["mounted_after"] = "mounted_after_request"
+    return response
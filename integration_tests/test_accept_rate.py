@@ -0,0 +1,61 @@
+import socket
+import time
+
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+from test_metrics import get_sample
+
+accept_rate_server = with_ephemeral_server(
+    "accept_rate",
+    env={
+        "ROBYN_ACCEPT_RATE": "2",
+        "ROBYN_ACCEPT_BURST": "2",
+        "ROBYN_METRICS_PATH": "/metrics",
+    },
+)
+
+# the time taken to refill the bucket of 2 connections at 2 a second
+REFILL_TIME = 1
+
+
+@pytest.mark.benchmark
+@accept_rate_server
+def test_accepts_above_the_rate_wait(ephemeral_server):
+    time.sleep(REFILL_TIME)
+    url = f"http://127.0.0.1:{ephemeral_server.port}/sync/str"
+    start = time.time()
+    for _ in range(6):
+        # a new connection for each request
+        assert requests.get(url, headers={"Connection": "close"}).text == "sync str get"
+    # the first 2 are accepted at once, then one every half second
+    elapsed = time.time() - start
+    assert 1 < elapsed < 5, elapsed
+
+
+@pytest.mark.benchmark
+@accept_rate_server
+def test_throttled_accepts_metric(ephemeral_server):
+    time.sleep(REFILL_TIME)
+    url = f"http://127.0.0.1:{ephemeral_server.port}/metrics"
+    # the metrics are read on a connection accepted before the others
+    session = requests.Session()
+    assert get_sample(session.get(url).text, "robyn_throttled_accepts") == 0
+
+    connections = [
+        socket.create_connection(("127.0.0.1", ephemeral_server.port), timeout=5)
+        for _ in range(6)
+    ]
+    for connection in connections:
+        connection.sendall(b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n")
+    for connection in connections:
+        assert connection.recv(65536).startswith(b"HTTP/1.1 200 OK")
+        connection.close()
+    assert get_sample(session.get(url).text, "robyn_throttled_accepts") >= 1
+
+    # the accepts resume once the bucket is refilled
+    deadline = time.time() + 5
+    while get_sample(session.get(url).text, "robyn_throttled_accepts") != 0:
+        assert time.time() < deadline
+        time.sleep(0.1)
@@ -0,0 +1,64 @@
+import pytest
+
+from helpers.app_helpers import build_server
+from robyn import Robyn
+from robyn.robyn import HttpMethod, TestClient
+
+
+def hello():
+    return "hello"
+
+
+async def created():
+    return "created"
+
+
+def test_add_routes_from_a_table():
+    app = Robyn(__file__)
+    app.add_routes(
+        [
+            ("GET", "/hello", hello),
+            ("post", "/created", created),
+            (HttpMethod.PUT, "/users/:id", lambda request: request.path_params["id"]),
+        ]
+    )
+
+    client = TestClient(build_server(app))
+    assert client.get("/hello").body == "hello"
+    assert client.post("/created").body == "created"
+    assert client.put("/users/42").body == "42"
+
+
+def test_add_routes_with_an_unknown_method():
+    app = Robyn(__file__)
+    with pytest.raises(ValueError):
+        app.add_routes([("FETCH", "/hello", hello)])
+
+
+@pytest.mark.parametrize(
+    "const",
+    [False, True],
+)
+def test_duplicate_route_refused(const: bool):
+    app = Robyn(__file__)
+    app.get("/hello", const=const)(hello)
+    # a table with the route already registered by the decorator
+    app.add_routes([("GET", "/hello", lambda: "overwritten")])
+
+    with pytest.raises(ValueError, match="GET /hello"):
+        build_server(app)
+
+
+def test_duplicate_route_in_the_table_refused():
+    app = Robyn(__file__)
+    app.add_routes([("GET", "/hello", hello), ("POST", "/hello", created)])
+    client = TestClient(build_server(app))
+    assert client.get("/hello").body == "hello"
+
+    server = build_server(Robyn(__file__))
+    duplicated = Robyn(__file__)
+    duplicated.add_routes([("GET", "/hello", hello), ("GET", "/hello", created)])
+    with pytest.raises(ValueError):
+        server.add_routes(duplicated.router.get_routes())
+    # none of the routes of the table were added
+    assert TestClient(server).get("/hello").status_code == 404
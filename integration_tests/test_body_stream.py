@@ -0,0 +1,46 @@
+import gzip
+import time
+
+import pytest
+
+from helpers.http_methods_helpers import post
+
+
+@pytest.mark.benchmark
+def test_streamed_body(session):
+    r = post("/async/body/stream", data=b"robyn" * 1000)
+    assert r.json()["size"] == 5000
+    # the body isn't read before the handler is called
+    assert r.json()["body"] == ""
+
+
+@pytest.mark.benchmark
+def test_streamed_body_arrives_in_chunks(session):
+    def body():
+        for _ in range(5):
+            yield b"0" * 1000
+            time.sleep(0.1)
+
+    r = post("/async/body/stream", data=body())
+    assert r.json()["size"] == 5000
+    assert r.json()["chunks"] >= 5
+
+
+@pytest.mark.benchmark
+def test_streamed_body_above_max_payload_size(session):
+    # the streamed body isn't read in memory, so the max payload size doesn't apply
+    r = post("/async/body/stream", data=b"0" * 3_000_000)
+    assert r.json()["size"] == 3_000_000
+
+    r = post("/async/body", data=b"0" * 3_000_000, should_check_response=False)
+    assert r.status_code == 413
+
+
+@pytest.mark.benchmark
+def test_streamed_body_is_decompressed(session):
+    r = post(
+        "/async/body/stream",
+        data=gzip.compress(b"robyn" * 1000),
+        headers={"Content-Encoding": "gzip"},
+    )
+    assert r.json()["size"] == 5000
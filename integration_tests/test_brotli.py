@@ -0,0 +1,97 @@
+import pytest
+import requests
+
+from helpers.http_methods_helpers import get
+from helpers.server_helpers import with_ephemeral_server
+
+brotli_server = with_ephemeral_server("brotli", env={"ROBYN_BROTLI_QUALITY": "5"})
+
+LARGE_RESPONSE = "a large sync response " * 100
+
+
+def raw_get(port: int, path: str, accept_encoding: str) -> requests.Response:
+    # the body is read as sent, requests only decodes brotli when a brotli module is installed
+    r = requests.get(
+        f"http://127.0.0.1:{port}{path}",
+        headers={"Accept-Encoding": accept_encoding},
+        stream=True,
+    )
+    r.raw_body = r.raw.read(decode_content=False)
+    return r
+
+
+@pytest.mark.benchmark
+@brotli_server
+def test_brotli_response(ephemeral_server):
+    r = raw_get(ephemeral_server.port, "/sync/response/large", "gzip, br")
+    assert r.headers["Content-Encoding"] == "br"
+    assert r.headers["Vary"] == "Accept-Encoding"
+    assert len(r.raw_body) < len(LARGE_RESPONSE)
+
+    # the server decompresses the brotli request bodies, so it can check its response
+    r = requests.post(
+        f"http://127.0.0.1:{ephemeral_server.port}/sync/body",
+        data=r.raw_body,
+        headers={"Content-Encoding": "br", "Accept-Encoding": "identity"},
+    )
+    assert r.text == LARGE_RESPONSE
+
+
+@pytest.mark.benchmark
+@brotli_server
+def test_brotli_response_not_accepted(ephemeral_server):
+    r = raw_get(ephemeral_server.port, "/sync/response/large", "gzip, br;q=0")
+    assert "Content-Encoding" not in r.headers
+    # the response depends on the header all the same
+    assert r.headers["Vary"] == "Accept-Encoding"
+    assert r.raw_body.decode() == LARGE_RESPONSE
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "path",
+    [
+        # too small to gain from the compression
+        "/sync/str",
+        # not text
+        "/sync/response/large/octet",
+    ],
+)
+@brotli_server
+def test_brotli_uncompressed_responses(ephemeral_server, path: str):
+    r = raw_get(ephemeral_server.port, path, "br")
+    assert "Content-Encoding" not in r.headers
+    assert "Vary" not in r.headers
+
+
+@pytest.mark.benchmark
+def test_brotli_disabled_by_default(session):
+    r = get("/sync/response/large", headers={"Accept-Encoding": "br"})
+    assert "Content-Encoding" not in r.headers
+    assert r.text == LARGE_RESPONSE
+
+
+@pytest.mark.benchmark
+@brotli_server
+def test_brotli_response_etag(ephemeral_server, tmp_path):
+    path = tmp_path / "page.txt"
+    path.write_text(LARGE_RESPONSE)
+    url = f"/sync/file/response/sized?path={path}"
+
+    identity = raw_get(ephemeral_server.port, url, "identity")
+    compressed = raw_get(ephemeral_server.port, url, "br")
+    assert compressed.headers["Content-Encoding"] == "br"
+    # the compressed body is another variant, the caches mustn't mix up their tags
+    assert compressed.headers["ETag"] == identity.headers["ETag"][:-1] + '-br"'
+
+    # the 304 has the tag of the variant it stands for
+    r = requests.get(
+        f"http://127.0.0.1:{ephemeral_server.port}{url}",
+        headers={
+            "Accept-Encoding": "br",
+            "If-Modified-Since": compressed.headers["Last-Modified"],
+        },
+    )
+    assert r.status_code == 304
+    assert r.headers["ETag"] == compressed.headers["ETag"]
+    assert r.headers["Vary"] == "Accept-Encoding"
@@ -0,0 +1,70 @@
+import pytest
+import requests
+
+from helpers.http_methods_helpers import get
+from helpers.server_helpers import with_ephemeral_server
+
+static_max_age_server = with_ephemeral_server(
+    "static_max_age", env={"ROBYN_STATIC_MAX_AGE": "60"}
+)
+
+IMMUTABLE = "public, max-age=31536000, immutable"
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+def test_cache_control_of_the_route(function_type: str, session):
+    r = get(f"/{function_type}/cache_control")
+    assert r.headers["Cache-Control"] == "no-store"
+
+
+@pytest.mark.benchmark
+def test_cache_control_of_a_const_route(session):
+    r = get("/sync/cache_control/const")
+    assert r.headers["Cache-Control"] == IMMUTABLE
+
+
+@pytest.mark.benchmark
+def test_cache_control_set_by_the_handler(session):
+    r = get("/sync/cache_control/override")
+    assert r.headers["Cache-Control"] == "max-age=60"
+
+
+@pytest.mark.benchmark
+def test_error_responses_are_not_cached(session):
+    r = get("/sync/cache_control/error", expected_status_code=500)
+    assert "Cache-Control" not in r.headers
+
+
+@pytest.mark.benchmark
+def test_routes_without_cache_control(session):
+    r = get("/sync/str")
+    assert "Cache-Control" not in r.headers
+
+
+@pytest.mark.benchmark
+def test_cache_control_of_the_directory(session):
+    r = requests.get("http://127.0.0.1:8080/cached_site/index.html")
+    assert r.status_code == 200
+    assert r.headers["Cache-Control"] == IMMUTABLE
+    r = requests.get("http://127.0.0.1:8080/cached_site/missing.html")
+    assert r.status_code == 404
+    assert "Cache-Control" not in r.headers
+
+    # the other directories keep their files uncached
+    r = requests.get("http://127.0.0.1:8080/static_site/index.html")
+    assert "Cache-Control" not in r.headers
+
+
+@pytest.mark.benchmark
+@static_max_age_server
+def test_max_age_of_the_directories(ephemeral_server):
+    url = f"http://127.0.0.1:{ephemeral_server.port}"
+    r = requests.get(f"{url}/static_site/index.html")
+    assert r.headers["Cache-Control"] == "max-age=60"
+    # the Cache-Control of a directory wins over the max-age
+    r = requests.get(f"{url}/cached_site/index.html")
+    assert r.headers["Cache-Control"] == IMMUTABLE
+    # the routes don't get it
+    r = requests.get(f"{url}/sync/str")
+    assert "Cache-Control" not in r.headers
@@ -0,0 +1,56 @@
+import socket
+import struct
+import time
+
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+def wait_for_state(name: str) -> str:
+    # a sync handler holds its worker, so the state can only be read once it returns
+    for _ in range(50):
+        state = get(f"/sync/cancellation/state/{name}").text
+        if state != "running":
+            return state
+        time.sleep(0.1)
+    return "running"
+
+
+def disconnect_after(path: str, delay: float, read: bool = False):
+    with socket.create_connection(("127.0.0.1", 8080), timeout=5) as sock:
+        sock.sendall(f"GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n".encode())
+        if read:
+            assert sock.recv(4096).startswith(b"HTTP/1.1 200 OK")
+        time.sleep(delay)
+        # resets the connection as it is closed
+        sock.setsockopt(socket.SOL_SOCKET, socket.SO_LINGER, struct.pack("ii", 1, 0))
+
+
+@pytest.mark.benchmark
+def test_handler_cancelled_on_disconnect(session):
+    disconnect_after("/async/cancellation", 0.3)
+    assert wait_for_state("async") == "cancelled"
+
+
+@pytest.mark.benchmark
+def test_half_closed_connection_isnt_cancelled(session):
+    with socket.create_connection(("127.0.0.1", 8080), timeout=10) as sock:
+        sock.sendall(b"GET /async/cancellation HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        sock.shutdown(socket.SHUT_WR)
+        # the client still reads the response
+        assert sock.recv(4096).startswith(b"HTTP/1.1 200 OK")
+    assert wait_for_state("async") == "finished"
+
+
+@pytest.mark.benchmark
+def test_streamed_response_stops_on_disconnect(session):
+    disconnect_after("/sync/cancellation/stream", 0.3, read=True)
+    assert wait_for_state("stream") == "closed"
+
+
+@pytest.mark.benchmark
+def test_answered_request_isnt_cancelled(session):
+    assert get("/sync/cancellation/stored").text == "False"
+    # the connection of the request is closed, the token still tells it was answered
+    assert get("/sync/cancellation/stored/state").text == "False"
@@ -0,0 +1,54 @@
+import socket
+import time
+
+import pytest
+
+from helpers.server_helpers import with_ephemeral_server
+
+client_request_timeout_server = with_ephemeral_server(
+    "client_request_timeout", env={"ROBYN_CLIENT_REQUEST_TIMEOUT": "1500"}
+)
+
+
+def read_until_closed(sock: socket.socket) -> bytes:
+    response = b""
+    while True:
+        chunk = sock.recv(65536)
+        if not chunk:
+            return response
+        response += chunk
+
+
+@pytest.mark.benchmark
+@client_request_timeout_server
+def test_slow_request_head(ephemeral_server):
+    # the head is sent a byte at a time to hold the connection open
+    head = b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n"
+    with socket.create_connection(
+        ("127.0.0.1", ephemeral_server.port), timeout=5
+    ) as sock:
+        start = time.time()
+        try:
+            for byte in head:
+                sock.sendall(bytes([byte]))
+                time.sleep(0.1)
+        except OSError:
+            # the connection can be closed while the head is still sent
+            pass
+        response = read_until_closed(sock)
+        assert time.time() - start < 2.5
+    assert response.startswith(b"HTTP/1.1 408")
+
+
+@pytest.mark.benchmark
+@client_request_timeout_server
+def test_request_head_in_time(ephemeral_server):
+    with socket.create_connection(
+        ("127.0.0.1", ephemeral_server.port), timeout=5
+    ) as sock:
+        sock.sendall(b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\n")
+        time.sleep(0.2)
+        sock.sendall(b"Connection: close\r\n\r\n")
+        response = read_until_closed(sock)
+    assert response.startswith(b"HTTP/1.1 200 OK")
+    assert response.endswith(b"sync str get")
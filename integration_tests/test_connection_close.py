@@ -0,0 +1,38 @@
+import socket
+import time
+
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+@pytest.mark.benchmark
+def test_large_response_with_connection_close(session):
+    r = get("/sync/large_response", headers={"Connection": "close"})
+    assert r.headers["Connection"] == "close"
+    assert len(r.content) == 5_000_000
+    assert r.text == "a" * 5_000_000
+
+
+@pytest.mark.benchmark
+def test_large_response_to_a_slow_reader(session):
+    with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as sock:
+        # a small receive buffer makes the server's writes partial
+        sock.setsockopt(socket.SOL_SOCKET, socket.SO_RCVBUF, 4096)
+        sock.settimeout(10)
+        sock.connect(("127.0.0.1", 8080))
+        sock.sendall(
+            b"GET /sync/large_response HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+        )
+        response = b""
+        while True:
+            chunk = sock.recv(65536)
+            if not chunk:
+                break
+            response += chunk
+            if len(response) < 1_000_000:
+                time.sleep(0.001)
+
+    head, body = response.split(b"\r\n\r\n", 1)
+    assert head.startswith(b"HTTP/1.1 200 OK")
+    assert body == b"a" * 5_000_000
@@ -0,0 +1,55 @@
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "accept, content_type",
+    [
+        ("application/json", "application/json"),
+        ("text/html", "text/html"),
+        ("text/*", "text/html"),
+        ("application/json;q=0.5, text/html;q=0.9", "text/html"),
+        # the most specific range gives the quality of a type
+        ("text/*;q=1, text/html;q=0.1, application/*;q=0.5", "application/json"),
+        ("text/html;q=0, */*", "application/json"),
+        # the first type offered is the default
+        ("*/*", "application/json"),
+        ("application/json, text/html", "application/json"),
+        # a malformed header accepts every type
+        ("not a media range", "application/json"),
+    ],
+)
+def test_negotiation(accept: str, content_type: str, session):
+    r = get("/sync/negotiate", headers={"Accept": accept})
+    assert r.headers["Content-Type"] == content_type
+    assert r.headers["Vary"] == "Accept"
+    if content_type == "application/json":
+        assert r.json() == {"sync": "negotiate"}
+    else:
+        assert r.text == "<p>sync negotiate</p>"
+
+
+@pytest.mark.parametrize(
+    "accept, content_type",
+    [
+        ("*/*", "text/html"),
+        ("application/json, text/html", "text/html"),
+        ("application/json, text/html;q=0.9", "application/json"),
+    ],
+)
+def test_negotiation_default(accept: str, content_type: str, session):
+    r = get("/sync/negotiate?default=text/html", headers={"Accept": accept})
+    assert r.headers["Content-Type"] == content_type
+
+
+@pytest.mark.parametrize("accept", ["image/png", "text/html;q=0, application/json;q=0"])
+def test_not_acceptable(accept: str, session):
+    r = get("/sync/negotiate", expected_status_code=406, headers={"Accept": accept})
+    assert r.headers["Vary"] == "Accept"
+    assert r.text == "Not Acceptable, the media types are application/json, text/html"
+
+
+def test_unknown_default(session):
+    get("/sync/negotiate?default=image/png", expected_status_code=500)
@@ -0,0 +1,79 @@
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+
+cors_server = with_ephemeral_server("cors", app="cors_app.py")
+
+
+def preflight(port: int, path: str, method: str = "POST") -> requests.Response:
+    return requests.options(
+        f"http://127.0.0.1:{port}{path}",
+        headers={
+            "Origin": "http://example.com",
+            "Access-Control-Request-Method": method,
+            "Access-Control-Request-Headers": "Content-Type",
+        },
+    )
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "path,allowed_methods",
+    [
+        ("/items", "GET, POST"),
+        # the const routes are included
+        ("/items/42", "GET, DELETE"),
+    ],
+)
+@cors_server
+def test_cors_preflight(ephemeral_server, path: str, allowed_methods: str):
+    r = preflight(ephemeral_server.port, path)
+    assert r.status_code == 204
+    assert r.headers["Access-Control-Allow-Methods"] == allowed_methods
+    assert r.headers["Access-Control-Allow-Origin"] == "*"
+    assert r.headers["Access-Control-Allow-Headers"] == "Content-Type, Authorization"
+    assert r.text == ""
+
+
+@pytest.mark.benchmark
+@cors_server
+def test_cors_preflight_of_unknown_path(ephemeral_server):
+    r = preflight(ephemeral_server.port, "/unknown")
+    assert r.status_code == 404
+
+
+@pytest.mark.benchmark
+@cors_server
+def test_options_request_without_preflight(ephemeral_server):
+    # without Access-Control-Request-Method, it isn't a preflight request
+    r = requests.options(
+        f"http://127.0.0.1:{ephemeral_server.port}/items",
+        headers={"Origin": "http://example.com"},
+    )
+    assert r.status_code == 404
+
+
+@pytest.mark.benchmark
+@cors_server
+def test_cors_preflight_with_options_route(ephemeral_server):
+    r = preflight(ephemeral_server.port, "/custom")
+    assert r.status_code == 200
+    assert r.headers["custom"] == "options"
+
+
+@pytest.mark.benchmark
+def test_cors_preflight_without_cors(session):
+    r = preflight(8080, "/sync/str")
+    assert r.status_code == 404
+
+
+@pytest.mark.benchmark
+@cors_server
+def test_cors_request_headers(ephemeral_server):
+    r = requests.get(
+        f"http://127.0.0.1:{ephemeral_server.port}/allowed_origin",
+        headers={"Authorization": "Bearer robyn"},
+    )
+    assert r.status_code == 200
+    assert r.text == "*"
@@ -0,0 +1,115 @@
+import os
+
+import pytest
+import requests
+
+BASE_URL = "http://127.0.0.1:8080"
+STATIC_SITE = os.path.join(os.path.dirname(os.path.abspath(__file__)), "static_site")
+
+
+@pytest.mark.benchmark
+def test_index_file_of_the_directory(session):
+    res = requests.get(f"{BASE_URL}/static_site/")
+    assert res.status_code == 200
+    assert "Static site" in res.text
+
+
+@pytest.mark.benchmark
+def test_index_file_fallback(session):
+    res = requests.get(f"{BASE_URL}/static_site/docs/")
+    assert res.status_code == 200
+    assert "Docs" in res.text
+
+
+@pytest.mark.benchmark
+def test_directory_without_trailing_slash_is_redirected(session):
+    res = requests.get(f"{BASE_URL}/static_site/docs", allow_redirects=False)
+    assert res.status_code == 301
+    assert res.headers["Location"] == "/static_site/docs/"
+
+    res = requests.get(f"{BASE_URL}/static_site/docs?page=1", allow_redirects=False)
+    assert res.status_code == 301
+    assert res.headers["Location"] == "/static_site/docs/?page=1"
+
+
+@pytest.mark.benchmark
+def test_directory_without_index_file(session):
+    res = requests.get(f"{BASE_URL}/static_site/empty/")
+    assert res.status_code == 404
+
+    res = requests.get(f"{BASE_URL}/static_site_forbidden/empty/")
+    assert res.status_code == 403
+
+    # on this route, only index.html is an index file
+    res = requests.get(f"{BASE_URL}/static_site_forbidden/docs/")
+    assert res.status_code == 403
+
+
+@pytest.mark.benchmark
+def test_files_of_the_directory(session):
+    res = requests.get(f"{BASE_URL}/static_site/empty/page.txt")
+    assert res.status_code == 200
+    assert res.text == "A page\n"
+
+
+@pytest.mark.benchmark
+def test_files_listing(session):
+    res = requests.get(f"{BASE_URL}/listed_site/")
+    assert res.status_code == 200
+    assert res.headers["Content-Type"] == "text/html; charset=utf-8"
+    assert "<title>Index of /listed_site/</title>" in res.text
+    assert '<a href="docs/">docs/</a>' in res.text
+    assert '<a href="index.html">index.html</a>' in res.text
+    # the root of the route has no parent link
+    assert '<a href="../">' not in res.text
+
+    res = requests.get(f"{BASE_URL}/listed_site/empty/")
+    assert res.status_code == 200
+    assert '<a href="../">../</a>' in res.text
+    assert '<a href="page.txt">page.txt</a></td><td>7</td>' in res.text
+    assert ".hidden" not in res.text
+
+
+@pytest.mark.benchmark
+def test_hidden_files(session):
+    # the paths with a hidden segment are refused
+    res = requests.get(f"{BASE_URL}/listed_site/empty/.hidden")
+    assert res.status_code == 400
+
+    res = requests.get(f"{BASE_URL}/listed_site_hidden/empty/")
+    assert '<a href=".hidden">.hidden</a>' in res.text
+    res = requests.get(f"{BASE_URL}/listed_site_hidden/empty/.hidden")
+    assert res.status_code == 200
+    assert res.text == "hidden\n"
+
+
+@pytest.mark.benchmark
+def test_files_listing_escapes_the_names(session):
+    path = os.path.join(STATIC_SITE, "empty", "<script>'&\".txt")
+    with open(path, "w"):
+        pass
+    try:
+        res = requests.get(f"{BASE_URL}/listed_site/empty/")
+        assert (
+            '<a href="%3Cscript%3E%27%26%22.txt">&lt;script&gt;&#x27;&amp;&quot;.txt</a>'
+            in res.text
+        )
+        assert "<script>" not in res.text
+    finally:
+        os.remove(path)
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "extension,content_type",
+    [
+        # registered with add_mime_type
+        ("robyn", "text/x-robyn"),
+        ("txt", "text/plain; charset=utf-8"),
+        ("wasm", "application/wasm"),
+    ],
+)
+def test_directory_mime_types(extension: str, content_type: str, session):
+    res = requests.get(f"{BASE_URL}/downloads/test.{extension}")
+    assert res.status_code == 200
+    assert res.headers["Content-Type"] == content_type
@@ -0,0 +1,115 @@
+import signal
+import socket
+import time
+
+import pytest
+
+DRAIN_TIMEOUT = 3
+
+
+def request(path: str) -> bytes:
+    return f"GET {path} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n".encode()
+
+
+def read_response(sock: socket.socket, body: bytes) -> bytes:
+    response = b""
+    while body not in response:
+        chunk = sock.recv(65536)
+        if not chunk:
+            break
+        response += chunk
+    return response
+
+
+@pytest.mark.benchmark
+def test_in_flight_request_answered_with_connection_close(drain_server):
+    process, port = drain_server
+    with socket.create_connection(("127.0.0.1", port), timeout=5) as sock:
+        sock.sendall(request("/sync/str"))
+        response = read_response(sock, b"sync str get")
+        assert response.startswith(b"HTTP/1.1 200 OK")
+        assert b"connection: close" not in response.lower()
+
+        sock.sendall(request("/async/slow"))
+        time.sleep(0.3)
+        terminated = time.time()
+        process.send_signal(signal.SIGTERM)
+
+        response = read_response(sock, b"slow")
+        assert response.startswith(b"HTTP/1.1 200 OK")
+        assert b"connection: close" in response.lower()
+        # the connection is closed once the request in flight is answered
+        assert sock.recv(65536) == b""
+
+    # no new connection is served while the open ones are drained
+    with pytest.raises((socket.timeout, ConnectionError)):
+        with socket.create_connection(("127.0.0.1", port), timeout=1) as sock:
+            sock.sendall(request("/sync/str"))
+            if sock.recv(65536) == b"":
+                raise ConnectionResetError
+
+    # the server exits as soon as its connections are drained
+    process.wait(timeout=DRAIN_TIMEOUT + 5)
+    assert time.time() - terminated < DRAIN_TIMEOUT
+
+
+@pytest.mark.benchmark
+def test_handler_stops_for_the_shutdown(drain_server):
+    process, port = drain_server
+    with socket.create_connection(("127.0.0.1", port), timeout=5) as sock:
+        sock.sendall(request("/async/cancellation/shutdown"))
+        time.sleep(0.3)
+        terminated = time.time()
+        process.send_signal(signal.SIGTERM)
+
+        response = read_response(sock, b"stopped for the shutdown")
+        assert response.startswith(b"HTTP/1.1 200 OK")
+        assert b"connection: close" in response.lower()
+
+    # the handler polling its token doesn't hold the shutdown up until the drain timeout
+    process.wait(timeout=DRAIN_TIMEOUT + 5)
+    assert time.time() - terminated < DRAIN_TIMEOUT - 1
+
+
+@pytest.mark.benchmark
+def test_idle_connection_closed_after_drain_timeout(drain_server):
+    process, port = drain_server
+    with socket.create_connection(("127.0.0.1", port), timeout=10) as sock:
+        sock.sendall(request("/sync/str"))
+        assert read_response(sock, b"sync str get").startswith(b"HTTP/1.1 200 OK")
+
+        terminated = time.time()
+        process.send_signal(signal.SIGTERM)
+        # the idle connection is kept alive for longer than the drain timeout
+        assert sock.recv(65536) == b""
+        assert DRAIN_TIMEOUT - 1 < time.time() - terminated < DRAIN_TIMEOUT + 2
+
+    process.wait(timeout=5)
+
+
+@pytest.mark.benchmark
+def test_connections_above_the_limit_told_to_retry_after_the_drain(drain_server):
+    process, port = drain_server
+    # let the server drop the connection used to check it was up
+    time.sleep(0.5)
+    # the server is limited to 2 open connections
+    connections = [
+        socket.create_connection(("127.0.0.1", port), timeout=5) for _ in range(3)
+    ]
+    try:
+        connections[0].sendall(request("/async/slow"))
+        time.sleep(0.3)
+        process.send_signal(signal.SIGTERM)
+        time.sleep(0.3)
+
+        connections[2].sendall(request("/sync/str"))
+        response = read_response(connections[2], b"Service unavailable")
+        assert response.startswith(b"HTTP/1.1 503 Service Unavailable")
+        # the clients wait longer than for a busy server, the process is going away
+        assert b"retry-after: 10\r\n" in response.lower()
+
+        assert read_response(connections[0], b"slow").startswith(b"HTTP/1.1 200 OK")
+    finally:
+        for connection in connections:
+            connection.close()
+    process.wait(timeout=DRAIN_TIMEOUT + 5)
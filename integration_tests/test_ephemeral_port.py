@@ -0,0 +1,15 @@
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+
+ephemeral_port_server = with_ephemeral_server("ephemeral")
+
+
+@ephemeral_port_server
+def test_server_on_ephemeral_port(ephemeral_server):
+    port = ephemeral_server.port
+    assert port != 0
+
+    r = requests.get(f"http://127.0.0.1:{port}/sync/str")
+    assert r.status_code == 200
+    assert r.text == "sync str get"
@@ -0,0 +1,14 @@
+import os
+import pathlib
+import subprocess
+
+
+def test_startup_handler_error_aborts_the_start():
+    app = pathlib.Path(__file__).parent / "startup_error_app.py"
+    env = dict(os.environ, ROBYN_URL="127.0.0.1", ROBYN_PORT="0")
+    # the process exits instead of serving the requests
+    result = subprocess.run(
+        ["python3", str(app)], env=env, capture_output=True, timeout=10
+    )
+    assert b"Error while executing the startup handler" in result.stderr
+    assert b"the database is unreachable" in result.stderr
@@ -22,3 +22,4 @@ def test_exception_handling(
 ):
     r = method(route, expected_status_code=500)
     assert r.text == "error msg: value error"
+    assert r.headers["X-Error-Path"] == route
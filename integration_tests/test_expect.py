@@ -0,0 +1,41 @@
+import time
+
+import pytest
+import requests
+
+from helpers.network_helpers import send_raw_request
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("expect", ["gzip", "200-ok", "100-continue, gzip"])
+def test_unsupported_expectation(expect: str, session):
+    r = requests.post(
+        "http://127.0.0.1:8080/sync/body", data="robyn", headers={"Expect": expect}
+    )
+    assert r.status_code == 417
+    assert r.text == "Expectation failed"
+
+
+@pytest.mark.benchmark
+def test_unsupported_expectation_closes_the_connection(session):
+    # the client waits for an answer before sending the body, the server doesn't wait for it
+    start = time.time()
+    response = send_raw_request(
+        b"POST /sync/body HTTP/1.1\r\nHost: 127.0.0.1\r\nExpect: gzip\r\n"
+        + b"Content-Length: 5\r\n\r\n"
+    )
+    assert response.startswith(b"HTTP/1.1 417 Expectation Failed")
+    assert b"connection: close" in response.lower()
+    # the connection was closed by the server, before the read timed out
+    assert time.time() - start < 1
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("expect", [b"100-continue", b"100-Continue"])
+def test_continue_expectation(expect: bytes, session):
+    response = send_raw_request(
+        b"POST /sync/body HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n"
+        + b"Expect: " + expect + b"\r\nContent-Length: 5\r\n\r\nrobyn"
+    )
+    assert response.startswith(b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK")
+    assert response.endswith(b"\r\n\r\nrobyn")
@@ -0,0 +1,58 @@
+import os
+
+import pytest
+
+from base_routes import app
+from helpers.http_methods_helpers import get, post
+
+SPA_INDEX = os.path.join(os.path.dirname(__file__), "build", "index.html")
+
+
+@pytest.mark.benchmark
+def test_sync_fallback(session):
+    r = get("/sync/fallback/users/42")
+    assert r.text == "sync fallback /sync/fallback/users/42"
+
+
+@pytest.mark.benchmark
+def test_fallback_chain(session):
+    # the first fallback leaves the request to the second one
+    r = get("/async/fallback/users/42", expected_status_code=202)
+    assert r.headers["async"] == "fallback"
+    assert r.text == "async fallback /async/fallback/users/42"
+
+
+@pytest.mark.benchmark
+def test_fallbacks_leave_the_request_to_the_404(session):
+    get("/not_a_fallback/users/42", expected_status_code=404)
+
+
+@pytest.mark.benchmark
+def test_fallback_error(session):
+    get("/sync/fallback/error", expected_status_code=500)
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("path", ["/spa", "/spa/", "/spa/users/42"])
+def test_spa_fallback(session, path: str):
+    r = get(path)
+    assert r.headers["Content-Type"] == "text/html"
+    with open(SPA_INDEX) as index_file:
+        assert r.text == index_file.read()
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("path", ["/spa/assets/app.js", "/spam/users/42"])
+def test_spa_fallback_leaves_the_files_and_other_paths(session, path: str):
+    get(path, expected_status_code=404)
+
+
+@pytest.mark.benchmark
+def test_spa_fallback_only_serves_get_requests(session):
+    post("/spa/users/42", expected_status_code=404)
+
+
+def test_fallback_test_client():
+    res = app.test_client().get("/sync/fallback/users/42")
+    assert res.status_code == 200
+    assert res.body == "sync fallback /sync/fallback/users/42"
@@ -0,0 +1,40 @@
+import os
+
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+
+FAVICON = os.path.join(os.path.dirname(os.path.abspath(__file__)), "favicon.ico")
+favicon_server = with_ephemeral_server("favicon", env={"ROBYN_FAVICON": FAVICON})
+server_config_server = with_ephemeral_server(
+    "server_config", env={"ROBYN_MAX_PAYLOAD_SIZE": "4"}, app="server_config_app.py"
+)
+
+
+@pytest.mark.benchmark
+def test_favicon_without_icon(session):
+    r = requests.get("http://127.0.0.1:8080/favicon.ico")
+    assert r.status_code == 204
+    assert r.content == b""
+
+    # only the icon of the site is answered without a 404
+    r = requests.get("http://127.0.0.1:8080/images/favicon.ico")
+    assert r.status_code == 404
+
+
+@pytest.mark.benchmark
+@favicon_server
+def test_favicon_served_from_the_file(ephemeral_server):
+    r = requests.get(f"http://127.0.0.1:{ephemeral_server.port}/favicon.ico")
+    assert r.status_code == 200
+    assert r.headers["Content-Type"] == "image/x-icon"
+    with open(FAVICON, "rb") as favicon:
+        assert r.content == favicon.read()
+
+
+@pytest.mark.benchmark
+@server_config_server
+def test_favicon_fallback_disabled(ephemeral_server):
+    r = requests.get(f"http://127.0.0.1:{ephemeral_server.port}/favicon.ico")
+    assert r.status_code == 404
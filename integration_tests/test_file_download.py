@@ -8,3 +8,14 @@ def test_file_download(function_type: str, session):
     r = get(f"/{function_type}/file/download")
     assert r.headers["Content-Disposition"] == "attachment"
     assert r.text == "This is a test file for the downloading purpose"
+
+
+@pytest.mark.benchmark
+def test_binary_file_download(session, tmp_path):
+    # below the response buffer size, so the file is read in memory
+    content = bytes(range(256)) * 4
+    path = tmp_path / "download.bin"
+    path.write_bytes(content)
+    r = get(f"/sync/file/download/sized?path={path}")
+    assert r.headers["Content-Disposition"] == "attachment"
+    assert r.content == content
@@ -0,0 +1,104 @@
+import os
+
+import pytest
+
+from helpers.http_methods_helpers import get
+from helpers.network_helpers import send_raw_request
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+def test_file_response(function_type: str, session):
+    r = get(f"/{function_type}/file/response")
+    assert "Hello world. How are you?" in r.text
+    assert r.headers["Content-Type"] == "text/html"
+    assert r.headers["Content-Length"] == str(len(r.content))
+    assert r.headers["ETag"].startswith('"')
+    assert r.headers["Last-Modified"].endswith(" GMT")
+
+
+@pytest.mark.benchmark
+def test_missing_file_response(session):
+    r = get("/sync/file/response/missing", expected_status_code=404)
+    assert r.text == "Not found"
+
+
+@pytest.mark.benchmark
+def test_precompressed_file_response(session):
+    r = get("/sync/file/response/precompressed", headers={"Accept-Encoding": "gzip"})
+    assert r.headers["Content-Encoding"] == "gzip"
+    assert r.headers["Vary"] == "Accept-Encoding"
+    # the headers describe the uncompressed file
+    assert r.headers["Content-Type"] == "text/html"
+    assert r.text == "<h1>Hello from a precompressed page</h1>\n"
+    gzip_etag = r.headers["ETag"]
+
+    r = get(
+        "/sync/file/response/precompressed",
+        headers={"Accept-Encoding": "gzip;q=0, identity"},
+    )
+    assert "Content-Encoding" not in r.headers
+    assert r.headers["Vary"] == "Accept-Encoding"
+    assert r.text == "<h1>Hello from a precompressed page</h1>\n"
+    # the tag of the compressed variant has its encoding, so caches can't mix them up
+    etag = r.headers["ETag"]
+    assert gzip_etag == etag[:-1] + '-gzip"'
+
+
+@pytest.mark.benchmark
+def test_precompressed_file_not_modified(session):
+    r = get("/sync/file/response/precompressed", headers={"Accept-Encoding": "br"})
+    assert r.headers["ETag"].endswith('-br"')
+
+    # the 304 has the tag of the variant the client has
+    r = get(
+        "/sync/file/response/precompressed",
+        headers={
+            "Accept-Encoding": "br",
+            "If-Modified-Since": r.headers["Last-Modified"],
+        },
+        expected_status_code=304,
+    )
+    assert r.headers["ETag"].endswith('-br"')
+    assert "Content-Encoding" not in r.headers
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("accept_encoding", ["br", "gzip, br", "gzip;q=1.0, br;q=0.5"])
+def test_brotli_precompressed_file_response(session, accept_encoding: str):
+    response = send_raw_request(
+        b"GET /sync/file/response/precompressed HTTP/1.1\r\nHost: localhost\r\n"
+        b"Accept-Encoding: %s\r\nConnection: close\r\n\r\n" % accept_encoding.encode()
+    )
+    head, body = response.split(b"\r\n\r\n", 1)
+    # brotli is preferred whenever the client accepts it
+    assert b"\r\ncontent-encoding: br\r\n" in head
+    assert b"\r\nvary: Accept-Encoding\r\n" in head
+    assert b'-br"\r\n' in head
+    with open(
+        os.path.join(os.path.dirname(__file__), "precompressed", "page.html.br"), "rb"
+    ) as compressed:
+        assert body == compressed.read()
+
+
+@pytest.mark.benchmark
+def test_file_response_without_precompressed_variant(session):
+    r = get("/sync/file/response", headers={"Accept-Encoding": "gzip"})
+    assert "Content-Encoding" not in r.headers
+    assert "Vary" not in r.headers
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "extension,content_type",
+    [
+        # registered with add_mime_type
+        ("robyn", "text/x-robyn"),
+        ("txt", "text/plain; charset=utf-8"),
+        ("unknownext", "application/octet-stream"),
+        ("wasm", "application/wasm"),
+    ],
+)
+def test_file_response_mime_types(extension: str, content_type: str, session):
+    r = get(f"/sync/file/response/mime/{extension}")
+    assert r.headers["Content-Type"] == content_type
@@ -0,0 +1,110 @@
+import os
+import re
+import time
+
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+
+file_breaker_server = with_ephemeral_server(
+    "file_breaker",
+    env={"ROBYN_FILE_BREAKER_THRESHOLD": "3", "ROBYN_FILE_BREAKER_COOLDOWN": "1000"},
+)
+
+THRESHOLD = 3
+COOLDOWN = 1
+
+current_file_path = os.path.dirname(os.path.abspath(__file__))
+# a directory can't be read as a file, like the files of a failing disk
+BROKEN_FILE = current_file_path
+FILE = os.path.join(current_file_path, "downloads", "test.txt")
+MISSING_FILE = os.path.join(current_file_path, "missing.html")
+
+
+def url(port: int, route: str, path: str) -> str:
+    # the query isn't percent-decoded by the server, the path is given as it is
+    return f"http://127.0.0.1:{port}{route}?path={path}"
+
+
+def get_file(port: int, path: str) -> requests.Response:
+    return requests.get(url(port, "/sync/file/response/sized", path))
+
+
+def get_sample(port: int, name: str) -> float:
+    metrics = requests.get(f"http://127.0.0.1:{port}/metrics").text
+    match = re.search(rf"^{re.escape(name)} (\S+)$", metrics, re.MULTILINE)
+    assert match is not None, f"{name} is missing"
+    return float(match.group(1))
+
+
+def trip(port: int):
+    for _ in range(THRESHOLD):
+        assert get_file(port, BROKEN_FILE).status_code == 404
+
+
+@pytest.mark.benchmark
+@file_breaker_server
+def test_breaker_opens_after_consecutive_failures(ephemeral_server):
+    port = ephemeral_server.port
+    errors = get_sample(port, "robyn_file_store_errors_total")
+    trips = get_sample(port, "robyn_file_store_breaker_trips_total")
+
+    # a successful read resets the failures in a row
+    assert get_file(port, BROKEN_FILE).status_code == 404
+    assert get_file(port, FILE).status_code == 200
+    trip(port)
+
+    # the store isn't read while the breaker is open
+    r = get_file(port, FILE)
+    assert r.status_code == 503
+    assert r.headers["Retry-After"] == "1"
+    r = requests.get(url(port, "/sync/file/download/sized", FILE))
+    assert r.status_code == 503
+    assert requests.get(f"http://127.0.0.1:{port}/test_dir/").status_code == 503
+    # the routes that don't read files are still served
+    assert requests.get(f"http://127.0.0.1:{port}/sync/str").status_code == 200
+
+    assert get_sample(port, "robyn_file_store_errors_total") == errors + THRESHOLD + 1
+    assert get_sample(port, "robyn_file_store_breaker_trips_total") == trips + 1
+    assert get_sample(port, "robyn_file_store_breaker_state") == 1
+
+    # once the cooldown is over a read probes the store, which closes the breaker
+    time.sleep(COOLDOWN + 0.1)
+    assert get_sample(port, "robyn_file_store_breaker_state") == 2
+    r = get_file(port, FILE)
+    assert r.status_code == 200
+    assert r.text == "This is a test file for the downloading purpose"
+    assert get_sample(port, "robyn_file_store_breaker_state") == 0
+    assert requests.get(f"http://127.0.0.1:{port}/test_dir/").status_code == 200
+
+
+@file_breaker_server
+def test_failed_probe_reopens_breaker(ephemeral_server):
+    port = ephemeral_server.port
+    trip(port)
+    assert get_file(port, FILE).status_code == 503
+
+    time.sleep(COOLDOWN + 0.1)
+    assert get_file(port, BROKEN_FILE).status_code == 404
+    assert get_file(port, FILE).status_code == 503
+    assert get_sample(port, "robyn_file_store_breaker_state") == 1
+
+    time.sleep(COOLDOWN + 0.1)
+    assert get_file(port, FILE).status_code == 200
+
+
+@file_breaker_server
+def test_missing_file_is_neither_failure_nor_success(ephemeral_server):
+    port = ephemeral_server.port
+    # the failures in a row aren't reset by a missing file
+    for path in [BROKEN_FILE, MISSING_FILE, BROKEN_FILE, BROKEN_FILE]:
+        assert get_file(port, path).status_code == 404
+    assert get_file(port, FILE).status_code == 503
+
+    # nor does it close the breaker as a probe, the next read probes the store again
+    time.sleep(COOLDOWN + 0.1)
+    assert get_file(port, MISSING_FILE).status_code == 404
+    assert get_sample(port, "robyn_file_store_breaker_state") == 2
+    assert get_file(port, FILE).status_code == 200
+    assert get_sample(port, "robyn_file_store_breaker_state") == 0
@@ -0,0 +1,93 @@
+import hashlib
+import os
+
+import pytest
+import requests
+
+from helpers.http_methods_helpers import BASE_URL
+
+BOUNDARY = "robyn-boundary"
+
+
+def multipart_body(content: bytes) -> bytes:
+    return (
+        f"--{BOUNDARY}\r\n"
+        'Content-Disposition: form-data; name="name"\r\n\r\n'
+        "robyn\r\n"
+        f"--{BOUNDARY}\r\n"
+        'Content-Disposition: form-data; name="upload"; filename="data.bin"\r\n'
+        "Content-Type: application/octet-stream\r\n\r\n"
+    ).encode() + content + f"\r\n--{BOUNDARY}--\r\n".encode()
+
+
+def post_form(endpoint: str, data) -> requests.Response:
+    return requests.post(
+        f"{BASE_URL}/{endpoint}",
+        data=data,
+        headers={"Content-Type": f"multipart/form-data; boundary={BOUNDARY}"},
+    )
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+def test_form_files(session, function_type: str):
+    r = requests.post(
+        f"{BASE_URL}/{function_type}/form/files",
+        data={"name": "robyn"},
+        files={"upload": ("hello.txt", b"hello robyn", "text/plain")},
+    )
+    assert r.status_code == 200
+    form = r.json()
+    assert form["fields"] == {"name": "robyn"}
+    assert form["files"]["upload"]["filename"] == "hello.txt"
+    assert form["files"]["upload"]["content_type"] == "text/plain"
+    assert form["files"]["upload"]["size"] == 11
+    assert (
+        form["files"]["upload"]["sha256"]
+        == hashlib.sha256(b"hello robyn").hexdigest()
+    )
+    # the form isn't read in memory
+    assert form["body"] == ""
+    # the temporary file is removed once the request is answered
+    assert not os.path.exists(form["files"]["upload"]["path"])
+
+
+@pytest.mark.benchmark
+def test_form_files_across_chunks(session):
+    # the content looks like a boundary, and the chunks split the boundaries
+    content = (b"\r\n--robyn" + bytes(range(256))) * 2000
+    body = multipart_body(content)
+
+    def chunks():
+        for start in range(0, len(body), 1000):
+            yield body[start : start + 1000]
+
+    r = post_form("async/form/files", chunks())
+    assert r.status_code == 200
+    form = r.json()
+    assert form["fields"] == {"name": "robyn"}
+    assert form["files"]["upload"]["size"] == len(content)
+    assert form["files"]["upload"]["sha256"] == hashlib.sha256(content).hexdigest()
+
+
+@pytest.mark.benchmark
+def test_form_files_above_max_payload_size(session):
+    r = post_form("async/form/files", multipart_body(b"0" * 3_000_000))
+    assert r.status_code == 413
+
+
+@pytest.mark.benchmark
+def test_malformed_form(session):
+    r = post_form("async/form/files", multipart_body(b"robyn")[:-10])
+    assert r.status_code == 400
+
+    r = post_form("async/form/files", b"--robyn-boundary\r\nno headers end")
+    assert r.status_code == 400
+
+
+@pytest.mark.benchmark
+def test_form_files_route_without_form(session):
+    # the bodies that aren't multipart forms are read as usual
+    r = requests.post(f"{BASE_URL}/async/form/files", data=b"robyn")
+    assert r.status_code == 200
+    assert r.json() == {"fields": {}, "files": {}, "body": "robyn"}
@@ -0,0 +1,16 @@
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "headers",
+    [
+        {"X-Forwarded-Proto": "https"},
+        {"Forwarded": "for=192.0.2.60;proto=https;by=203.0.113.43"},
+    ],
+)
+def test_forwarded_scheme_is_ignored_by_default(headers: dict, session):
+    r = get("/sync/http/param", headers=headers)
+    assert r.json()["url"]["scheme"] == "http"
@@ -0,0 +1,100 @@
+import json
+
+import pytest
+import requests
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+
+allowed_hosts_server = with_ephemeral_server(
+    "allowed_hosts", env={"ROBYN_ALLOWED_HOSTS": "localhost, Example.com"}
+)
+refuse_absolute_form_server = with_ephemeral_server(
+    "refuse_absolute_form", env={"ROBYN_REFUSE_ABSOLUTE_FORM": "true"}
+)
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("host", ["localhost", "example.com", "EXAMPLE.com:8080"])
+@allowed_hosts_server
+def test_allowed_host(host: str, ephemeral_server):
+    r = requests.get(
+        f"http://127.0.0.1:{ephemeral_server.port}/sync/str", headers={"Host": host}
+    )
+    assert r.status_code == 200
+    assert r.text == "sync str get"
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("host", ["127.0.0.1", "evil.com", "sub.example.com"])
+@allowed_hosts_server
+def test_unknown_host(host: str, ephemeral_server):
+    r = requests.get(
+        f"http://127.0.0.1:{ephemeral_server.port}/sync/str", headers={"Host": host}
+    )
+    assert r.status_code == 421
+    assert r.text == "Misdirected request"
+
+
+@pytest.mark.benchmark
+@allowed_hosts_server
+def test_unknown_host_of_absolute_uri(ephemeral_server):
+    # the host of an absolute URI takes precedence over the header
+    response = send_raw_request(
+        b"GET http://evil.com/sync/str HTTP/1.1\r\nHost: localhost\r\n"
+        + b"Connection: close\r\n\r\n",
+        port=ephemeral_server.port,
+    )
+    assert response.startswith(b"HTTP/1.1 421 Misdirected Request")
+
+
+@pytest.mark.benchmark
+def test_absolute_uri_is_served_on_its_host(session):
+    response = send_raw_request(
+        b"GET http://Example.com:8080/sync/http/param?a=1 HTTP/1.1\r\n"
+        + b"Host: localhost\r\nConnection: close\r\n\r\n"
+    )
+    assert response.startswith(b"HTTP/1.1 200 OK")
+    body = json.loads(response.split(b"\r\n\r\n", 1)[1])
+    assert body["url"]["host"] == "Example.com:8080"
+    assert body["url"]["path"] == "/sync/http/param"
+
+
+@pytest.mark.benchmark
+@refuse_absolute_form_server
+def test_absolute_uri_refused(ephemeral_server):
+    port = ephemeral_server.port
+    response = send_raw_request(
+        b"GET http://localhost/sync/str HTTP/1.1\r\nHost: localhost\r\n"
+        + b"Connection: close\r\n\r\n",
+        port=port,
+    )
+    assert response.startswith(b"HTTP/1.1 400 Bad Request")
+
+    response = send_raw_request(
+        b"GET /sync/str HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        port=port,
+    )
+    assert response.startswith(b"HTTP/1.1 200 OK")
+
+
+@pytest.mark.benchmark
+@allowed_hosts_server
+def test_missing_host_of_http_1_0(ephemeral_server, session):
+    request = b"GET /sync/str HTTP/1.0\r\n\r\n"
+    assert send_raw_request(request).startswith(b"HTTP/1.0 200 OK")
+    response = send_raw_request(request, port=ephemeral_server.port)
+    assert response.startswith(b"HTTP/1.0 421 Misdirected Request")
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "headers",
+    [b"", b"Host: localhost\r\nHost: example.com\r\n", b"Host: not a host\r\n"],
+)
+def test_invalid_host(headers: bytes, session):
+    response = send_raw_request(
+        b"GET /sync/str HTTP/1.1\r\n" + headers + b"Connection: close\r\n\r\n"
+    )
+    assert response.startswith(b"HTTP/1.1 400 Bad Request")
+    assert b"<title>400 Bad Request</title>" in response
@@ -0,0 +1,143 @@
+import os
+import time
+
+import pytest
+import requests
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+
+debug_logs_server = with_ephemeral_server("debug_logs", args=["--log-level", "DEBUG"])
+slow_request_server = with_ephemeral_server(
+    "slow_request", env={"ROBYN_SLOW_REQUEST_THRESHOLD": "500"}
+)
+
+# the start of a TLS 1.2 ClientHello, as sent by a client speaking HTTPS to the plaintext port
+TLS_CLIENT_HELLO = bytes.fromhex(
+    "16030100a5010000a10303"
+    "5b902dc2c2f0b7e0b5db5d70f7d6ab36bc6c1e3cd5d3a6f7b4a5c4ee3f1e2d0c"
+    "000020c02bc02fc02cc030cca9cca8c013c014009c009d002f0035000a01000058"
+)
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "data",
+    [
+        TLS_CLIENT_HELLO,
+        os.urandom(512),
+        b"\x00\x01\x02\x03 / HTTP/1.1\r\n\r\n",
+        b"NOT A REQUEST\r\n\r\n",
+    ],
+)
+def test_invalid_request_answered_with_400(data: bytes, session):
+    response = send_raw_request(data)
+    # the connection is closed after the response
+    assert response.startswith(b"HTTP/1.1 400 Bad Request")
+
+    # the worker is still able to serve requests
+    r = requests.get("http://127.0.0.1:8080/sync/str")
+    assert r.status_code == 200
+
+
+def read_logs(logs) -> str:
+    logs.seek(0)
+    return logs.read().decode()
+
+
+def wait_for_log(logs, line: str):
+    deadline = time.time() + 2
+    while line not in read_logs(logs):
+        assert time.time() < deadline, f"{line!r} isn't logged"
+        time.sleep(0.1)
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "data,reason",
+    [
+        (b"GET / HTTP/1.1\r\nHost: 127.0.0.1\r\nX Bad: 1\r\n\r\n", "Invalid Header"),
+        (b"GET / HTP/1.1\r\nHost: 127.0.0.1\r\n\r\n", "Invalid HTTP version"),
+    ],
+)
+@debug_logs_server
+def test_malformed_request_is_logged_in_debug(
+    data: bytes, reason: str, ephemeral_server
+):
+    port, logs = ephemeral_server.port, ephemeral_server.logs
+    response = send_raw_request(data, port=port)
+    # the response stays generic
+    assert response.startswith(b"HTTP/1.1 400 Bad Request")
+    assert reason.encode() not in response
+    wait_for_log(logs, f"Malformed request answered with a 400: {reason}")
+
+
+@pytest.mark.benchmark
+@debug_logs_server
+def test_rejected_request_is_summarized_in_debug(ephemeral_server):
+    port, logs = ephemeral_server.port, ephemeral_server.logs
+    response = send_raw_request(
+        b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\nHost: example.com\r\n"
+        b"Connection: close\r\n\r\n",
+        port=port,
+    )
+    assert response.startswith(b"HTTP/1.1 400 Bad Request")
+    assert b"example.com" not in response
+    wait_for_log(logs, "Invalid host")
+    assert "GET /sync/str with 3 headers in 81 bytes" in read_logs(logs)
+
+
+@pytest.mark.benchmark
+@slow_request_server
+def test_malformed_request_is_not_logged_by_default(ephemeral_server):
+    port, logs = ephemeral_server.port, ephemeral_server.logs
+    response = send_raw_request(
+        b"GET / HTTP/1.1\r\nHost: 127.0.0.1\r\nX Bad: 1\r\n\r\n", port=port
+    )
+    assert response.startswith(b"HTTP/1.1 400 Bad Request")
+    time.sleep(0.2)
+    assert "Malformed request" not in read_logs(logs)
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "value, expected",
+    [
+        ("café".encode(), "café".encode()),
+        # the bytes that aren't UTF-8 are replaced
+        (b"caf\xe9", "caf\ufffd".encode()),
+    ],
+)
+def test_header_value_above_ascii(value: bytes, expected: bytes, session):
+    response = send_raw_request(
+        b"GET /sync/http/header HTTP/1.1\r\nHost: 127.0.0.1\r\nX-Value: "
+        + value
+        + b"\r\nConnection: close\r\n\r\n"
+    )
+    assert response.startswith(b"HTTP/1.1 200 OK")
+    assert response.endswith(b"\r\n\r\n" + expected)
+
+
+@pytest.mark.benchmark
+def test_unknown_method_not_implemented(session):
+    response = send_raw_request(
+        b"FOO /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+    )
+    assert response.startswith(b"HTTP/1.1 501 Not Implemented")
+    assert b"\r\nallow: GET, POST, PUT, DELETE, PATCH" in response
+
+
+@pytest.mark.benchmark
+@debug_logs_server
+def test_panic_answered_with_500(ephemeral_server):
+    port, logs = ephemeral_server.port, ephemeral_server.logs
+    r = requests.get(f"http://127.0.0.1:{port}/sync/panic")
+    assert r.status_code == 500
+    # pyo3 resumes the panics raised from Python with a message of its own
+    wait_for_log(
+        logs, "The request handling panicked: Unwrapped panic from Python code"
+    )
+
+    # the worker is still able to serve requests
+    r = requests.get(f"http://127.0.0.1:{port}/sync/str")
+    assert r.status_code == 200
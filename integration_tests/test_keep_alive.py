@@ -0,0 +1,89 @@
+import socket
+import time
+
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+
+keep_alive_disabled_server = with_ephemeral_server(
+    "keep_alive_disabled", env={"ROBYN_KEEP_ALIVE": "false"}
+)
+
+BASE_URL = "http://127.0.0.1:8080"
+MAX_REQUESTS_PER_CONNECTION = 100
+
+
+@pytest.mark.benchmark
+def test_connection_closed_after_max_requests(session):
+    with requests.Session() as http:
+        for _ in range(MAX_REQUESTS_PER_CONNECTION - 1):
+            res = http.get(f"{BASE_URL}/sync/str")
+            assert res.status_code == 200
+            assert res.headers.get("Connection") != "close"
+
+        res = http.get(f"{BASE_URL}/sync/str")
+        assert res.status_code == 200
+        assert res.headers.get("Connection") == "close"
+
+        # the count starts over on the next connection
+        res = http.get(f"{BASE_URL}/sync/str")
+        assert res.status_code == 200
+        assert res.headers.get("Connection") != "close"
+
+
+@pytest.mark.benchmark
+def test_connection_closed_by_the_client_between_requests(session):
+    with socket.create_connection(("127.0.0.1", 8080), timeout=5) as sock:
+        sock.sendall(b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n")
+        assert sock.recv(65536).startswith(b"HTTP/1.1 200 OK")
+        # an end of stream between two requests closes the connection without an answer
+        sock.shutdown(socket.SHUT_WR)
+        assert sock.recv(65536) == b""
+
+    with socket.create_connection(("127.0.0.1", 8080), timeout=5) as sock:
+        # as well as on a connection closed before sending anything
+        sock.shutdown(socket.SHUT_WR)
+        assert sock.recv(65536) == b""
+
+    res = requests.get(f"{BASE_URL}/sync/str")
+    assert res.status_code == 200
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "request_head",
+    [
+        b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+        b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: keep-alive\r\n"
+        b"\r\n",
+        b"GET /sync/str HTTP/1.0\r\nConnection: keep-alive\r\n\r\n",
+    ],
+)
+@keep_alive_disabled_server
+def test_keep_alive_disabled(request_head: bytes, ephemeral_server):
+    port = ephemeral_server.port
+    with socket.create_connection(("127.0.0.1", port), timeout=5) as sock:
+        sock.sendall(request_head)
+        start = time.time()
+        response = b""
+        while chunk := sock.recv(65536):
+            response += chunk
+        # the connection is closed after the request, without waiting for another one
+        assert time.time() - start < 1
+        assert b"keep-alive" not in response.lower()
+        # the connections of HTTP/1.0 are closed unless told otherwise
+        if b"HTTP/1.1" in request_head:
+            assert b"\r\nconnection: close\r\n" in response.lower()
+        assert response.endswith(b"sync str get")
+
+
+@pytest.mark.benchmark
+@keep_alive_disabled_server
+def test_keep_alive_disabled_serves_every_request(ephemeral_server):
+    url = f"http://127.0.0.1:{ephemeral_server.port}/sync/str"
+    with requests.Session() as http:
+        for _ in range(5):
+            res = http.get(url)
+            assert res.status_code == 200
+            assert res.headers["Connection"] == "close"
@@ -0,0 +1,35 @@
+import socket
+import time
+
+import pytest
+
+KEEP_ALIVE_TIMEOUT = 5
+
+REQUEST = b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n"
+
+
+def read_response(sock: socket.socket) -> bytes:
+    response = b""
+    while b"sync str get" not in response:
+        chunk = sock.recv(65536)
+        if not chunk:
+            break
+        response += chunk
+    return response
+
+
+@pytest.mark.benchmark
+def test_idle_connection_closed_after_keep_alive_timeout(session):
+    with socket.create_connection(("127.0.0.1", 8080), timeout=10) as sock:
+        sock.sendall(REQUEST)
+        assert read_response(sock).startswith(b"HTTP/1.1 200 OK")
+
+        # the connection is still usable within the timeout
+        time.sleep(1)
+        sock.sendall(REQUEST)
+        assert read_response(sock).startswith(b"HTTP/1.1 200 OK")
+
+        start = time.time()
+        # the server closes the idle connection cleanly, without a response
+        assert sock.recv(65536) == b""
+        assert KEEP_ALIVE_TIMEOUT - 1 < time.time() - start < KEEP_ALIVE_TIMEOUT + 2
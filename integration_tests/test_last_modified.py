@@ -0,0 +1,39 @@
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+def test_last_modified(function_type: str, session):
+    r = get(f"/{function_type}/serve/html")
+    last_modified = r.headers["Last-Modified"]
+    assert last_modified.endswith(" GMT")
+
+    r = get(
+        f"/{function_type}/serve/html",
+        expected_status_code=304,
+        headers={"If-Modified-Since": last_modified},
+    )
+    assert r.text == ""
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "if_modified_since",
+    ["Mon, 01 Jan 1990 00:00:00 GMT", "not a date"],
+)
+def test_if_modified_since_serves_full_response(if_modified_since: str, session):
+    r = get("/sync/serve/html", headers={"If-Modified-Since": if_modified_since})
+    assert "Hello world. How are you?" in r.text
+
+
+@pytest.mark.benchmark
+def test_if_none_match_takes_precedence_over_if_modified_since(session):
+    last_modified = get("/sync/serve/html").headers["Last-Modified"]
+    # the date would answer a 304, but the entity tag doesn't match
+    r = get(
+        "/sync/serve/html",
+        headers={"If-None-Match": '"other"', "If-Modified-Since": last_modified},
+    )
+    assert "Hello world. How are you?" in r.text
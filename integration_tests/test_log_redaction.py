@@ -0,0 +1,47 @@
+import pytest
+import requests
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+from test_invalid_requests import read_logs, wait_for_log
+
+redaction_server = with_ephemeral_server(
+    "redaction",
+    env={"ROBYN_REDACTED_HEADERS": "X-Api-Key"},
+    app="redaction_app.py",
+    args=["--log-level", "DEBUG"],
+)
+
+
+@pytest.mark.benchmark
+@redaction_server
+def test_sensitive_headers_are_redacted(ephemeral_server):
+    port, logs = ephemeral_server.port, ephemeral_server.logs
+    r = requests.get(
+        f"http://127.0.0.1:{port}/login",
+        headers={"Authorization": "Bearer auth-secret", "Cookie": "id=cookie-id"},
+    )
+    assert r.status_code == 200
+    # the client still gets the values
+    assert r.headers["set-cookie"] == "session=cookie-secret"
+    assert r.headers["x-api-key"] == "key-secret"
+
+    wait_for_log(logs, '"Set-Cookie": "<redacted>"')
+    output = read_logs(logs)
+    # the headers of ROBYN_REDACTED_HEADERS are redacted too
+    assert '"X-Api-Key": "<redacted>"' in output
+    for secret in ["auth-secret", "cookie-id", "cookie-secret", "key-secret"]:
+        assert secret not in output
+
+
+@pytest.mark.benchmark
+@redaction_server
+def test_log_redactor(ephemeral_server):
+    port, logs = ephemeral_server.port, ephemeral_server.logs
+    response = send_raw_request(
+        b"GET /login?token=query-secret HTTP/1.1\r\n\r\n", port=port
+    )
+    assert response.startswith(b"HTTP/1.1 400 Bad Request")
+
+    wait_for_log(logs, "Missing host: GET /login?token=***")
+    assert "query-secret" not in read_logs(logs)
@@ -0,0 +1,94 @@
+import os
+import socket
+import time
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+
+max_connections_server = with_ephemeral_server(
+    "max_connections", env={"ROBYN_MAX_CONNECTIONS": "2"}
+)
+OVERLOAD_PAGE = os.path.join(
+    os.path.dirname(os.path.abspath(__file__)), "overload.html"
+)
+overload_page_server = with_ephemeral_server(
+    "overload_page",
+    env={"ROBYN_MAX_CONNECTIONS": "1", "ROBYN_OVERLOAD_PAGE": OVERLOAD_PAGE},
+)
+max_connections_per_ip_server = with_ephemeral_server(
+    "max_connections_per_ip", env={"ROBYN_MAX_CONNECTIONS_PER_IP": "2"}
+)
+
+REQUEST = b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+
+
+@max_connections_server
+def test_connections_above_the_limit_get_a_503(ephemeral_server):
+    port = ephemeral_server.port
+    # let the server drop the connection used to check it was up
+    time.sleep(0.5)
+
+    # the server is limited to 2 open connections
+    open_connections = [
+        socket.create_connection(("127.0.0.1", port), timeout=5) for _ in range(2)
+    ]
+    try:
+        response = send_raw_request(REQUEST, port=port)
+        assert response.startswith(b"HTTP/1.1 503 Service Unavailable")
+        assert b"connection: close" in response.lower()
+        # the clients are told to back off for a moment
+        assert b"retry-after: 1\r\n" in response.lower()
+
+        # new connections are served again once another one is closed
+        open_connections.pop().close()
+        time.sleep(0.5)
+        response = send_raw_request(REQUEST, port=port)
+        assert response.startswith(b"HTTP/1.1 200 OK")
+    finally:
+        for connection in open_connections:
+            connection.close()
+
+
+@overload_page_server
+def test_connections_above_the_limit_get_the_overload_page(ephemeral_server):
+    port = ephemeral_server.port
+    time.sleep(0.5)
+
+    with socket.create_connection(("127.0.0.1", port), timeout=5):
+        response = send_raw_request(REQUEST, port=port)
+    head, body = response.split(b"\r\n\r\n", 1)
+    assert head.startswith(b"HTTP/1.1 503 Service Unavailable")
+    assert b"content-type: text/html" in head.lower()
+    with open(OVERLOAD_PAGE, "rb") as page:
+        assert body == page.read()
+
+
+@max_connections_per_ip_server
+def test_connections_above_the_limit_of_their_ip_get_a_503(ephemeral_server):
+    port = ephemeral_server.port
+    time.sleep(0.5)
+
+    # the server is limited to 2 open connections by IP address
+    open_connections = [
+        socket.create_connection(("127.0.0.1", port), timeout=5) for _ in range(2)
+    ]
+    try:
+        response = send_raw_request(REQUEST, port=port)
+        assert response.startswith(b"HTTP/1.1 503 Service Unavailable")
+        assert b"connection: close" in response.lower()
+        assert b"retry-after: 1\r\n" in response.lower()
+
+        # the clients connecting from another address are still served
+        with socket.create_connection(
+            ("127.0.0.1", port), timeout=5, source_address=("127.0.0.2", 0)
+        ) as connection:
+            connection.sendall(REQUEST)
+            assert connection.recv(65536).startswith(b"HTTP/1.1 200 OK")
+
+        open_connections.pop().close()
+        time.sleep(0.5)
+        response = send_raw_request(REQUEST, port=port)
+        assert response.startswith(b"HTTP/1.1 200 OK")
+    finally:
+        for connection in open_connections:
+            connection.close()
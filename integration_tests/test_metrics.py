@@ -0,0 +1,75 @@
+import re
+import time
+
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+def get_sample(metrics: str, name: str) -> float:
+    match = re.search(rf"^{re.escape(name)} (\S+)$", metrics, re.MULTILINE)
+    assert match is not None, f"{name} is missing"
+    return float(match.group(1))
+
+
+@pytest.mark.benchmark
+def test_metrics(session):
+    for id in range(3):
+        get(f"/sync/middlewares/matched_route/{id}")
+    get("/metrics/not/a/route", expected_status_code=404)
+
+    # the endpoint is served by the server itself, without the global response headers
+    r = get("/metrics", should_check_response=False)
+    assert r.status_code == 200
+    assert r.headers["Content-Type"] == "text/plain; version=0.0.4"
+    metrics = r.text
+
+    # the requests are grouped by the pattern of their route
+    route = 'route="/sync/middlewares/matched_route/:id"'
+    assert get_sample(metrics, f'robyn_requests_total{{{route},status="200"}}') >= 3
+    assert (
+        get_sample(metrics, 'robyn_requests_total{route="<not found>",status="404"}')
+        >= 1
+    )
+    assert "/sync/middlewares/matched_route/0" not in metrics
+
+    count = get_sample(metrics, f"robyn_request_duration_seconds_count{{{route}}}")
+    assert count >= 3
+    assert (
+        get_sample(metrics, f'robyn_request_duration_seconds_bucket{{{route},le="+Inf"}}')
+        == count
+    )
+    assert get_sample(metrics, "robyn_open_connections") >= 1
+
+
+@pytest.mark.benchmark
+def test_metrics_time_to_first_byte(session):
+    r = get("/sync/metrics/stream")
+    assert r.text == "first chunk\nlast chunk\n"
+
+    metrics = get("/metrics", should_check_response=False).text
+    route = 'route="/sync/metrics/stream"'
+    count = get_sample(metrics, f"robyn_time_to_first_byte_seconds_count{{{route}}}")
+    assert count == get_sample(
+        metrics, f"robyn_request_duration_seconds_count{{{route}}}"
+    )
+    # the duration includes the streamed body, unlike the time to the first byte
+    first_byte = get_sample(metrics, f"robyn_time_to_first_byte_seconds_sum{{{route}}}")
+    duration = get_sample(metrics, f"robyn_request_duration_seconds_sum{{{route}}}")
+    assert duration - first_byte >= 0.25 * count
+
+
+@pytest.mark.benchmark
+def test_metrics_worker_lag(session):
+    def lag() -> float:
+        metrics = get("/metrics", should_check_response=False).text
+        assert get_sample(metrics, "robyn_busy_workers") >= 0
+        return get_sample(metrics, "robyn_worker_lag_seconds_sum")
+
+    before = lag()
+    # the sync handler blocks its worker for 300ms, so its timer fires about 200ms late
+    get("/sync/metrics/blocking")
+    deadline = time.time() + 2
+    while lag() - before < 0.1:
+        assert time.time() < deadline, "the lag of the worker isn't recorded"
+        time.sleep(0.1)
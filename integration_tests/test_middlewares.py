@@ -27,3 +27,23 @@ def test_global_middleware(session):
 def test_response_in_before_middleware(session):
     r = get("/sync/middlewares/401", should_check_response=False)
     assert r.status_code == 401
+
+
+@pytest.mark.benchmark
+def test_extensions_passed_from_middleware_to_handler(session):
+    r = get("/sync/middlewares/extensions")
+    assert r.text == "hello robyn, 1 extension"
+
+    # every request starts with its own empty extensions
+    r = get("/sync/extensions")
+    assert r.text == "0 extensions"
+
+
+@pytest.mark.benchmark
+def test_matched_route(session):
+    r = get("/sync/middlewares/matched_route/42")
+    # the pattern is known to the middlewares and the handler, not the concrete path
+    assert (
+        r.text
+        == "/sync/middlewares/matched_route/:id /sync/middlewares/matched_route/:id"
+    )
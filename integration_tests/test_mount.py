@@ -0,0 +1,39 @@
+import pytest
+
+from helpers.http_methods_helpers import get
+from robyn.router import join_url_path
+
+
+@pytest.mark.parametrize(
+    "prefix,endpoint,expected",
+    [
+        ("/admin", "/users", "/admin/users"),
+        ("/admin/", "/users", "/admin/users"),
+        ("admin", "users", "/admin/users"),
+        ("/admin/", "/", "/admin"),
+        ("/", "/users", "/users"),
+        ("", "/", "/"),
+    ],
+)
+def test_join_url_path(prefix: str, endpoint: str, expected: str):
+    assert join_url_path(prefix, endpoint) == expected
+
+
+@pytest.mark.benchmark
+def test_mounted_index(session):
+    r = get("/mounted")
+    assert r.text == "mounted index"
+
+
+@pytest.mark.benchmark
+def test_mounted_route(session):
+    r = get("/mounted/users/42")
+    assert r.text == "42"
+    # both the parent's global middlewares and the mounted route middlewares are run
+    assert r.headers["global_after"] == "global_after_request"
+    assert r.headers["mounted_after"] == "mounted_after_request"
+
+
+@pytest.mark.benchmark
+def test_mounted_route_not_served_without_prefix(session):
+    get("/users/42", expected_status_code=404)
@@ -0,0 +1,12 @@
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+@pytest.mark.benchmark
+def test_offload(session):
+    r = get("/async/offload")
+    result = r.json()
+    assert result["sum"] == sum(range(0, 1000))
+    # the function didn't run on the thread of the event loop
+    assert result["thread"] != result["loop_thread"]
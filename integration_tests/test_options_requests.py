@@ -0,0 +1,15 @@
+import pytest
+
+from helpers.network_helpers import send_raw_request
+
+
+@pytest.mark.benchmark
+def test_server_wide_options(session):
+    response = send_raw_request(
+        b"OPTIONS * HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+    )
+    head = response.decode().lower()
+    assert head.startswith("http/1.1 200 ok")
+    assert (
+        "allow: get, post, put, delete, patch, head, options, connect, trace" in head
+    )
@@ -0,0 +1,42 @@
+import os
+import platform
+from typing import List
+
+import pytest
+
+from helpers.server_helpers import with_ephemeral_server
+
+pin_workers_server = with_ephemeral_server(
+    "pin_workers", env={"ROBYN_PIN_WORKERS": "true"}
+)
+
+
+def descendants(pid: int) -> List[int]:
+    with open(f"/proc/{pid}/task/{pid}/children") as children:
+        pids = [int(child) for child in children.read().split()]
+    return pids + [pid for child in pids for pid in descendants(child)]
+
+
+def worker_threads_allowed_cpus(pid: int) -> List[str]:
+    allowed_cpus = []
+    for process in [pid] + descendants(pid):
+        for thread in os.listdir(f"/proc/{process}/task"):
+            # the workers of actix each run an arbiter on a thread of its own
+            with open(f"/proc/{process}/task/{thread}/comm") as comm:
+                if not comm.read().startswith("actix-rt|system"):
+                    continue
+            with open(f"/proc/{process}/task/{thread}/status") as status:
+                for line in status:
+                    if line.startswith("Cpus_allowed_list:"):
+                        allowed_cpus.append(line.split(":")[1].strip())
+    return allowed_cpus
+
+
+@pytest.mark.skipif(platform.system() != "Linux", reason="reads the threads in /proc")
+@pin_workers_server
+def test_pinned_workers(ephemeral_server):
+    allowed_cpus = worker_threads_allowed_cpus(ephemeral_server.process.pid)
+    assert allowed_cpus
+    # each worker thread is pinned to a single core
+    for cpus in allowed_cpus:
+        assert cpus.isdigit()
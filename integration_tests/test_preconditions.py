@@ -0,0 +1,88 @@
+import pytest
+
+from helpers.http_methods_helpers import get, put
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "if_match,expected_status_code",
+    [
+        ('"v1"', 200),
+        ('"v0", "v1"', 200),
+        ("*", 200),
+        ('"v2"', 412),
+        # the comparison of If-Match is strong
+        ('W/"v1"', 412),
+    ],
+)
+def test_if_match(session, if_match: str, expected_status_code: int):
+    put(
+        "/sync/preconditions/document",
+        headers={"If-Match": if_match},
+        expected_status_code=expected_status_code,
+    )
+
+
+@pytest.mark.benchmark
+def test_if_match_any_on_a_missing_resource(session):
+    put(
+        "/async/preconditions/missing",
+        headers={"If-Match": "*"},
+        expected_status_code=412,
+    )
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "if_none_match,expected_status_code",
+    [
+        ('"v1"', 304),
+        # the comparison of If-None-Match is weak
+        ('W/"v1"', 304),
+        ("*", 304),
+        ('"v2"', 200),
+    ],
+)
+def test_if_none_match(session, if_none_match: str, expected_status_code: int):
+    r = get(
+        "/sync/preconditions/document",
+        headers={"If-None-Match": if_none_match},
+        expected_status_code=expected_status_code,
+    )
+    assert r.headers["ETag"] == '"v1"'
+
+
+@pytest.mark.benchmark
+def test_if_none_match_on_an_unsafe_method(session):
+    put(
+        "/sync/preconditions/document",
+        headers={"If-None-Match": "*"},
+        expected_status_code=412,
+    )
+    # creating a resource only if it doesn't exist yet
+    r = put("/async/preconditions/missing", headers={"If-None-Match": "*"})
+    assert r.text == "created"
+
+
+@pytest.mark.benchmark
+def test_no_preconditions(session):
+    r = put("/sync/preconditions/document")
+    assert r.text == "updated"
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "headers,expected_tags",
+    [
+        ({}, {"if_match": None, "if_none_match": None}),
+        (
+            {"If-Match": '"a,b", W/"c"', "If-None-Match": "*"},
+            {"if_match": ['"a,b"', 'W/"c"'], "if_none_match": ["*"]},
+        ),
+        # a malformed header is ignored
+        ({"If-Match": "v1"}, {"if_match": None, "if_none_match": None}),
+    ],
+)
+def test_precondition_headers(session, headers: dict, expected_tags: dict):
+    r = get("/sync/preconditions/tags", headers=headers)
+    assert r.json() == expected_tags
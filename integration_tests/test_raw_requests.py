@@ -0,0 +1,66 @@
+import socket
+import time
+
+import pytest
+
+from helpers.network_helpers import send_raw_request
+
+
+def get_body(response: bytes) -> bytes:
+    return response.split(b"\r\n\r\n", 1)[1]
+
+
+@pytest.mark.benchmark
+def test_body_sent_with_the_head(session):
+    # the head and the body arrive in a single write
+    body = b"hello robyn"
+    response = send_raw_request(
+        b"POST /sync/body HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n"
+        + f"Content-Length: {len(body)}\r\n\r\n".encode()
+        + body
+    )
+    assert response.startswith(b"HTTP/1.1 200 OK")
+    assert get_body(response) == body
+
+
+@pytest.mark.benchmark
+def test_body_split_across_writes(session):
+    body = b"hello robyn, this body is split in parts"
+    head = (
+        b"POST /sync/body HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n"
+        + f"Content-Length: {len(body)}\r\n\r\n".encode()
+    )
+    # the first part of the body is sent with the head, the rest comes later
+    with socket.create_connection(("127.0.0.1", 8080), timeout=5) as sock:
+        sock.sendall(head + body[:5])
+        time.sleep(0.1)
+        sock.sendall(body[5:20])
+        time.sleep(0.1)
+        sock.sendall(body[20:])
+        response = b""
+        while True:
+            chunk = sock.recv(65536)
+            if not chunk:
+                break
+            response += chunk
+    assert get_body(response) == body
+
+
+@pytest.mark.benchmark
+def test_pipelined_requests(session):
+    # the requests are sent in a single write before any response is read, the bytes of the
+    # next requests are already buffered when the first one is parsed
+    body = b"first request"
+    response = send_raw_request(
+        b"POST /sync/body HTTP/1.1\r\nHost: 127.0.0.1\r\n"
+        + f"Content-Length: {len(body)}\r\n\r\n".encode()
+        + body
+        + b"GET /async/timeout/in_time HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n"
+        + b"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+    )
+    assert response.count(b"HTTP/1.1 200 OK") == 3
+    # the responses are written in the order of the requests, even when a handler is slower
+    first = response.index(b"first request")
+    second = response.index(b"in time")
+    third = response.index(b"sync str get")
+    assert first < second < third
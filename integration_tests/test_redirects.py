@@ -0,0 +1,82 @@
+import pytest
+import requests
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+from robyn import Response
+
+absolute_redirects_server = with_ephemeral_server(
+    "absolute_redirects",
+    env={"ROBYN_ABSOLUTE_REDIRECTS": "true", "ROBYN_TRUST_PROXY_HEADERS": "true"},
+)
+
+BASE_URL = "http://127.0.0.1:8080"
+
+
+def get_location(url: str, **kwargs) -> str:
+    return requests.get(url, allow_redirects=False, **kwargs).headers["Location"]
+
+
+@pytest.mark.benchmark
+def test_redirect_is_relative(session):
+    r = requests.get(f"{BASE_URL}/sync/redirect/relative", allow_redirects=False)
+    assert r.status_code == 307
+    assert r.headers["Location"] == "/sync/str"
+    assert r.text == ""
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "location,absolute",
+    [
+        ("/sync/str", "http://127.0.0.1:8080/sync/str"),
+        # relative to the directory of the path of the request
+        ("login", "http://127.0.0.1:8080/sync/redirect/login"),
+        ("?page=2", "http://127.0.0.1:8080/sync/redirect/absolute?page=2"),
+        ("https://example.com/login", "https://example.com/login"),
+    ],
+)
+def test_absolute_redirect(location: str, absolute: str, session):
+    url = f"{BASE_URL}/sync/redirect/absolute?to={location}"
+    r = requests.get(url, allow_redirects=False)
+    assert r.status_code == 303
+    assert r.headers["Location"] == absolute
+
+
+@pytest.mark.benchmark
+def test_absolute_redirect_ignores_the_proxy_headers_by_default(session):
+    headers = {"X-Forwarded-Proto": "https", "X-Forwarded-Host": "evil.com"}
+    url = f"{BASE_URL}/sync/redirect/absolute?to=/sync/str"
+    assert get_location(url, headers=headers) == "http://127.0.0.1:8080/sync/str"
+
+
+@pytest.mark.benchmark
+def test_absolute_redirect_without_host(session):
+    request = b"GET /sync/redirect/absolute?to=/sync/str HTTP/1.0\r\n\r\n"
+    response = send_raw_request(request)
+    assert response.startswith(b"HTTP/1.0 303 See Other")
+    # the location stays relative, as the host isn't known
+    assert b"\r\nlocation: /sync/str\r\n" in response.lower()
+
+
+def test_invalid_redirect():
+    with pytest.raises(ValueError):
+        Response.redirect("/sync/str", 200)
+    with pytest.raises(ValueError):
+        Response.redirect("/sync/str\n")
+
+
+@pytest.mark.benchmark
+@absolute_redirects_server
+def test_absolute_directory_redirect(ephemeral_server):
+    base_url = f"http://127.0.0.1:{ephemeral_server.port}"
+    assert (
+        get_location(f"{base_url}/static_site/docs?page=1")
+        == f"{base_url}/static_site/docs/?page=1"
+    )
+    # behind a trusted proxy, on the host and with the scheme the client sent to it
+    headers = {"X-Forwarded-Proto": "https", "X-Forwarded-Host": "example.com"}
+    assert (
+        get_location(f"{base_url}/static_site/docs", headers=headers)
+        == "https://example.com/static_site/docs/"
+    )
@@ -0,0 +1,61 @@
+import pytest
+
+from helpers.app_helpers import build_server
+from robyn import Robyn
+from robyn.robyn import TestClient
+
+
+def test_reload_routes():
+    app = Robyn(__file__)
+
+    @app.get("/hello")
+    def hello():
+        return "hello"
+
+    @app.get("/removed")
+    def removed():
+        return "removed"
+
+    server = build_server(app)
+    client = TestClient(server)
+    assert client.get("/hello").body == "hello"
+
+    reloaded = Robyn(__file__)
+
+    @reloaded.get("/hello")
+    def hello_reloaded():
+        return "hello reloaded"
+
+    @reloaded.get("/const", const=True)
+    def const():
+        return "const"
+
+    @reloaded.post("/added")
+    async def added():
+        return "added"
+
+    server.reload_routes(reloaded.router.get_routes())
+    assert client.get("/hello").body == "hello reloaded"
+    assert client.get("/const").body == "const"
+    assert client.post("/added").body == "added"
+    assert client.get("/removed").status_code == 404
+
+
+def test_reload_invalid_routes():
+    app = Robyn(__file__)
+
+    @app.get("/hello")
+    def hello():
+        return "hello"
+
+    server = build_server(app)
+    client = TestClient(server)
+
+    reloaded = Robyn(__file__)
+    reloaded.get("/conflict/:id")(lambda: "id")
+    reloaded.get("/conflict/:name")(lambda: "name")
+
+    with pytest.raises(ValueError):
+        server.reload_routes(reloaded.router.get_routes())
+    # the routes are kept when the new ones can't be added
+    assert client.get("/hello").body == "hello"
@@ -0,0 +1,35 @@
+import gzip
+import zlib
+
+import pytest
+
+from helpers.http_methods_helpers import post
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "encoding,compress",
+    [("gzip", gzip.compress), ("deflate", zlib.compress)],
+)
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+def test_compressed_body(function_type: str, encoding: str, compress, session):
+    body = b'{"hello": "robyn"}'
+    r = post(
+        f"/{function_type}/body",
+        data=compress(body),
+        headers={"Content-Encoding": encoding},
+    )
+    assert r.text == body.decode()
+
+
+@pytest.mark.benchmark
+def test_compressed_body_over_max_payload_size(session):
+    # ~2KB compressed, but above the default max payload size of 1MB once decompressed
+    body = gzip.compress(b"0" * 2_000_000)
+    r = post(
+        "/sync/body",
+        data=body,
+        headers={"Content-Encoding": "gzip"},
+        should_check_response=False,
+    )
+    assert r.status_code == 413
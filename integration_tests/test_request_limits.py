@@ -0,0 +1,87 @@
+import time
+
+import pytest
+
+from helpers.http_methods_helpers import get, post
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+
+max_headers_server = with_ephemeral_server(
+    "max_headers", env={"ROBYN_MAX_HEADERS": "10"}
+)
+
+
+def request_with_headers(count: int) -> bytes:
+    # the Host and Connection headers are two of them
+    headers = "".join(f"X-Header-{i}: {i}\r\n" for i in range(count - 2))
+    return (
+        f"GET /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\n{headers}"
+        "Connection: close\r\n\r\n"
+    ).encode()
+
+
+@pytest.mark.benchmark
+def test_uri_too_long(session):
+    r = get(f"/sync/queries?hello={'a' * 9000}", should_check_response=False)
+    assert r.status_code == 414
+
+
+@pytest.mark.benchmark
+def test_uri_below_limit(session):
+    r = get(f"/sync/queries?hello={'a' * 4000}")
+    assert r.json() == {"hello": "a" * 4000}
+
+
+@pytest.mark.benchmark
+def test_thousands_of_headers(session):
+    start = time.time()
+    # the parser stops at its limit, rather than reading every header
+    response = send_raw_request(request_with_headers(5000))
+    assert response.startswith(b"HTTP/1.1 431 Request Header Fields Too Large")
+    assert time.time() - start < 1
+
+    r = get("/sync/str")
+    assert r.text == "sync str get"
+
+
+@pytest.mark.benchmark
+@max_headers_server
+def test_max_headers(ephemeral_server):
+    response = send_raw_request(request_with_headers(10), port=ephemeral_server.port)
+    assert response.startswith(b"HTTP/1.1 200 OK")
+
+    response = send_raw_request(request_with_headers(11), port=ephemeral_server.port)
+    assert response.startswith(b"HTTP/1.1 431 Request Header Fields Too Large")
+    assert response.endswith(b"Request header fields too large")
+
+
+@pytest.mark.benchmark
+def test_route_max_body_size(session):
+    r = post("/sync/body/small", data=b"a" * 64)
+    assert r.text == "a" * 64
+
+    r = post("/sync/body/small", data=b"a" * 65, should_check_response=False)
+    assert r.status_code == 413
+
+
+@pytest.mark.benchmark
+def test_route_max_body_size_without_content_length(session):
+    # a chunked body is limited as it is received
+    def body():
+        for _ in range(3):
+            yield b"a" * 30
+
+    r = post("/sync/body/small", data=body(), should_check_response=False)
+    assert r.status_code == 413
+
+
+@pytest.mark.benchmark
+def test_route_max_body_size_above_the_global_limit(session):
+    r = post("/async/body/large", data=b"a" * 2_000_000)
+    assert r.text == "2000000"
+
+    r = post("/async/body/large", data=b"a" * 3_000_001, should_check_response=False)
+    assert r.status_code == 413
+    # the other routes keep the global limit
+    r = post("/async/body", data=b"a" * 2_000_000, should_check_response=False)
+    assert r.status_code == 413
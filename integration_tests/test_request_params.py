@@ -0,0 +1,44 @@
+import pytest
+import requests
+
+from helpers.http_methods_helpers import post
+
+BASE_URL = "http://127.0.0.1:8080"
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("route", ["/sync/params", "/async/params"])
+def test_params_precedence(route: str, session):
+    r = post(
+        f"{route}/42?page=2&id=query",
+        data={"id": "form", "page": "form", "name": "robyn & co"},
+    )
+    # the path params come first, then the query params and the form fields
+    assert r.json()["params"] == {
+        "id": "42",
+        "page": "2",
+        "name": "robyn & co",
+        "missing": "default",
+    }
+    assert r.json()["form"] == {"id": "form", "page": "form", "name": "robyn & co"}
+
+
+@pytest.mark.parametrize("route", ["/sync/params", "/async/params"])
+def test_form_of_other_bodies(route: str, session):
+    r = post(
+        f"{route}/42",
+        data="name=robyn",
+        headers={"Content-Type": "text/plain"},
+    )
+    assert r.json()["params"]["name"] == "default"
+    assert r.json()["form"] == {}
+
+
+def test_multipart_form_fields(session):
+    r = requests.post(
+        f"{BASE_URL}/sync/params/42",
+        files={"name": (None, "robyn"), "avatar": ("avatar.png", b"png")},
+    )
+    assert r.status_code == 200
+    assert r.json()["params"]["name"] == "robyn"
+    assert r.json()["form"] == {"name": "robyn"}
@@ -0,0 +1,79 @@
+import pytest
+import requests
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+
+strip_conflicting_length_server = with_ephemeral_server(
+    "strip_conflicting_length", env={"ROBYN_CONFLICTING_LENGTH": "strip"}
+)
+
+SMUGGLED = b"GET /sync/str HTTP/1.1\r\nHost: localhost\r\n\r\n"
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "data",
+    [
+        # read to its end from the `Content-Length`, the smuggled request is in the body
+        b"POST /sync/body/framing HTTP/1.1\r\nHost: localhost\r\n"
+        b"Content-Length: 48\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n" + SMUGGLED,
+        # cut short by the `Content-Length`, the rest of the chunks would be a request
+        b"POST /sync/body/framing HTTP/1.1\r\nHost: localhost\r\n"
+        b"Transfer-Encoding: chunked\r\nContent-Length: 4\r\n\r\n"
+        b"2B\r\n" + SMUGGLED + b"\r\n0\r\n\r\n",
+        b"POST /sync/body/framing HTTP/1.1\r\nHost: localhost\r\n"
+        b"content-length: 5\r\ntransfer-encoding: Chunked\r\n\r\n0\r\n\r\n",
+        # the `Transfer-Encoding` of an HTTP/1.0 request is ignored by the server
+        b"POST /sync/body/framing HTTP/1.0\r\nHost: localhost\r\n"
+        b"Content-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+    ],
+)
+def test_conflicting_length_rejected(data: bytes, session):
+    response = send_raw_request(data)
+    assert response.split(b"\r\n", 1)[0].endswith(b" 400 Bad Request")
+    # the request smuggled in the body isn't served
+    assert b"sync str get" not in response
+
+    r = requests.get("http://127.0.0.1:8080/sync/str")
+    assert r.status_code == 200
+
+
+@pytest.mark.benchmark
+@strip_conflicting_length_server
+def test_conflicting_length_stripped(ephemeral_server):
+    # the body is read from the chunks, the length above the size limit isn't checked
+    response = send_raw_request(
+        b"POST /sync/body/framing HTTP/1.1\r\nHost: localhost\r\n"
+        b"Content-Length: 100000000\r\nTransfer-Encoding: chunked\r\n\r\n"
+        b"5\r\nhello\r\n0\r\n\r\n" + SMUGGLED,
+        port=ephemeral_server.port,
+    )
+    head, body = response.split(b"\r\n\r\n", 1)
+    assert head.startswith(b"HTTP/1.1 200 OK")
+    assert body.startswith(b"None chunked hello")
+    # the connection is closed, and the request read after the body isn't served
+    assert b"connection: close" in head.lower()
+    assert b"sync str get" not in response
+
+
+@pytest.mark.benchmark
+@strip_conflicting_length_server
+def test_conflicting_length_stripped_http_1_0(ephemeral_server):
+    response = send_raw_request(
+        b"POST /sync/body/framing HTTP/1.0\r\nHost: localhost\r\n"
+        b"Content-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\nhello",
+        port=ephemeral_server.port,
+    )
+    assert response.split(b"\r\n", 1)[0].endswith(b"200 OK")
+    assert response.endswith(b"\r\n\r\n5 None hello")
+
+
+@pytest.mark.benchmark
+def test_single_length_served(session):
+    response = send_raw_request(
+        b"POST /sync/body/framing HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n"
+        b"Transfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n"
+    )
+    assert response.startswith(b"HTTP/1.1 200 OK")
+    assert response.endswith(b"\r\n\r\nNone chunked hello")
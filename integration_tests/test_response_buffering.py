@@ -0,0 +1,147 @@
+import gzip
+import os
+
+import pytest
+import requests
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+
+response_buffer_size_server = with_ephemeral_server(
+    "response_buffer_size", env={"ROBYN_RESPONSE_BUFFER_SIZE": "1024"}
+)
+mmap_files_server = with_ephemeral_server(
+    "mmap_files", env={"ROBYN_RESPONSE_BUFFER_SIZE": "1024", "ROBYN_MMAP_FILES": "true"}
+)
+
+
+@pytest.fixture
+def sized_files(tmp_path):
+    paths = {}
+    for size in [1024, 1025, 200_000]:
+        path = tmp_path / f"file-{size}.bin"
+        path.write_bytes(os.urandom(size))
+        paths[size] = str(path)
+    return paths
+
+
+def url(port: int, route: str, path) -> str:
+    # the query isn't percent-decoded by the server, the path is given as it is
+    return f"http://127.0.0.1:{port}{route}?path={path}"
+
+
+@pytest.mark.benchmark
+@response_buffer_size_server
+def test_files_streamed_above_the_buffer_size(
+    ephemeral_server, sized_files
+):
+    port = ephemeral_server.port
+    route = "/sync/file/response/sized/streamed"
+    # a file of the exact buffer size is still read in memory
+    assert requests.get(url(port, route, sized_files[1024])).text == "False"
+    assert requests.get(url(port, route, sized_files[1025])).text == "True"
+    assert requests.get(url(port, route, sized_files[200_000])).text == "True"
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("size", [1024, 1025, 200_000])
+@response_buffer_size_server
+def test_streamed_file_response(ephemeral_server, sized_files, size: int):
+    port = ephemeral_server.port
+    r = requests.get(url(port, "/sync/file/response/sized", sized_files[size]))
+    assert r.status_code == 200
+    # the length of a streamed file is known, so it isn't sent in chunks
+    assert r.headers["Content-Length"] == str(size)
+    assert "Transfer-Encoding" not in r.headers
+    with open(sized_files[size], "rb") as file:
+        assert r.content == file.read()
+
+
+@pytest.mark.benchmark
+@response_buffer_size_server
+def test_streamed_file_download(ephemeral_server, tmp_path):
+    port = ephemeral_server.port
+    path = tmp_path / "download.txt"
+    path.write_text("robyn\n" * 10_000)
+    r = requests.get(url(port, "/sync/file/download/sized", path))
+    assert r.headers["Content-Length"] == "60000"
+    assert r.text == "robyn\n" * 10_000
+
+
+@pytest.mark.benchmark
+@response_buffer_size_server
+def test_streamed_precompressed_file(ephemeral_server, tmp_path):
+    port = ephemeral_server.port
+    path = tmp_path / "page.html"
+    content = os.urandom(4096)
+    path.write_bytes(content)
+    # the random bytes don't compress, the variant is above the buffer size too
+    compressed = gzip.compress(content)
+    (tmp_path / "page.html.gz").write_bytes(compressed)
+    r = requests.get(
+        url(port, "/sync/file/response/sized", path),
+        headers={"Accept-Encoding": "gzip"},
+    )
+    assert r.headers["Content-Encoding"] == "gzip"
+    assert r.headers["Content-Length"] == str(len(compressed))
+    assert r.content == content
+
+
+@pytest.mark.benchmark
+@response_buffer_size_server
+def test_streamed_file_head(ephemeral_server, sized_files):
+    port = ephemeral_server.port
+    r = requests.head(url(port, "/sync/file/response/sized", sized_files[200_000]))
+    assert r.status_code == 200
+    assert r.headers["Content-Length"] == "200000"
+    assert r.content == b""
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("size", [1024, 1025, 200_000])
+@mmap_files_server
+def test_mapped_file_response(ephemeral_server, sized_files, size: int):
+    port = ephemeral_server.port
+    r = requests.get(url(port, "/sync/file/response/sized", sized_files[size]))
+    assert r.status_code == 200
+    assert r.headers["Content-Length"] == str(size)
+    assert "Transfer-Encoding" not in r.headers
+    with open(sized_files[size], "rb") as file:
+        assert r.content == file.read()
+
+
+@pytest.mark.benchmark
+@response_buffer_size_server
+def test_streamed_file_iterated(ephemeral_server, sized_files):
+    port = ephemeral_server.port
+    path = sized_files[200_000]
+    r = requests.get(url(port, "/sync/file/response/sized/iterated", path))
+    assert r.text == "200000"
+
+
+@pytest.mark.benchmark
+@mmap_files_server
+def test_mapped_file_iterated(ephemeral_server, sized_files):
+    port = ephemeral_server.port
+    path = sized_files[200_000]
+    r = requests.get(url(port, "/sync/file/response/sized/iterated", path))
+    assert r.text == "200000"
+
+
+@pytest.mark.benchmark
+@mmap_files_server
+def test_mapped_file_truncated(ephemeral_server, tmp_path):
+    port = ephemeral_server.port
+    path = tmp_path / "truncated.bin"
+    path.write_bytes(os.urandom(200_000))
+    response = send_raw_request(
+        b"GET /sync/file/response/sized/truncated?path="
+        + str(path).encode()
+        + b" HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+        port=port,
+    )
+    # the response is cut short rather than read past the end of the file, here before its
+    # head is even sent as the first chunk fails
+    assert len(response) < 200_000
+    # the server is still up
+    assert requests.get(f"http://127.0.0.1:{port}/sync/str").text == "sync str get"
@@ -0,0 +1,35 @@
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+@pytest.mark.benchmark
+def test_status_and_body(session):
+    r = get("/sync/tuple", expected_status_code=201)
+    assert r.headers["Content-Type"] == "text/plain"
+    assert r.text == "sync tuple get"
+
+
+@pytest.mark.benchmark
+def test_status_headers_and_body(session):
+    r = get("/async/tuple", expected_status_code=201)
+    assert r.headers["Content-Type"] == "application/octet-stream"
+    assert r.headers["async"] == "tuple"
+    assert r.text == "async tuple get"
+
+
+def test_content_type_of_the_headers(session):
+    r = get("/sync/tuple/content_type")
+    assert r.headers["Content-Type"] == "application/json"
+    assert r.json() == {"sync": "tuple"}
+
+
+def test_const_route(session):
+    r = get("/sync/tuple/const", expected_status_code=202)
+    assert r.headers["sync_const"] == "tuple"
+    assert r.text == "sync tuple const get"
+
+
+@pytest.mark.parametrize("route", ["/sync/tuple/invalid", "/async/tuple/invalid"])
+def test_invalid_tuple(route: str, session):
+    get(route, expected_status_code=500)
@@ -0,0 +1,51 @@
+import pytest
+
+from base_routes import app
+from robyn.robyn import MiddlewareType
+
+
+@pytest.fixture(scope="module")
+def routes():
+    # the methods aren't hashable, the routes are keyed by the name of their method
+    return {(str(route.method), route.path): route for route in app.routes()}
+
+
+def test_routes(routes):
+    route = routes[("HttpMethod.GET", "/sync/str")]
+    assert not route.is_const
+    assert not route.is_parameterized
+    assert route.params == []
+    assert route.middlewares == []
+    assert ("HttpMethod.POST", "/sync/str") not in routes
+    assert ("HttpMethod.POST", "/sync/body") in routes
+
+
+def test_const_routes(routes):
+    assert routes[("HttpMethod.GET", "/sync/str/const")].is_const
+
+
+@pytest.mark.parametrize(
+    "path,params",
+    [
+        ("/sync/param/:id", ["id"]),
+        ("/sync/extra/*extra", ["extra"]),
+    ],
+)
+def test_parameterized_routes(routes, path: str, params):
+    route = routes[("HttpMethod.GET", path)]
+    assert route.is_parameterized
+    assert route.params == params
+
+
+def test_route_middlewares(routes):
+    middlewares = routes[("HttpMethod.GET", "/sync/middlewares")].middlewares
+    assert middlewares == [MiddlewareType.BEFORE_REQUEST, MiddlewareType.AFTER_REQUEST]
+    route = routes[("HttpMethod.GET", "/sync/middlewares/matched_route/:id")]
+    assert route.middlewares == [MiddlewareType.BEFORE_REQUEST]
+
+
+def test_routes_repr(routes):
+    route = routes[("HttpMethod.GET", "/sync/param/:id")]
+    assert repr(route) == (
+        'RouteInfo(method=GET, path="/sync/param/:id", is_const=false, middlewares=[])'
+    )
@@ -0,0 +1,44 @@
+import pytest
+
+from helpers.http_methods_helpers import get
+from robyn import SecurityHeaders
+
+
+@pytest.mark.benchmark
+def test_security_headers(session):
+    r = get("/sync/str")
+    assert r.headers["X-Content-Type-Options"] == "nosniff"
+    assert r.headers["X-Frame-Options"] == "DENY"
+    assert (
+        r.headers["Strict-Transport-Security"] == "max-age=31536000; includeSubDomains"
+    )
+    assert r.headers["Referrer-Policy"] == "strict-origin-when-cross-origin"
+    # disabled headers must not be sent
+    assert "Content-Security-Policy" not in r.headers
+    assert "Expect-CT" not in r.headers
+
+
+@pytest.mark.benchmark
+def test_cross_origin_isolation_headers(session):
+    r = get("/sync/str")
+    assert r.headers["Cross-Origin-Opener-Policy"] == "same-origin"
+    assert r.headers["Cross-Origin-Embedder-Policy"] == "require-corp"
+
+
+def test_cross_origin_isolation_disabled_by_default():
+    keys = [header.key for header in SecurityHeaders().as_headers()]
+    assert "Cross-Origin-Opener-Policy" not in keys
+    assert "Cross-Origin-Embedder-Policy" not in keys
+
+
+@pytest.mark.benchmark
+def test_security_headers_on_not_found(session):
+    r = get("/not/a/route", expected_status_code=404)
+    assert r.headers["X-Content-Type-Options"] == "nosniff"
+
+
+@pytest.mark.benchmark
+def test_security_headers_override(session):
+    r = get("/sync/security_headers/override")
+    assert r.headers["X-Frame-Options"] == "SAMEORIGIN"
+    assert r.headers["X-Content-Type-Options"] == "nosniff"
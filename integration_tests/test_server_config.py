@@ -0,0 +1,115 @@
+import os
+
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+from robyn.robyn import Server
+
+# the config given to the server takes precedence over the environment variables
+server_config_server = with_ephemeral_server(
+    "server_config", env={"ROBYN_MAX_PAYLOAD_SIZE": "4"}, app="server_config_app.py"
+)
+
+
+@pytest.mark.benchmark
+@server_config_server
+def test_server_config(ephemeral_server):
+    url = f"http://127.0.0.1:{ephemeral_server.port}/body"
+    r = requests.post(url, data=b"a" * 16)
+    assert r.status_code == 200
+    assert r.text == "a" * 16
+    assert r.headers["server"] == "configured/1.0"
+
+    r = requests.post(url, data=b"a" * 17)
+    assert r.status_code == 413
+
+
+@pytest.mark.benchmark
+@server_config_server
+def test_default_headers(ephemeral_server):
+    url = f"http://127.0.0.1:{ephemeral_server.port}"
+    r = requests.post(f"{url}/body", data=b"a")
+    assert r.headers["X-Version"] == "1.2"
+    assert r.headers["Cache-Control"] == "no-cache"
+
+    # the headers set by the handler win over the default ones
+    r = requests.get(f"{url}/cached")
+    assert r.headers["Cache-Control"] == "max-age=60"
+    assert r.headers["X-Version"] == "1.2"
+
+    # the error responses get them too
+    r = requests.get(f"{url}/missing")
+    assert r.status_code == 404
+    assert r.headers["X-Version"] == "1.2"
+    r = requests.post(f"{url}/body", data=b"a" * 17)
+    assert r.status_code == 413
+    assert r.headers["X-Version"] == "1.2"
+
+
+@pytest.mark.parametrize(
+    "config",
+    [
+        {"max_payload_sizes": 16},
+        {"max_payload_size": "a lot"},
+        {"brotli_quality": 12},
+        {"server_header": "not\nvalid"},
+        {"unknown_host_status": 404},
+        {"deadline_header": "not a header"},
+        {"drain_timeout": -1},
+        {"slow_request_threshold": "slow"},
+        {"drain_retry_after": -1},
+        {"default_headers": "X-Version: 1.2"},
+        {"default_headers": {"not a name": "1.2"}},
+        {"default_headers": {"X-Version": "not\nvalid"}},
+        {"refuse_absolute_form": "maybe"},
+        {"max_connections_per_ip": -1},
+        {"keep_alive": "sometimes"},
+        {"static_max_age": -1},
+        {"favicon_fallback": "maybe"},
+        {"max_headers": 0},
+        {"max_headers": 100},
+        {"conflicting_length": "ignore"},
+        {"overload_page": "missing/overload.html"},
+        {"redacted_headers": "X-Api-Key, not a name"},
+        {"accept_rate": -1},
+        {"accept_rate": 100, "accept_burst": 0},
+        {"absolute_redirects": "maybe"},
+    ],
+)
+def test_invalid_server_config(config):
+    with pytest.raises(ValueError):
+        Server(config)
+
+
+def test_server_config_defaults():
+    Server()
+    Server({})
+    Server({"keep_alive_timeout": 0, "trace_echo": True, "metrics_path": "/metrics"})
+    Server({"allowed_hosts": "example.com", "unknown_host_status": 400})
+    Server({"drain_timeout": 10_000})
+    Server({"file_breaker_threshold": 5, "file_breaker_cooldown": 1_000})
+    Server({"slow_request_threshold": 500})
+    Server({"overload_retry_after": 0, "drain_retry_after": 30})
+    Server({"default_headers": {"X-Version": "1.2"}})
+    Server({"refuse_absolute_form": True})
+    Server({"max_connections_per_ip": 10})
+    Server({"keep_alive": False})
+    Server({"static_max_age": 3600})
+    Server({"favicon_fallback": False, "favicon": "static/favicon.ico"})
+    Server({"max_headers": 64})
+    Server({"conflicting_length": "strip"})
+    Server({"redacted_headers": "X-Api-Key, X-Session"})
+    Server({"accept_rate": 100, "accept_burst": 20})
+    Server({"absolute_redirects": True, "trust_proxy_headers": True})
+
+
+def test_default_headers_environment_variable():
+    os.environ["ROBYN_DEFAULT_HEADERS"] = "X-Version: 1.2\nCache-Control: no-cache\n"
+    try:
+        Server()
+        os.environ["ROBYN_DEFAULT_HEADERS"] = "X-Version"
+        with pytest.raises(ValueError):
+            Server()
+    finally:
+        del os.environ["ROBYN_DEFAULT_HEADERS"]
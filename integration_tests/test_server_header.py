@@ -0,0 +1,47 @@
+import pytest
+import requests
+
+from base_routes import app
+from helpers.http_methods_helpers import BASE_URL
+from helpers.server_helpers import with_ephemeral_server
+from robyn.robyn import get_version
+
+server_header_server = with_ephemeral_server(
+    "server_header", env={"ROBYN_SERVER_HEADER": "custom-server/1.0"}
+)
+no_server_header_server = with_ephemeral_server(
+    "no_server_header", env={"ROBYN_SERVER_HEADER": ""}
+)
+
+
+# the files of the directories are served without the global response headers of the app
+
+
+@pytest.mark.benchmark
+def test_default_server_header(session):
+    res = requests.get(f"{BASE_URL}/static_site/")
+    assert res.status_code == 200
+    assert res.headers["server"] == f"robyn/{get_version()}"
+
+
+@pytest.mark.benchmark
+@server_header_server
+def test_custom_server_header(ephemeral_server):
+    res = requests.get(f"http://127.0.0.1:{ephemeral_server.port}/static_site/")
+    assert res.status_code == 200
+    assert res.headers["server"] == "custom-server/1.0"
+
+
+@pytest.mark.benchmark
+@no_server_header_server
+def test_disabled_server_header(ephemeral_server):
+    res = requests.get(f"http://127.0.0.1:{ephemeral_server.port}/static_site/")
+    assert res.status_code == 200
+    assert "server" not in res.headers
+
+
+def test_server_header_overridden_by_handler():
+    client = app.test_client()
+    assert client.get("/sync/str").headers["server"] == f"robyn/{get_version()}"
+    res = client.get("/sync/response/server_header")
+    assert res.headers["server"] == "handler"
@@ -0,0 +1,44 @@
+import time
+
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+
+slow_request_server = with_ephemeral_server(
+    "slow_request", env={"ROBYN_SLOW_REQUEST_THRESHOLD": "500"}
+)
+
+
+def read_logs(logs) -> str:
+    logs.seek(0)
+    return logs.read().decode()
+
+
+@pytest.mark.benchmark
+@slow_request_server
+def test_slow_requests_are_logged(ephemeral_server):
+    port, logs = ephemeral_server.port, ephemeral_server.logs
+    r = requests.get(f"http://127.0.0.1:{port}/async/slow")
+    assert r.status_code == 200
+
+    deadline = time.time() + 2
+    while "Slow request: GET /async/slow" not in read_logs(logs):
+        assert time.time() < deadline, "the slow request isn't logged"
+        time.sleep(0.1)
+    assert "(route /async/slow) answered with 200 in" in read_logs(logs)
+    assert "its first byte in" in read_logs(logs)
+
+
+@pytest.mark.benchmark
+@slow_request_server
+def test_fast_requests_are_not_logged(ephemeral_server):
+    port, logs = ephemeral_server.port, ephemeral_server.logs
+    r = requests.get(f"http://127.0.0.1:{port}/sync/str")
+    assert r.status_code == 200
+    r = requests.get(f"http://127.0.0.1:{port}/not/a/route")
+    assert r.status_code == 404
+
+    time.sleep(0.2)
+    assert "GET /sync/str" not in read_logs(logs)
+    assert "GET /not/a/route" not in read_logs(logs)
@@ -0,0 +1,26 @@
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+
+socket_buffer_sizes_server = with_ephemeral_server(
+    "socket_buffer_sizes",
+    env={
+        "ROBYN_SOCKET_RECV_BUFFER_SIZE": "4096",
+        "ROBYN_SOCKET_SEND_BUFFER_SIZE": "4096",
+    },
+)
+
+
+@pytest.mark.benchmark
+@socket_buffer_sizes_server
+def test_responses_with_small_socket_buffers(ephemeral_server):
+    base_url = f"http://127.0.0.1:{ephemeral_server.port}"
+
+    res = requests.get(f"{base_url}/sync/large_response")
+    assert res.status_code == 200
+    assert res.text == "a" * 5_000_000
+
+    res = requests.post(f"{base_url}/sync/body", data=b"robyn" * 100_000)
+    assert res.status_code == 200
+    assert res.content == b"robyn" * 100_000
@@ -9,8 +9,36 @@ def test_404_status_code(session):
 
 @pytest.mark.benchmark
 def test_404_not_found(session):
+    # the page added for the 404 is missing, so the built-in one is used
     r = get("/real/404", expected_status_code=404)
-    assert r.text == "Not found"
+    assert r.headers["Content-Type"] == "text/html; charset=utf-8"
+    assert "<title>404 Not Found</title>" in r.text
+    assert "<link" not in r.text and "<script" not in r.text
+
+
+@pytest.mark.benchmark
+def test_404_of_a_handler_keeps_its_body(session):
+    r = get("/404", expected_status_code=404)
+    assert r.text == "not found"
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize(
+    "path, status_code, title",
+    [
+        ("/static_site/missing.txt", 404, "404 Not Found"),
+        ("/static_site/empty/", 404, "404 Not Found"),
+        ("/static_site_forbidden/empty/", 403, "403 Forbidden"),
+    ],
+)
+def test_error_pages_of_the_static_files(
+    path: str, status_code: int, title: str, session
+):
+    # the static files don't go through the global middlewares
+    r = get(path, should_check_response=False)
+    assert r.status_code == status_code
+    assert r.headers["Content-Type"] == "text/html; charset=utf-8"
+    assert f"<title>{title}</title>" in r.text
 
 
 @pytest.mark.benchmark
@@ -21,4 +49,6 @@ def test_202_status_code(session):
 @pytest.mark.benchmark
 @pytest.mark.parametrize("function_type", ["sync", "async"])
 def test_sync_500_internal_server_error(function_type: str, session):
-    get(f"/{function_type}/raise", expected_status_code=500)
+    r = get(f"/{function_type}/raise", expected_status_code=500)
+    assert r.headers["Content-Type"] == "text/html"
+    assert "Something went wrong on our side." in r.text
@@ -0,0 +1,116 @@
+import hashlib
+
+import pytest
+import requests
+
+from base_routes import app
+from helpers.http_methods_helpers import get
+from helpers.network_helpers import send_raw_request
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+def test_streamed_response(session, function_type: str):
+    r = get(f"/{function_type}/response/stream")
+    assert r.headers["Transfer-Encoding"] == "chunked"
+    assert "Content-Length" not in r.headers
+    assert r.text == "".join(f"{function_type} chunk {i}\n" for i in range(3))
+
+
+@pytest.mark.benchmark
+def test_streamed_response_headers(session):
+    r = get("/async/response/stream")
+    assert r.headers["async"] == "stream"
+
+
+@pytest.mark.benchmark
+def test_streamed_response_sends_the_chunks_as_yielded(session):
+    response = send_raw_request(
+        b"GET /sync/response/stream HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+    )
+    body = response.split(b"\r\n\r\n", 1)[1]
+    # each yielded chunk is its own chunk of the encoding
+    assert body == b"".join(
+        b"%X\r\n%s\r\n" % (len(chunk), chunk)
+        for chunk in (b"sync chunk %d\n" % i for i in range(3))
+    ) + b"0\r\n\r\n"
+
+
+TRAILERS_BODY = "".join(f"sync chunk {i}\n" for i in range(3))
+CHECKSUM = hashlib.sha256(TRAILERS_BODY.encode()).hexdigest()
+
+
+@pytest.mark.benchmark
+def test_streamed_response_trailers(session):
+    response = send_raw_request(
+        b"GET /sync/response/stream/trailers HTTP/1.1\r\nHost: localhost\r\n"
+        + b"Connection: close\r\n\r\n"
+    )
+    head, body = response.split(b"\r\n\r\n", 1)
+    assert b"\r\ntrailer: Checksum\r\n" in head
+    assert head.count(b"transfer-encoding: chunked") == 1
+    # the empty chunks are left out, the undeclared trailers too
+    assert body == b"".join(
+        b"%X\r\n%s\r\n" % (len(chunk), chunk)
+        for chunk in (b"sync chunk %d\n" % i for i in range(3))
+    ) + b"0\r\nchecksum: %s\r\n\r\n" % CHECKSUM.encode()
+
+
+@pytest.mark.benchmark
+def test_streamed_response_trailers_keep_alive(session):
+    request = (
+        b"GET /sync/response/stream/trailers HTTP/1.1\r\nHost: localhost\r\n\r\n"
+    )
+    response = send_raw_request(
+        request + request.replace(b"\r\n\r\n", b"\r\nConnection: close\r\n\r\n")
+    )
+    # the second response comes after the trailers of the first one
+    assert response.count(b"HTTP/1.1 200 OK") == 2
+    assert response.count(b"0\r\nchecksum: %s\r\n\r\n" % CHECKSUM.encode()) == 2
+
+
+@pytest.mark.benchmark
+def test_streamed_response_trailers_ignored(session):
+    # the clients that don't read the trailers get the body
+    r = get("/sync/response/stream/trailers")
+    assert r.text == TRAILERS_BODY
+
+
+@pytest.mark.benchmark
+def test_streamed_response_trailers_http_1_0(session):
+    response = send_raw_request(
+        b"GET /sync/response/stream/trailers HTTP/1.0\r\n\r\n"
+    )
+    assert response.startswith(b"HTTP/1.0 200 OK")
+    assert b"checksum" not in response
+
+
+@pytest.mark.benchmark
+def test_streamed_const_response(session):
+    # the iterator of a const route is read once, when the route is added
+    for _ in range(2):
+        r = get("/sync/response/stream/const")
+        assert r.text == "".join(f"sync const chunk {i}\n" for i in range(3))
+        assert r.headers["Content-Length"] == str(len(r.text))
+
+
+@pytest.mark.benchmark
+def test_streamed_response_error(session):
+    # the status code can't be changed once the chunks are sent, so the connection is closed
+    # to cut the response short, before or after the head depending on what is buffered
+    with pytest.raises(
+        (requests.exceptions.ChunkedEncodingError, requests.exceptions.ConnectionError)
+    ):
+        get("/sync/response/stream/error")
+
+
+def test_streamed_response_test_client():
+    res = app.test_client().get("/sync/response/stream")
+    assert res.status_code == 200
+    assert res.body == "".join(f"sync chunk {i}\n" for i in range(3))
+
+
+def test_streamed_response_trailers_test_client():
+    res = app.test_client().get("/sync/response/stream/trailers")
+    assert res.body == TRAILERS_BODY
+    assert res.trailers["Checksum"] == CHECKSUM
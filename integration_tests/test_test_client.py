@@ -0,0 +1,67 @@
+import json
+
+import pytest
+
+from base_routes import app
+
+
+@pytest.fixture(scope="module")
+def client():
+    return app.test_client()
+
+
+@pytest.mark.parametrize(
+    "route,expected_text",
+    [
+        ("/sync/str", "sync str get"),
+        ("/async/str", "async str get"),
+        ("/sync/str/const", "sync str const get"),
+        ("/async/str/const", "async str const get"),
+    ],
+)
+def test_test_client_get(client, route: str, expected_text: str):
+    res = client.get(route)
+    assert res.status_code == 200
+    assert res.body == expected_text
+
+
+def test_test_client_queries(client):
+    res = client.get("/sync/queries?hello=robyn")
+    assert res.status_code == 200
+    assert json.loads(res.body) == {"hello": "robyn"}
+
+
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+def test_test_client_middlewares(client, function_type: str):
+    res = client.get(f"/{function_type}/middlewares")
+    assert res.status_code == 200
+    assert res.headers["global_after"] == "global_after_request"
+    assert res.headers["after"] == f"{function_type}_after_request"
+    assert res.body == f"{function_type} middlewares after"
+
+
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+def test_test_client_body(client, function_type: str):
+    res = client.post(f"/{function_type}/body", body="hello robyn")
+    assert res.status_code == 200
+    assert res.body == "hello robyn"
+
+    res = client.request("put", f"/{function_type}/body", body=b"hello robyn")
+    assert res.status_code == 200
+    assert res.body == "hello robyn"
+
+
+def test_test_client_headers(client):
+    res = client.get("/sync/middlewares/401", headers={"x-robyn": "test"})
+    assert res.status_code == 401
+    assert res.body == "sync before request 401"
+
+
+def test_test_client_not_found(client):
+    res = client.get("/not_a_route")
+    assert res.status_code == 404
+
+
+def test_test_client_invalid_method(client):
+    with pytest.raises(ValueError):
+        client.request("not a method", "/sync/str")
@@ -0,0 +1,101 @@
+import time
+from datetime import datetime, timedelta, timezone
+
+import pytest
+import requests
+
+from helpers.http_methods_helpers import get
+from helpers.server_helpers import with_ephemeral_server
+
+deadline_server = with_ephemeral_server(
+    "deadline", env={"ROBYN_DEADLINE_HEADER": "X-Request-Deadline"}
+)
+
+
+@pytest.mark.benchmark
+def test_handler_timeout(session):
+    r = get("/async/timeout", expected_status_code=504)
+    assert r.text == "Gateway Timeout"
+    # the connection is still usable after a handler was cancelled
+    r = get("/async/timeout/in_time")
+    assert r.text == "in time"
+
+
+@pytest.mark.benchmark
+def test_timeout_raised_by_handler(session):
+    # only a handler cancelled at its timeout is answered with a 504
+    get("/async/timeout/raised", expected_status_code=500)
+
+
+def deadline_get(port: int, route: str, deadline: str) -> requests.Response:
+    return requests.get(
+        f"http://127.0.0.1:{port}{route}", headers={"X-Request-Deadline": deadline}
+    )
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("deadline", ["100m", "100000u"])
+@deadline_server
+def test_relative_deadline(deadline: str, ephemeral_server):
+    r = deadline_get(ephemeral_server.port, "/async/deadline", deadline)
+    assert r.status_code == 504
+    assert r.text == "Gateway Timeout"
+
+
+@pytest.mark.benchmark
+@deadline_server
+def test_relative_deadline_in_time(ephemeral_server):
+    start = time.time()
+    r = deadline_get(ephemeral_server.port, "/async/deadline", "5S")
+    assert r.status_code == 200
+    assert start + 5 <= float(r.text) <= time.time() + 5
+
+
+@pytest.mark.benchmark
+@deadline_server
+def test_timestamp_deadline(ephemeral_server):
+    deadline = datetime.now(timezone.utc) + timedelta(seconds=5)
+    r = deadline_get(ephemeral_server.port, "/async/deadline", deadline.isoformat())
+    assert r.status_code == 200
+    assert abs(float(r.text) - deadline.timestamp()) < 0.001
+
+    deadline = datetime.now(timezone.utc) + timedelta(milliseconds=100)
+    r = deadline_get(ephemeral_server.port, "/async/deadline", deadline.isoformat())
+    assert r.status_code == 504
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("function_type", ["sync", "async"])
+@deadline_server
+def test_passed_deadline(function_type: str, ephemeral_server):
+    # a sync handler can't be interrupted, it isn't run at all
+    route = f"/{function_type}/deadline"
+    r = deadline_get(ephemeral_server.port, route, "2020-01-01T00:00:00Z")
+    assert r.status_code == 504
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("deadline", ["100m", "2020-01-01T00:00:00Z"])
+@deadline_server
+def test_fallback_deadline(deadline: str, ephemeral_server):
+    r = deadline_get(ephemeral_server.port, "/async/fallback/slow", deadline)
+    assert r.status_code == 504
+
+    r = deadline_get(ephemeral_server.port, "/async/fallback/slow", "5S")
+    assert r.status_code == 202
+
+
+@pytest.mark.benchmark
+@pytest.mark.parametrize("deadline", ["soon", "1.5S", "123456789S", "2020-01-01"])
+@deadline_server
+def test_malformed_deadline(deadline: str, ephemeral_server):
+    r = deadline_get(ephemeral_server.port, "/sync/deadline", deadline)
+    assert r.status_code == 200
+    assert r.text == "None"
+
+
+@pytest.mark.benchmark
+def test_deadline_header_not_configured(session):
+    r = deadline_get(8080, "/sync/deadline", "2020-01-01T00:00:00Z")
+    assert r.status_code == 200
+    assert r.text == "None"
@@ -0,0 +1,42 @@
+import pytest
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+
+trace_echo_server = with_ephemeral_server(
+    "trace_echo", env={"ROBYN_TRACE_ECHO": "true"}
+)
+
+
+def split_response(response: bytes):
+    head, body = response.split(b"\r\n\r\n", 1)
+    return head.lower(), body
+
+
+@pytest.mark.benchmark
+def test_trace_is_disabled_by_default(session):
+    response = send_raw_request(
+        b"TRACE /sync/str HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+    )
+    assert response.startswith(b"HTTP/1.1 405 Method Not Allowed")
+    head, body = split_response(response)
+    assert b"\r\nallow: " in head
+    assert b"x-trace" not in body
+
+
+@pytest.mark.benchmark
+@trace_echo_server
+def test_trace_echoes_the_request_head(ephemeral_server):
+    response = send_raw_request(
+        b"TRACE /sync/str?a=b HTTP/1.1\r\nHost: 127.0.0.1\r\nX-Trace: robyn\r\n"
+        b"Cookie: session=secret\r\nAuthorization: Bearer secret\r\nConnection: close\r\n\r\n",
+        port=ephemeral_server.port,
+    )
+    assert response.startswith(b"HTTP/1.1 200 OK")
+    head, body = split_response(response)
+    assert b"\r\ncontent-type: message/http" in head
+    assert body.startswith(b"TRACE /sync/str?a=b HTTP/1.1\r\n")
+    assert b"\r\nx-trace: robyn\r\n" in body
+    assert body.endswith(b"\r\n\r\n")
+    # the credentials are never echoed
+    assert b"secret" not in body
@@ -0,0 +1,58 @@
+import socket
+import time
+
+import pytest
+
+from helpers.network_helpers import send_raw_request
+from helpers.server_helpers import with_ephemeral_server
+
+server_config_server = with_ephemeral_server(
+    "server_config", env={"ROBYN_MAX_PAYLOAD_SIZE": "4"}, app="server_config_app.py"
+)
+
+
+def read_until(sock: socket.socket, end: bytes) -> bytes:
+    output = b""
+    while not output.endswith(end):
+        chunk = sock.recv(65536)
+        if not chunk:
+            break
+        output += chunk
+    return output
+
+
+@pytest.mark.benchmark
+def test_tunnel(session):
+    with socket.create_connection(("127.0.0.1", 8080), timeout=5) as sock:
+        sock.sendall(
+            b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n"
+        )
+        head, greeting = read_until(sock, b":443\n").split(b"\r\n\r\n", 1)
+        assert head.startswith(b"HTTP/1.1 200 Connection Established\r\n")
+        # the bytes of the tunnel are sent as they are, without any framing
+        assert b"transfer-encoding" not in head.lower()
+        assert greeting == b"tunnel to example.com:443\n"
+
+        sock.sendall(b"hello")
+        assert read_until(sock, b"HELLO") == b"HELLO"
+        sock.sendall(b"robyn")
+        assert read_until(sock, b"ROBYN") == b"ROBYN"
+
+        # the handler returns once the client stopped sending, closing the connection
+        sock.shutdown(socket.SHUT_WR)
+        assert sock.recv(65536) == b""
+
+
+@pytest.mark.benchmark
+@server_config_server
+def test_tunnel_without_handler(ephemeral_server):
+    start = time.time()
+    response = send_raw_request(
+        b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\nhello",
+        port=ephemeral_server.port,
+    )
+    assert response.startswith(b"HTTP/1.1 405 Method Not Allowed")
+    assert b"allow: GET, POST, PUT, DELETE, PATCH, HEAD, OPTIONS, TRACE\r\n" in response
+    assert b"connection: close" in response.lower()
+    # the rest of the connection isn't read as the body of the request
+    assert time.time() - start < 1
@@ -0,0 +1,19 @@
+import pytest
+
+from helpers.http_methods_helpers import get
+
+
+@pytest.mark.benchmark
+def test_vary_added_by_handler(session):
+    r = get("/sync/vary")
+    assert r.headers["Vary"] == "Accept-Language, Cookie"
+
+
+@pytest.mark.benchmark
+def test_vary_merged_with_content_negotiation(session):
+    r = get(
+        "/sync/file/response/precompressed/vary", headers={"Accept-Encoding": "gzip"}
+    )
+    # duplicates are dropped and a single header is sent
+    assert r.headers["Vary"] == "Accept-Language, Accept-Encoding"
+    assert r.headers["Content-Encoding"] == "gzip"
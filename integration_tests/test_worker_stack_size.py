@@ -0,0 +1,30 @@
+import pytest
+import requests
+
+from helpers.server_helpers import with_ephemeral_server
+
+worker_stack_size_server = with_ephemeral_server(
+    "worker_stack_size",
+    env={"ROBYN_WORKER_STACK_SIZE": str(64 * 1024 * 1024), "ROBYN_WORKERS": "2"},
+)
+
+
+@pytest.mark.benchmark
+@worker_stack_size_server
+def test_handler_using_more_than_the_default_stack(ephemeral_server):
+    port = ephemeral_server.port
+    res = requests.get(f"http://127.0.0.1:{port}/sync/deep_recursion")
+    assert res.status_code == 200
+    assert res.text == "100002"
+
+
+@worker_stack_size_server
+def test_sync_handlers_reuse_the_thread_of_their_worker(ephemeral_server):
+    port = ephemeral_server.port
+    threads = set()
+    for _ in range(20):
+        res = requests.get(f"http://127.0.0.1:{port}/sync/thread")
+        assert res.status_code == 200
+        threads.add(res.text)
+    # each of the 2 workers runs its sync handlers on a thread of its own, started once
+    assert 1 <= len(threads) <= 2
@@ -0,0 +1,70 @@
+import os
+import re
+import time
+
+import pytest
+
+from helpers.server_helpers import with_ephemeral_server
+
+debug_logs_server = with_ephemeral_server("debug_logs", args=["--log-level", "DEBUG"])
+workers_env_server = with_ephemeral_server(
+    "workers_env", env={"ROBYN_WORKERS": "3"}, args=["--log-level", "DEBUG"]
+)
+# the number of workers given on the command line wins over the environment variable
+workers_override_server = with_ephemeral_server(
+    "workers_override",
+    env={"ROBYN_WORKERS": "3"},
+    args=["--log-level", "DEBUG", "--workers", "2"],
+)
+workers_invalid_env_server = with_ephemeral_server(
+    "workers_invalid_env", env={"ROBYN_WORKERS": "many"}, args=["--log-level", "DEBUG"]
+)
+
+
+def worker_count(logs) -> int:
+    # the number is logged by the server as it starts, after its port
+    deadline = time.time() + 2
+    while True:
+        logs.seek(0)
+        match = re.search(rb"The number of workers is (\d+)", logs.read())
+        if match is not None:
+            return int(match.group(1))
+        assert time.time() < deadline, "the number of workers isn't logged"
+        time.sleep(0.1)
+
+
+def cores() -> int:
+    if hasattr(os, "sched_getaffinity"):
+        return len(os.sched_getaffinity(0))
+    return os.cpu_count() or 1
+
+
+@pytest.mark.benchmark
+@debug_logs_server
+def test_workers_default_to_the_cores(ephemeral_server):
+    logs = ephemeral_server.logs
+    assert worker_count(logs) == cores()
+
+
+@pytest.mark.benchmark
+@workers_env_server
+def test_workers_from_the_environment(ephemeral_server):
+    assert worker_count(ephemeral_server.logs) == 3
+
+
+@pytest.mark.benchmark
+@workers_override_server
+def test_workers_given_on_the_command_line(ephemeral_server):
+    assert worker_count(ephemeral_server.logs) == 2
+
+
+@pytest.mark.benchmark
+@workers_invalid_env_server
+def test_workers_invalid_environment_variable(ephemeral_server):
+    # the invalid number is skipped for the next source, the cores
+    assert worker_count(ephemeral_server.logs) == cores()
+    ephemeral_server.logs.seek(0)
+    assert (
+        b"Ignoring the environment variable ROBYN_WORKERS - `many` isn't a positive "
+        b"number of workers" in ephemeral_server.logs.read()
+    )
@@ -2,7 +2,7 @@ import asyncio
 import logging
 import multiprocess as mp
 import os
-from typing import Callable, List, Optional, Tuple
+from typing import Any, Callable, Dict, Iterable, List, Optional, Tuple, Union
 from nestd import get_all_nested
 
 from robyn.argument_parser import Config
@@ -12,11 +12,28 @@ from robyn.reloader import setup_reloader
 from robyn.env_populator import load_vars
 from robyn.events import Events
 from robyn.logger import logger
-from robyn.processpool import run_processes
-from robyn.responses import jsonify, serve_file, serve_html
-from robyn.robyn import FunctionInfo, HttpMethod, Request, Response, get_version
-from robyn.router import MiddlewareRouter, MiddlewareType, Router, WebSocketRouter
-from robyn.types import Directory, Header
+from robyn.processpool import init_server, run_processes
+from robyn.responses import jsonify, negotiate, serve_file, serve_html
+from robyn.robyn import (
+    FunctionInfo,
+    HttpMethod,
+    Request,
+    Response,
+    RouteInfo,
+    Server,
+    TestClient,
+    UploadedFile,
+    get_version,
+    offload,
+)
+from robyn.router import (
+    MiddlewareRouter,
+    MiddlewareType,
+    Router,
+    WebSocketRouter,
+    join_url_path,
+)
+from robyn.types import CacheControl, Directory, Header, SecurityHeaders
 from robyn import status_codes
 from robyn.ws import WS
 
@@ -52,10 +69,14 @@ class Robyn:
         self.web_socket_router = WebSocketRouter()
         self.request_headers: List[Header] = []  # This needs a better type
         self.response_headers: List[Header] = []  # This needs a better type
+        self.default_response_headers: List[Header] = []
         self.directories: List[Directory] = []
+        self.error_pages: Dict[int, str] = {}
+        self.mime_types: Dict[str, str] = {}
         self.event_handlers = {}
-        self.exception_handler: Optional[Callable] = None
         self.authentication_handler: Optional[AuthenticationHandler] = None
+        self.tunnel_handler: Optional[FunctionInfo] = None
+        self.log_redactor: Optional[Callable[[str], str]] = None
 
     def _add_route(
         self,
@@ -64,6 +85,11 @@ class Robyn:
         handler: Callable,
         is_const: bool = False,
         auth_required: bool = False,
+        timeout: Optional[float] = None,
+        stream_body: bool = False,
+        stream_files: bool = False,
+        max_body_size: Optional[int] = None,
+        cache_control: Optional[str] = None,
     ):
         """
         This is base handler for all the route decorators
@@ -73,6 +99,11 @@ class Robyn:
         :param handler function: represents the sync or async function passed as a handler for the route
         :param is_const bool: represents if the handler is a const function or not
         :param auth_required bool: represents if the route needs authentication or not
+        :param timeout float|None: the time in seconds after which an async handler is cancelled and a 504 is returned
+        :param stream_body bool: gives the body to an async handler in chunks in request.stream, instead of reading it in request.body
+        :param stream_files bool: writes the files of a multipart form to temporary files as they are received, given in request.files with the fields in request.form_data
+        :param max_body_size int|None: the max size of the request body in bytes, instead of ROBYN_MAX_PAYLOAD_SIZE
+        :param cache_control str|None: the Cache-Control of the responses that don't set one, e.g. CacheControl.NO_STORE
         """
 
         """ We will add the status code here only
@@ -81,7 +112,15 @@ class Robyn:
             self.middleware_router.add_auth_middleware(endpoint)(handler)
 
         return self.router.add_route(
-            route_type, endpoint, handler, is_const, self.exception_handler
+            route_type,
+            endpoint,
+            handler,
+            is_const,
+            timeout,
+            stream_body,
+            stream_files,
+            max_body_size,
+            cache_control,
         )
 
     def before_request(self, endpoint: Optional[str] = None) -> Callable[..., None]:
@@ -110,19 +149,77 @@ class Robyn:
         self,
         route: str,
         directory_path: str,
-        index_file: Optional[str] = None,
+        index_file: Union[str, List[str], None] = "index.html",
         show_files_listing: bool = False,
+        show_hidden_files: bool = False,
+        missing_index_status: int = 404,
+        cache_control: Optional[str] = None,
     ):
+        """
+        Serves the files of a directory under the route
+
+        :param route str: the route to serve the files at. e.g. "/static"
+        :param directory_path str: the path of the directory to serve
+        :param index_file str | List[str] | None: the file served for a request to a directory, or a list of them tried in order. e.g. ["index.html", "index.htm"]
+        :param show_files_listing bool: lists the entries of the directories without an index file
+        :param show_hidden_files bool: lists and serves the dotfiles, which are hidden by default
+        :param missing_index_status int: the status of the requests to a directory without an index file that isn't listed, 404 or 403
+        :param cache_control str|None: the Cache-Control of the files, instead of the max-age of ROBYN_STATIC_MAX_AGE. e.g. CacheControl.IMMUTABLE
+        """
+        if index_file is None:
+            index_files = []
+        elif isinstance(index_file, str):
+            index_files = [index_file]
+        else:
+            index_files = list(index_file)
         self.directories.append(
-            Directory(route, directory_path, show_files_listing, index_file)
+            Directory(
+                route,
+                directory_path,
+                show_files_listing,
+                show_hidden_files,
+                index_files,
+                missing_index_status,
+                cache_control,
+            )
         )
 
+    def add_error_page(self, status_code: int, file_path: str) -> None:
+        """
+        Serves the file instead of the default body when the server produces an error response with the status code
+
+        :param status_code int: the status code of the error response. e.g. 404, 500
+        :param file_path str: the path of the page to serve
+        """
+        self.error_pages[status_code] = file_path
+
+    def add_mime_type(self, extension: str, mime_type: str) -> None:
+        """
+        Sets the content type of the files with the extension, over the default one of
+        the extension. It applies to the files served by the handlers and the ones of the
+        directories added with add_directory.
+
+        :param extension str: the file extension. e.g. "mjs" or ".mjs"
+        :param mime_type str: the content type of the files. e.g. "text/javascript"
+        """
+        self.mime_types[extension] = mime_type
+
     def add_request_header(self, key: str, value: str) -> None:
         self.request_headers.append(Header(key, value))
 
     def add_response_header(self, key: str, value: str) -> None:
         self.response_headers.append(Header(key, value))
 
+    def add_security_headers(
+        self, security_headers: SecurityHeaders = SecurityHeaders()
+    ) -> None:
+        """
+        Adds the security headers to every response, unless the handler has already set them
+
+        :param security_headers SecurityHeaders: the security headers to add, each can be disabled by setting it to None
+        """
+        self.default_response_headers.extend(security_headers.as_headers())
+
     def add_web_socket(self, endpoint: str, ws: WS) -> None:
         self.web_socket_router.add_route(endpoint, ws)
 
@@ -140,11 +237,45 @@ class Robyn:
     def shutdown_handler(self, handler: Callable) -> None:
         self._add_event_handler(Events.SHUTDOWN, handler)
 
-    def start(self, url: str = "127.0.0.1", port: int = 8080):
+    def tunnel(self, handler: Callable):
+        """
+        The @app.tunnel decorator to handle the CONNECT requests, e.g. to proxy them. The server
+        answers them with a 200 Connection Established, then the async handler reads and writes
+        the raw connection with the tunnel, until it returns. Without a handler, the CONNECT
+        requests are refused with a 405.
+        e.g. async def handler(request, tunnel): await tunnel.write(await tunnel.read())
+
+        :param handler Callable: the async handler taking the request and its tunnel
+        """
+        if not asyncio.iscoroutinefunction(handler):
+            raise ValueError("The tunnel handler must be an async function")
+        self.tunnel_handler = FunctionInfo(handler, True, 2)
+        return handler
+
+    def redact_logs(self, redactor: Callable[[str], str]):
+        """
+        The @app.redact_logs decorator to redact the messages logged by the server, e.g. to mask
+        the tokens of the query strings. The values of the Authorization, Cookie and Set-Cookie
+        headers, and of the ones in ROBYN_REDACTED_HEADERS, are already logged as <redacted>.
+        A message the redactor raises on is replaced by the name of the exception.
+        e.g. def redactor(message): return re.sub(r"token=[^&\s]*", "token=***", message)
+
+        :param redactor Callable: the function taking a message and returning the one to log
+        """
+        self.log_redactor = redactor
+        return redactor
+
+    def start(
+        self,
+        url: str = "127.0.0.1",
+        port: int = 8080,
+        server_config: Optional[Dict[str, Any]] = None,
+    ):
         """
         Starts the server
 
-        :param port int: represents the port number at which the server is listening
+        :param port int: represents the port number at which the server is listening, 0 lets the OS pick a free one
+        :param server_config Optional[Dict[str, Any]]: The settings of the server by the name of their environment variable in lowercase without the ROBYN_ prefix, e.g. {"max_payload_size": 2_000_000}. The settings it doesn't give are read from the environment variables.
         """
 
         url = os.getenv("ROBYN_URL", url)
@@ -152,7 +283,6 @@ class Robyn:
         open_browser = bool(os.getenv("ROBYN_BROWSER_OPEN", self.config.open_browser))
 
         logger.info(f"Robyn version: {__version__}")
-        logger.info(f"Starting server at {url}:{port}")
 
         mp.allow_connection_pickling()
 
@@ -162,6 +292,7 @@ class Robyn:
             self.directories,
             self.request_headers,
             self.router.get_routes(),
+            self.router.get_fallbacks(),
             self.middleware_router.get_global_middlewares(),
             self.middleware_router.get_route_middlewares(),
             self.web_socket_router.get_routes(),
@@ -169,11 +300,90 @@ class Robyn:
             self.config.workers,
             self.config.processes,
             self.response_headers,
+            self.default_response_headers,
+            self.error_pages,
+            self.mime_types,
             open_browser,
+            server_config,
+            self.tunnel_handler,
+            self.log_redactor,
+        )
+
+    def test_client(self) -> TestClient:
+        """
+        Returns a client sending requests to the routes of the app without starting the server.
+        The requests go through the middlewares, but the startup handlers aren't run.
+        e.g. app.test_client().get("/users?page=2").status_code
+        """
+        return TestClient(self._init_server())
+
+    def routes(self) -> List[RouteInfo]:
+        """
+        Returns the routes of the app, with their method, path and the types of the middlewares
+        of their path. The const routes are run to build the list.
+        e.g. [print(route) for route in app.routes()]
+        """
+        return self._init_server().routes()
+
+    def _init_server(self) -> Server:
+        return init_server(
+            self.directories,
+            self.request_headers,
+            self.router.get_routes(),
+            self.router.get_fallbacks(),
+            self.middleware_router.get_global_middlewares(),
+            self.middleware_router.get_route_middlewares(),
+            self.web_socket_router.get_routes(),
+            self.event_handlers,
+            self.response_headers,
+            self.default_response_headers,
+            self.error_pages,
+            self.mime_types,
+            tunnel_handler=self.tunnel_handler,
+            log_redactor=self.log_redactor,
         )
 
     def exception(self, exception_handler: Callable):
-        self.exception_handler = exception_handler
+        """
+        The @app.exception decorator to build the response of the routes raising an exception.
+        The handler takes the exception, and optionally the request.
+
+        :param exception_handler Callable: the handler returning the response
+        """
+        self.router.set_error_handler(exception_handler)
+        return exception_handler
+
+    def fallback(self, handler: Callable):
+        """
+        The @app.fallback decorator to handle the requests that match no route, before they are
+        answered with a 404. Returning None leaves the request to the next fallback, or the 404.
+
+        :param handler Callable: the handler taking optionally the request, like a route handler
+        """
+        return self.router.add_fallback(handler)
+
+    def add_spa_fallback(self, index_file: str, prefix: str = "/") -> None:
+        """
+        Serves the index file of a single page app to the GET requests under the prefix that match
+        no route, so the app can route them on the client. The paths whose last segment has an
+        extension, e.g. /assets/app.js, are taken for missing files and left to the 404.
+
+        :param index_file str: the path of the index file of the app. e.g. "dist/index.html"
+        :param prefix str: the path the app is served under. e.g. "/app"
+        """
+        prefix = join_url_path(prefix, "/")
+
+        def spa_fallback(request: Request) -> Optional[Response]:
+            path = request.url.path
+            if request.method not in ("GET", "HEAD"):
+                return None
+            if path != prefix and not path.startswith(prefix.rstrip("/") + "/"):
+                return None
+            if "." in path.rsplit("/", 1)[-1]:
+                return None
+            return Response.from_file(index_file)
+
+        self.router.add_fallback(spa_fallback)
 
     def add_view(self, endpoint: str, view: Callable, const: bool = False):
         """
@@ -206,6 +416,23 @@ class Robyn:
         for route_type, handler in handlers:
             self._add_route(route_type, endpoint, handler, const)
 
+    def add_routes(
+        self, routes: Iterable[Tuple[Union[HttpMethod, str], str, Callable]]
+    ) -> None:
+        """
+        Adds the routes of a table, e.g. loaded from a config file, like their decorators would.
+        A method and an endpoint registered twice are refused as the server starts.
+
+        :param routes Iterable: the method, endpoint and handler of each route, the method being an HttpMethod or its name, e.g. "GET"
+        """
+        for route_type, endpoint, handler in routes:
+            if isinstance(route_type, str):
+                method = getattr(HttpMethod, route_type.upper(), None)
+                if not isinstance(method, HttpMethod):
+                    raise ValueError(f"Unknown method {route_type} for {endpoint}")
+                route_type = method
+            self._add_route(route_type, endpoint, handler)
+
     def view(self, endpoint: str, const: bool = False):
         """
         The @app.view decorator to add a view with the GET/POST/PUT/DELETE/PATCH/HEAD/OPTIONS method
@@ -218,7 +445,14 @@ class Robyn:
 
         return inner
 
-    def get(self, endpoint: str, const: bool = False, auth_required: bool = False):
+    def get(
+        self,
+        endpoint: str,
+        const: bool = False,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.get decorator to add a route with the GET method
 
@@ -227,12 +461,27 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.GET, endpoint, handler, const, auth_required
+                HttpMethod.GET,
+                endpoint,
+                handler,
+                const,
+                auth_required,
+                timeout,
+                cache_control=cache_control,
             )
 
         return inner
 
-    def post(self, endpoint: str, auth_required: bool = False):
+    def post(
+        self,
+        endpoint: str,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        stream_body: bool = False,
+        stream_files: bool = False,
+        max_body_size: Optional[int] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.post decorator to add a route with POST method
 
@@ -241,12 +490,29 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.POST, endpoint, handler, auth_required=auth_required
+                HttpMethod.POST,
+                endpoint,
+                handler,
+                auth_required=auth_required,
+                timeout=timeout,
+                stream_body=stream_body,
+                stream_files=stream_files,
+                max_body_size=max_body_size,
+                cache_control=cache_control,
             )
 
         return inner
 
-    def put(self, endpoint: str, auth_required: bool = False):
+    def put(
+        self,
+        endpoint: str,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        stream_body: bool = False,
+        stream_files: bool = False,
+        max_body_size: Optional[int] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.put decorator to add a get route with PUT method
 
@@ -255,12 +521,26 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.PUT, endpoint, handler, auth_required=auth_required
+                HttpMethod.PUT,
+                endpoint,
+                handler,
+                auth_required=auth_required,
+                timeout=timeout,
+                stream_body=stream_body,
+                stream_files=stream_files,
+                max_body_size=max_body_size,
+                cache_control=cache_control,
             )
 
         return inner
 
-    def delete(self, endpoint: str, auth_required: bool = False):
+    def delete(
+        self,
+        endpoint: str,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.delete decorator to add a route with DELETE method
 
@@ -269,12 +549,26 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.DELETE, endpoint, handler, auth_required=auth_required
+                HttpMethod.DELETE,
+                endpoint,
+                handler,
+                auth_required=auth_required,
+                timeout=timeout,
+                cache_control=cache_control,
             )
 
         return inner
 
-    def patch(self, endpoint: str, auth_required: bool = False):
+    def patch(
+        self,
+        endpoint: str,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        stream_body: bool = False,
+        stream_files: bool = False,
+        max_body_size: Optional[int] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.patch decorator to add a route with PATCH method
 
@@ -283,12 +577,26 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.PATCH, endpoint, handler, auth_required=auth_required
+                HttpMethod.PATCH,
+                endpoint,
+                handler,
+                auth_required=auth_required,
+                timeout=timeout,
+                stream_body=stream_body,
+                stream_files=stream_files,
+                max_body_size=max_body_size,
+                cache_control=cache_control,
             )
 
         return inner
 
-    def head(self, endpoint: str, auth_required: bool = False):
+    def head(
+        self,
+        endpoint: str,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.head decorator to add a route with HEAD method
 
@@ -297,12 +605,23 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.HEAD, endpoint, handler, auth_required=auth_required
+                HttpMethod.HEAD,
+                endpoint,
+                handler,
+                auth_required=auth_required,
+                timeout=timeout,
+                cache_control=cache_control,
             )
 
         return inner
 
-    def options(self, endpoint: str, auth_required: bool = False):
+    def options(
+        self,
+        endpoint: str,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.options decorator to add a route with OPTIONS method
 
@@ -311,12 +630,23 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.OPTIONS, endpoint, handler, auth_required=auth_required
+                HttpMethod.OPTIONS,
+                endpoint,
+                handler,
+                auth_required=auth_required,
+                timeout=timeout,
+                cache_control=cache_control,
             )
 
         return inner
 
-    def connect(self, endpoint: str, auth_required: bool = False):
+    def connect(
+        self,
+        endpoint: str,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.connect decorator to add a route with CONNECT method
 
@@ -325,12 +655,23 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.CONNECT, endpoint, handler, auth_required=auth_required
+                HttpMethod.CONNECT,
+                endpoint,
+                handler,
+                auth_required=auth_required,
+                timeout=timeout,
+                cache_control=cache_control,
             )
 
         return inner
 
-    def trace(self, endpoint: str, auth_required: bool = False):
+    def trace(
+        self,
+        endpoint: str,
+        auth_required: bool = False,
+        timeout: Optional[float] = None,
+        cache_control: Optional[str] = None,
+    ):
         """
         The @app.trace decorator to add a route with TRACE method
 
@@ -339,7 +680,12 @@ class Robyn:
 
         def inner(handler):
             return self._add_route(
-                HttpMethod.TRACE, endpoint, handler, auth_required=auth_required
+                HttpMethod.TRACE,
+                endpoint,
+                handler,
+                auth_required=auth_required,
+                timeout=timeout,
+                cache_control=cache_control,
             )
 
         return inner
@@ -351,6 +697,7 @@ class Robyn:
         :param router Robyn: the router object to include the routes from
         """
         self.router.routes.extend(router.router.routes)
+        self.router.fallbacks.extend(router.router.fallbacks)
         self.middleware_router.global_middlewares.extend(
             router.middleware_router.global_middlewares
         )
@@ -366,6 +713,34 @@ class Robyn:
                 new_endpoint
             ] = router.web_socket_router.routes[route]
 
+    def mount(self, prefix: str, router):
+        """
+        The method to serve the routes from another router under a path prefix.
+        e.g. a `/users` route of a router mounted at `/admin` is served at `/admin/users`
+
+        :param prefix str: the path prefix to serve the routes under
+        :param router Robyn: the router object to mount
+        """
+        for route in router.router.routes:
+            self.router.routes.append(
+                route._replace(route=join_url_path(prefix, route.route))
+            )
+        for route_middleware in router.middleware_router.route_middlewares:
+            self.middleware_router.route_middlewares.append(
+                route_middleware._replace(
+                    route=join_url_path(prefix, route_middleware.route)
+                )
+            )
+        # the global middlewares of the parent already wrap the mounted routes
+        self.middleware_router.global_middlewares.extend(
+            router.middleware_router.global_middlewares
+        )
+
+        router_prefix = getattr(router, "prefix", "")
+        for route, web_socket in router.web_socket_router.routes.items():
+            new_endpoint = join_url_path(prefix, f"{router_prefix}{route}")
+            self.web_socket_router.routes[new_endpoint] = web_socket
+
     def configure_authentication(self, authentication_handler: AuthenticationHandler):
         """
         Configures the authentication handler for the application.
@@ -410,9 +785,21 @@ class SubRouter(Robyn):
     def options(self, endpoint: str):
         return super().options(self.__add_prefix(endpoint))
 
+    def add_routes(
+        self, routes: Iterable[Tuple[Union[HttpMethod, str], str, Callable]]
+    ) -> None:
+        super().add_routes(
+            (route_type, self.__add_prefix(endpoint), handler)
+            for route_type, endpoint, handler in routes
+        )
+
 
 def ALLOW_CORS(app: Robyn, origins: List[str]):
-    """Allows CORS for the given origins for the entire router."""
+    """
+    Allows CORS for the given origins for the entire router.
+    The preflight requests to the paths without an OPTIONS route are answered with the methods
+    of their routes.
+    """
     for origin in origins:
         app.add_request_header("Access-Control-Allow-Origin", origin)
         app.add_request_header(
@@ -429,9 +816,14 @@ __all__ = [
     "Robyn",
     "Request",
     "Response",
+    "UploadedFile",
     "status_codes",
     "jsonify",
+    "negotiate",
     "serve_file",
     "serve_html",
     "ALLOW_CORS",
+    "SecurityHeaders",
+    "CacheControl",
+    "offload",
 ]
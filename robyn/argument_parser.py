@@ -16,9 +16,10 @@ class Config:
         parser.add_argument(
             "--workers",
             type=int,
-            default=1,
+            default=None,
             required=False,
-            help="Choose the number of workers. [Default: 1]",
+            help="Choose the number of workers. "
+            "[Default: ROBYN_WORKERS, else one per core]",
         )
         parser.add_argument(
             "--dev",
@@ -61,7 +62,7 @@ class Config:
         self.docs = args.docs
         self.open_browser = args.open_browser
 
-        if self.dev and (self.processes != 1 or self.workers != 1):
+        if self.dev and (self.processes != 1 or self.workers not in (None, 1)):
             raise Exception("--processes and --workers shouldn't be used with --dev")
 
         if self.dev and args.log_level is None:
@@ -1,9 +1,11 @@
 import asyncio
+import os
 import webbrowser
 from multiprocess import Process
 import signal
 import sys
-from typing import Dict, List
+import time
+from typing import Any, Callable, Dict, List, Optional
 from robyn.logger import logger
 
 from robyn.events import Events
@@ -12,6 +14,9 @@ from robyn.router import GlobalMiddleware, RouteMiddleware, Route
 from robyn.types import Directory, Header
 from robyn.ws import WS
 
+# in seconds, the time given to the drained processes to run their shutdown handler
+DRAIN_MARGIN = 5
+
 
 def run_processes(
     url: str,
@@ -19,21 +24,40 @@ def run_processes(
     directories: List[Directory],
     request_headers: List[Header],
     routes: List[Route],
+    fallbacks: List[FunctionInfo],
     global_middlewares: List[GlobalMiddleware],
     route_middlewares: List[RouteMiddleware],
     web_sockets: Dict[str, WS],
     event_handlers: Dict[Events, FunctionInfo],
-    workers: int,
+    workers: Optional[int],
     processes: int,
     response_headers: List[Header],
+    default_response_headers: List[Header],
+    error_pages: Dict[int, str],
+    mime_types: Dict[str, str],
     open_browser: bool,
+    server_config: Optional[Dict[str, Any]] = None,
+    tunnel_handler: Optional[FunctionInfo] = None,
+    log_redactor: Optional[Callable[[str], str]] = None,
 ) -> List[Process]:
     socket = SocketHeld(url, port)
+    if port == 0:
+        # the port has been picked by the OS
+        port = socket.local_port()
+    logger.info(f"Starting server at {url}:{port}")
+
+    # in milliseconds, the time given to the open connections on a SIGTERM
+    drain_timeout = int(
+        (server_config or {}).get(
+            "drain_timeout", os.getenv("ROBYN_DRAIN_TIMEOUT", "0")
+        )
+    )
 
     process_pool = init_processpool(
         directories,
         request_headers,
         routes,
+        fallbacks,
         global_middlewares,
         route_middlewares,
         web_sockets,
@@ -42,12 +66,26 @@ def run_processes(
         workers,
         processes,
         response_headers,
+        default_response_headers,
+        error_pages,
+        mime_types,
+        server_config,
+        tunnel_handler,
+        log_redactor,
     )
 
-    def terminating_signal_handler(_sig, _frame):
+    def terminating_signal_handler(sig, _frame):
         logger.info("Terminating server!!", bold=True)
+        if sig == signal.SIGTERM and drain_timeout > 0:
+            # the processes stop accepting and exit once their connections are drained
+            for process in process_pool:
+                process.terminate()
+            deadline = time.monotonic() + drain_timeout / 1000 + DRAIN_MARGIN
+            for process in process_pool:
+                process.join(max(deadline - time.monotonic(), 0))
         for process in process_pool:
-            process.kill()
+            if process.is_alive():
+                process.kill()
 
     signal.signal(signal.SIGINT, terminating_signal_handler)
     signal.signal(signal.SIGTERM, terminating_signal_handler)
@@ -67,14 +105,21 @@ def init_processpool(
     directories: List[Directory],
     request_headers: List[Header],
     routes: List[Route],
+    fallbacks: List[FunctionInfo],
     global_middlewares: List[GlobalMiddleware],
     route_middlewares: List[RouteMiddleware],
     web_sockets: Dict[str, WS],
     event_handlers: Dict[Events, FunctionInfo],
     socket: SocketHeld,
-    workers: int,
+    workers: Optional[int],
     processes: int,
     response_headers: List[Header],
+    default_response_headers: List[Header],
+    error_pages: Dict[int, str],
+    mime_types: Dict[str, str],
+    server_config: Optional[Dict[str, Any]] = None,
+    tunnel_handler: Optional[FunctionInfo] = None,
+    log_redactor: Optional[Callable[[str], str]] = None,
 ) -> List[Process]:
     process_pool = []
     if sys.platform.startswith("win32"):
@@ -82,6 +127,7 @@ def init_processpool(
             directories,
             request_headers,
             routes,
+            fallbacks,
             global_middlewares,
             route_middlewares,
             web_sockets,
@@ -89,6 +135,12 @@ def init_processpool(
             socket,
             workers,
             response_headers,
+            default_response_headers,
+            error_pages,
+            mime_types,
+            server_config,
+            tunnel_handler,
+            log_redactor,
         )
 
         return process_pool
@@ -101,6 +153,7 @@ def init_processpool(
                 directories,
                 request_headers,
                 routes,
+                fallbacks,
                 global_middlewares,
                 route_middlewares,
                 web_sockets,
@@ -108,6 +161,12 @@ def init_processpool(
                 copied_socket,
                 workers,
                 response_headers,
+                default_response_headers,
+                error_pages,
+                mime_types,
+                server_config,
+                tunnel_handler,
+                log_redactor,
             ),
         )
         process.start()
@@ -132,36 +191,29 @@ def initialize_event_loop():
         return loop
 
 
-def spawn_process(
+def init_server(
     directories: List[Directory],
     request_headers: List[Header],
     routes: List[Route],
+    fallbacks: List[FunctionInfo],
     global_middlewares: List[GlobalMiddleware],
     route_middlewares: List[RouteMiddleware],
     web_sockets: Dict[str, WS],
     event_handlers: Dict[Events, FunctionInfo],
-    socket: SocketHeld,
-    workers: int,
     response_headers: List[Header],
-):
+    default_response_headers: List[Header],
+    error_pages: Dict[int, str],
+    mime_types: Dict[str, str],
+    server_config: Optional[Dict[str, Any]] = None,
+    tunnel_handler: Optional[FunctionInfo] = None,
+    log_redactor: Optional[Callable[[str], str]] = None,
+) -> Server:
     """
-    This function is called by the main process handler to create a server runtime.
-    This functions allows one runtime per process.
-
-    :param directories List: the list of all the directories and related data
-    :param headers tuple: All the global headers in a tuple
-    :param routes Tuple[Route]: The routes tuple, containing the description about every route.
-    :param middlewares Tuple[Route]: The middleware routes tuple, containing the description about every route.
-    :param web_sockets list: This is a list of all the web socket routes
-    :param event_handlers Dict: This is an event dict that contains the event handlers
-    :param socket SocketHeld: This is the main tcp socket, which is being shared across multiple processes.
-    :param process_name string: This is the name given to the process to identify the process
-    :param workers int: This is the name given to the process to identify the process
+    This function creates a server runtime with the configuration of the app, without starting it.
+    The settings missing from server_config are read from the environment variables.
     """
 
-    loop = initialize_event_loop()
-
-    server = Server()
+    server = Server(server_config)
 
     # TODO: if we remove the dot access
     # the startup time will improve in the server
@@ -174,9 +226,20 @@ def spawn_process(
     for header in response_headers:
         server.add_response_header(*header.as_list())
 
-    for route in routes:
-        route_type, endpoint, function, is_const = route
-        server.add_route(route_type, endpoint, function, is_const)
+    for header in default_response_headers:
+        server.add_default_response_header(*header.as_list())
+
+    for status_code, file_path in error_pages.items():
+        server.add_error_page(status_code, file_path)
+
+    for extension, mime_type in mime_types.items():
+        server.add_mime_type(extension, mime_type)
+
+    # a route registered twice is an error, rather than one of its handlers never being called
+    server.add_routes(routes)
+
+    for function in fallbacks:
+        server.add_fallback(function)
 
     for middleware_type, middleware_function in global_middlewares:
         server.add_global_middleware(middleware_type, middleware_function)
@@ -190,6 +253,12 @@ def spawn_process(
     if Events.SHUTDOWN in event_handlers:
         server.add_shutdown_handler(event_handlers[Events.SHUTDOWN])
 
+    if tunnel_handler is not None:
+        server.set_tunnel_handler(tunnel_handler)
+
+    if log_redactor is not None:
+        server.set_log_redactor(log_redactor)
+
     for endpoint in web_sockets:
         web_socket = web_sockets[endpoint]
         server.add_web_socket_route(
@@ -199,6 +268,67 @@ def spawn_process(
             web_socket.methods["message"],
         )
 
+    return server
+
+
+def spawn_process(
+    directories: List[Directory],
+    request_headers: List[Header],
+    routes: List[Route],
+    fallbacks: List[FunctionInfo],
+    global_middlewares: List[GlobalMiddleware],
+    route_middlewares: List[RouteMiddleware],
+    web_sockets: Dict[str, WS],
+    event_handlers: Dict[Events, FunctionInfo],
+    socket: SocketHeld,
+    workers: Optional[int],
+    response_headers: List[Header],
+    default_response_headers: List[Header],
+    error_pages: Dict[int, str],
+    mime_types: Dict[str, str],
+    server_config: Optional[Dict[str, Any]] = None,
+    tunnel_handler: Optional[FunctionInfo] = None,
+    log_redactor: Optional[Callable[[str], str]] = None,
+):
+    """
+    This function is called by the main process handler to create a server runtime.
+    This functions allows one runtime per process.
+
+    :param directories List: the list of all the directories and related data
+    :param headers tuple: All the global headers in a tuple
+    :param routes Tuple[Route]: The routes tuple, containing the description about every route.
+    :param fallbacks List[FunctionInfo]: The handlers tried in order when no route matches a request.
+    :param middlewares Tuple[Route]: The middleware routes tuple, containing the description about every route.
+    :param web_sockets list: This is a list of all the web socket routes
+    :param event_handlers Dict: This is an event dict that contains the event handlers
+    :param socket SocketHeld: This is the main tcp socket, which is being shared across multiple processes.
+    :param process_name string: This is the name given to the process to identify the process
+    :param workers Optional[int]: The number of workers, resolved from ROBYN_WORKERS and the cores when None
+    :param server_config Optional[Dict[str, Any]]: The settings of the server, e.g. {"max_payload_size": 2_000_000}
+    :param tunnel_handler Optional[FunctionInfo]: The handler given the tunnel of the CONNECT requests
+    :param log_redactor Optional[Callable[[str], str]]: The function returning each message logged by the server as it is logged
+    """
+
+    loop = initialize_event_loop()
+
+    server = init_server(
+        directories,
+        request_headers,
+        routes,
+        fallbacks,
+        global_middlewares,
+        route_middlewares,
+        web_sockets,
+        event_handlers,
+        response_headers,
+        default_response_headers,
+        error_pages,
+        mime_types,
+        server_config,
+        tunnel_handler,
+        log_redactor,
+    )
+
     try:
         server.start(socket, workers)
         loop = asyncio.get_event_loop()
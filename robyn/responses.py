@@ -1,5 +1,7 @@
 import json
-from typing import Any, Dict
+from typing import Any, Dict, Optional
+
+from robyn.robyn import Request, Response
 
 
 def serve_html(file_path: str) -> Dict[str, Any]:
@@ -36,3 +38,37 @@ def jsonify(input_dict: dict) -> str:
     """
 
     return json.dumps(input_dict)
+
+
+def negotiate(
+    request: Request, representations: Dict[str, Any], default: Optional[str] = None
+) -> Response:
+    """
+    This function answers with the representation whose media type is the best match of the
+    Accept header of the request, or a 406 listing the media types when none is acceptable
+
+    :param request Request: the request to answer
+    :param representations Dict[str, Any]: the bodies by media type, e.g. {"application/json": data}. A callable is only called once it is picked, dicts and lists are serialized to json.
+    :param default Optional[str]: the media type picked when several are accepted as much, e.g. for Accept: */*. The first one by default.
+    """
+
+    media_type = request.negotiate(list(representations), default)
+    if media_type is None:
+        return Response(
+            status_code=406,
+            headers={"Content-Type": "text/plain", "Vary": "Accept"},
+            body=f"Not Acceptable, the media types are {', '.join(representations)}",
+        )
+
+    body = representations[media_type]
+    if callable(body):
+        body = body()
+    if isinstance(body, (dict, list)):
+        body = json.dumps(body)
+    elif not isinstance(body, (str, bytes)):
+        body = str(body)
+    return Response(
+        status_code=200,
+        headers={"Content-Type": media_type, "Vary": "Accept"},
+        body=body,
+    )
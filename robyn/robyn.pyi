@@ -2,14 +2,29 @@ from __future__ import annotations
 
 from dataclasses import dataclass
 from enum import Enum
-from typing import Callable, Optional, Union
+from typing import Any, Awaitable, Callable, Iterator, List, Optional, Tuple, Union
 
 def get_version() -> str:
     pass
 
+def offload(function: Callable[..., Any], *args: Any) -> Awaitable[Any]:
+    """
+    Runs the function on a blocking thread pool and returns an awaitable of its result.
+    """
+    pass
+
+class PanicException(BaseException):
+    """
+    Raised for a panic of Rust, which resumes as a panic once it reaches Rust again.
+    """
+
+    pass
+
 class SocketHeld:
     def __init__(self, url: str, port: int):
         pass
+    def local_port(self) -> int:
+        pass
     def try_clone(self) -> SocketHeld:
         pass
 
@@ -33,6 +48,10 @@ class FunctionInfo:
     handler: Callable
     is_async: bool
     number_of_params: int
+    stream_body: bool
+    stream_files: bool
+    max_body_size: Optional[int]
+    cache_control: Optional[str]
 
 @dataclass
 class Url:
@@ -49,10 +68,93 @@ class Url:
     host: str
     path: str
 
+    def absolute(self, location: str) -> str:
+        """
+        Returns the absolute URL of the location on the host of the request, e.g. "/login" or
+        "login" relative to the path of the request. The location is returned as it is when it
+        is already absolute, or when the request has no host.
+        """
+        pass
+
 @dataclass
 class Identity:
     claims: dict[str, str]
 
+class BodyStream:
+    """
+    The body of a request, read in chunks as they are received. e.g. async for chunk in request.stream
+    """
+
+    def __aiter__(self) -> BodyStream:
+        pass
+    async def __anext__(self) -> bytes:
+        pass
+
+class CancellationToken:
+    """
+    Tells a handler whether the client of its request disconnected, or the server is shutting
+    down, so it can stop early.
+
+    Attributes:
+        cancelled (bool): Whether the connection failed before the request was answered
+        shutting_down (bool): Whether the server got a SIGTERM and drains its connections
+    """
+
+    cancelled: bool
+    shutting_down: bool
+
+class RouteInfo:
+    """
+    A route registered in the server, listed by Server.routes.
+
+    Attributes:
+        method (HttpMethod): The method of the route
+        path (str): The pattern of the route, e.g. /users/:id
+        is_const (bool): Whether the response of the route is computed once
+        middlewares (list[MiddlewareType]): The types of the middlewares of the path, the global ones excluded
+        params (list[str]): The names of the parameters of the path, e.g. ["id"]
+        is_parameterized (bool): Whether the path has parameters
+    """
+
+    method: HttpMethod
+    path: str
+    is_const: bool
+    middlewares: list[MiddlewareType]
+    params: list[str]
+    is_parameterized: bool
+
+@dataclass
+class UploadedFile:
+    """
+    A file of a multipart form, written to a temporary file removed once the request is answered.
+
+    Attributes:
+        filename (Optional[str]): The name of the file on the client. e.g. report.pdf
+        content_type (Optional[str]): The content type of the file. e.g. application/pdf
+        path (str): The path of the temporary file. e.g. /tmp/robyn-upload-9b1d...
+        size (int): The size of the file in bytes
+    """
+
+    filename: Optional[str]
+    content_type: Optional[str]
+    path: str
+    size: int
+
+class Tunnel:
+    """
+    The connection of a CONNECT request once established, given to the tunnel handler with the request.
+    """
+
+    async def read(self) -> bytes:
+        """The next bytes sent by the client, empty once it stopped sending"""
+        pass
+    async def write(self, data: Union[str, bytes]) -> None:
+        """Sends the bytes to the client, raises an IOError once the tunnel is closed"""
+        pass
+    def close(self) -> None:
+        """Closes the connection once the bytes written so far are sent"""
+        pass
+
 @dataclass
 class Request:
     """
@@ -65,6 +167,15 @@ class Request:
         body (Union[str, bytes]): The body of the request. If the request is a JSON, it will be a dict.
         method (str): The method of the request. e.g. GET, POST, PUT, DELETE
         ip_addr (Optional[str]): The IP Address of the client
+        matched_route (Optional[str]): The pattern of the route matching the request. e.g. /user/:id
+        extensions (dict[Any, Any]): The data passed from the middlewares to the handler. e.g. {User: User("robyn")}
+        stream (Optional[BodyStream]): The body of the request in chunks, on the routes streaming it
+        form_data (dict[str, str]): The fields of a multipart form, on the routes streaming its files. e.g. {"name": "robyn"}
+        files (dict[str, UploadedFile]): The files of a multipart form, on the routes streaming them
+        if_match (Optional[list[str]]): The entity tags of the If-Match header. e.g. ['"v1"', 'W/"v2"'] or ["*"]
+        if_none_match (Optional[list[str]]): The entity tags of the If-None-Match header
+        cancellation (Optional[CancellationToken]): Polled to know whether the client disconnected
+        deadline (Optional[float]): The deadline sent in the deadline header, in seconds since the epoch like time.time()
     """
 
     queries: dict[str, str]
@@ -75,6 +186,49 @@ class Request:
     url: Url
     ip_addr: Optional[str]
     identity: Optional[Identity]
+    matched_route: Optional[str]
+    extensions: dict[Any, Any]
+    stream: Optional[BodyStream]
+    form_data: dict[str, str]
+    files: dict[str, UploadedFile]
+    if_match: Optional[list[str]]
+    if_none_match: Optional[list[str]]
+    cancellation: Optional[CancellationToken]
+    deadline: Optional[float]
+
+    def evaluate_preconditions(self, etag: Optional[str]) -> Optional[int]:
+        """
+        Evaluates the If-Match and If-None-Match headers against the current entity tag of the
+        resource, e.g. '"v2"', or None when it doesn't exist. Returns the status to answer with
+        instead of handling the request, 412 or 304, or None to handle it.
+        """
+        pass
+
+    def negotiate(
+        self, media_types: list[str], default: Optional[str] = None
+    ) -> Optional[str]:
+        """
+        Picks the media type to answer with among the ones offered, e.g. ["application/json",
+        "text/html"], by the q values of the Accept header. The default is picked between the
+        types accepted as much, e.g. for */*, else the first one. Returns None when none of them
+        is acceptable.
+        """
+        pass
+
+    def form(self) -> dict[str, str]:
+        """
+        The fields of the form sent in the body, percent-decoded: the fields of a multipart form
+        on the routes streaming its files, else the fields of an
+        application/x-www-form-urlencoded body. Empty for the other bodies.
+        """
+        pass
+
+    def param(self, name: str, default: Any = None) -> Any:
+        """
+        The value of a parameter wherever it was sent, looked up in order in the path params,
+        the query params and the fields of the form. Returns default when it isn't found.
+        """
+        pass
 
 @dataclass
 class Response:
@@ -87,6 +241,8 @@ class Response:
         headers (dict[str, str]): The headers of the response. e.g. {"Content-Type": "application/json"}
         body (Union[str, bytes]): The body of the response. If the response is a JSON, it will be a dict.
         file_path (Optional[str]): The file path of the response. e.g. /home/user/file.txt
+        stream (Optional[Iterator[Union[str, bytes]]]): The iterator given as the body, whose chunks are sent as they are yielded.
+        trailers (dict[str, str]): The trailers sent after a streamed body, set by its iterator for the names declared in the Trailer header.
     """
 
     status_code: int
@@ -94,22 +250,67 @@ class Response:
     body: Union[str, bytes]
     response_type: Optional[str] = None
     file_path: Optional[str] = None
+    stream: Optional[Iterator[Union[str, bytes]]] = None
+    trailers: dict[str, str]
+
+    def __init__(
+        self,
+        status_code: int,
+        headers: dict[str, str],
+        body: Union[str, bytes, Iterator[Union[str, bytes]]],
+    ) -> None:
+        pass
+
+    @staticmethod
+    def from_file(file_path: str) -> Response:
+        """
+        Creates a response serving the file at file_path, with a Content-Type derived
+        from its extension. A 404 response is returned if the file doesn't exist.
+        The files larger than the response buffer size are streamed from disk.
+        """
+        pass
+    @staticmethod
+    def redirect(location: str, status_code: int = 307) -> Response:
+        """
+        Creates a redirect to the location, sent as it is in the Location header, e.g.
+        Response.redirect("/login") or Response.redirect(request.url.absolute("/login"), 303).
+        Raises a ValueError for a status code that isn't a 3xx.
+        """
+        pass
+    def add_vary(self, field: str) -> None:
+        """
+        Adds a request header the response varies on to the Vary header.
+        """
+        pass
 
 class Server:
-    def __init__(self) -> None:
+    def __init__(self, config: Optional[dict[str, Any]] = None) -> None:
+        """
+        The settings missing from the config are read from the environment variables.
+        e.g. {"max_payload_size": 2_000_000} for ROBYN_MAX_PAYLOAD_SIZE
+        """
         pass
     def add_directory(
         self,
         route: str,
         directory_path: str,
         show_files_listing: bool,
-        index_file: Optional[str],
+        show_hidden_files: bool,
+        index_files: List[str],
+        missing_index_status: int,
+        cache_control: Optional[str],
     ) -> None:
         pass
+    def add_error_page(self, status_code: int, file_path: str) -> None:
+        pass
+    def add_mime_type(self, extension: str, mime_type: str) -> None:
+        pass
     def add_request_header(self, key: str, value: str) -> None:
         pass
     def add_response_header(self, key: str, value: str) -> None:
         pass
+    def add_default_response_header(self, key: str, value: str) -> None:
+        pass
     def add_route(
         self,
         route_type: HttpMethod,
@@ -118,6 +319,24 @@ class Server:
         is_const: bool,
     ) -> None:
         pass
+    def add_routes(
+        self, routes: List[Tuple[HttpMethod, str, FunctionInfo, bool]]
+    ) -> None:
+        """
+        Adds the routes, raising a ValueError for a method and a path already registered.
+        """
+        pass
+    def add_fallback(self, function: FunctionInfo) -> None:
+        pass
+    def reload_routes(
+        self, routes: List[Tuple[HttpMethod, str, FunctionInfo, bool]]
+    ) -> None:
+        pass
+    def routes(self) -> List[RouteInfo]:
+        """
+        The registered routes. The websocket routes and the directories aren't listed.
+        """
+        pass
     def add_global_middleware(
         self, middleware_type: MiddlewareType, function: FunctionInfo
     ) -> None:
@@ -133,6 +352,10 @@ class Server:
         pass
     def add_shutdown_handler(self, function: FunctionInfo) -> None:
         pass
+    def set_tunnel_handler(self, function: FunctionInfo) -> None:
+        pass
+    def set_log_redactor(self, redactor: Callable[[str], str]) -> None:
+        pass
     def add_web_socket_route(
         self,
         route: str,
@@ -141,5 +364,53 @@ class Server:
         message_route: FunctionInfo,
     ) -> None:
         pass
-    def start(self, socket: SocketHeld, workers: int) -> None:
+    def start(self, socket: SocketHeld, workers: Optional[int]) -> None:
+        pass
+
+class TestClient:
+    """
+    Sends requests to the routes of a server without starting it.
+
+    The path can have a query string, e.g. `/users?page=2`
+    """
+
+    def __init__(self, server: Server) -> None:
+        pass
+    def request(
+        self,
+        method: str,
+        path: str,
+        body: Optional[Union[str, bytes]] = None,
+        headers: Optional[dict[str, str]] = None,
+    ) -> Response:
+        pass
+    def get(self, path: str, headers: Optional[dict[str, str]] = None) -> Response:
+        pass
+    def post(
+        self,
+        path: str,
+        body: Optional[Union[str, bytes]] = None,
+        headers: Optional[dict[str, str]] = None,
+    ) -> Response:
+        pass
+    def put(
+        self,
+        path: str,
+        body: Optional[Union[str, bytes]] = None,
+        headers: Optional[dict[str, str]] = None,
+    ) -> Response:
+        pass
+    def patch(
+        self,
+        path: str,
+        body: Optional[Union[str, bytes]] = None,
+        headers: Optional[dict[str, str]] = None,
+    ) -> Response:
+        pass
+    def delete(
+        self,
+        path: str,
+        body: Optional[Union[str, bytes]] = None,
+        headers: Optional[dict[str, str]] = None,
+    ) -> Response:
         pass
@@ -1,16 +1,75 @@
+import time
 from abc import ABC, abstractmethod
-from asyncio import iscoroutinefunction
+from collections.abc import Iterator
+from asyncio import TimeoutError, iscoroutinefunction, wait_for
 from functools import wraps
 from inspect import signature
 from types import CoroutineType
 from typing import Callable, Dict, List, NamedTuple, Union, Optional
 from robyn.authentication import AuthenticationHandler, AuthenticationNotConfiguredError
+from robyn.logger import logger
 
 from robyn.robyn import FunctionInfo, HttpMethod, MiddlewareType, Request, Response
 from robyn import status_codes
 from robyn.ws import WS
 
 
+def join_url_path(prefix: str, endpoint: str) -> str:
+    """
+    Joins a prefix and an endpoint without doubling or losing the slash between them.
+    e.g. ("/admin/", "/users") -> "/admin/users" and ("/admin", "/") -> "/admin"
+    """
+    prefix = "/" + prefix.strip("/")
+    endpoint = endpoint.lstrip("/")
+    if prefix == "/":
+        return f"/{endpoint}"
+    if not endpoint:
+        return prefix
+    return f"{prefix}/{endpoint}"
+
+
+def gateway_timeout() -> Response:
+    return Response(
+        status_code=status_codes.HTTP_504_GATEWAY_TIMEOUT,
+        headers={"Content-Type": "text/plain"},
+        body="Gateway Timeout",
+    )
+
+
+def handler_timeout(request: Request, timeout: Optional[float]) -> Optional[float]:
+    """
+    The time left to the handler of a request, the timeout of its route shortened to the
+    deadline of the request. It is zero or less once the deadline passed.
+    """
+    if request.deadline is None:
+        return timeout
+    remaining = request.deadline - time.time()
+    return remaining if timeout is None else min(timeout, remaining)
+
+
+class HandlerTimedOut(Exception):
+    """
+    Raised when a handler is cancelled as its time ran out, answered with a 504
+    """
+
+
+async def wait_for_handler(awaitable, request_timeout: Optional[float]):
+    """
+    Awaits the handler within the time left to it, which isn't limited when it is None.
+    A TimeoutError raised before that time comes from the handler itself, e.g. from a
+    call it made, so only the one of a cancelled handler becomes a HandlerTimedOut.
+    """
+    if request_timeout is None:
+        return await awaitable
+    expires = time.monotonic() + request_timeout
+    try:
+        return await wait_for(awaitable, request_timeout)
+    except TimeoutError as err:
+        if time.monotonic() < expires:
+            raise
+        raise HandlerTimedOut() from err
+
+
 class Route(NamedTuple):
     route_type: HttpMethod
     route: str
@@ -39,6 +98,22 @@ class Router(BaseRouter):
     def __init__(self) -> None:
         super().__init__()
         self.routes: List[Route] = []
+        self.fallbacks: List[FunctionInfo] = []
+        self.error_handler: Optional[Callable] = None
+
+    def set_error_handler(self, error_handler: Callable) -> None:
+        """
+        Sets the handler building the response of every route that raises an exception.
+        It is looked up when the exception is raised, so it also covers the routes added before it.
+
+        :param error_handler Callable: takes the exception, and optionally the request, and returns a response
+        """
+        self.error_handler = error_handler
+
+    def _handle_error(self, err: Exception, request: Request):
+        if len(signature(self.error_handler).parameters) > 1:
+            return self._format_response(self.error_handler(err, request))
+        return self._format_response(self.error_handler(err))
 
     def _format_response(self, res):
         response = {}
@@ -56,12 +131,21 @@ class Router(BaseRouter):
                 response.file_path = file_path
         elif isinstance(res, Response):
             response = res
+        elif isinstance(res, tuple):
+            response = self._format_tuple_response(res)
         elif isinstance(res, bytes):
             response = Response(
                 status_code=status_codes.HTTP_200_OK,
                 headers={"Content-Type": "application/octet-stream"},
                 body=res,
             )
+        elif isinstance(res, Iterator):
+            # e.g. a generator, whose chunks are sent as they are yielded
+            response = Response(
+                status_code=status_codes.HTTP_200_OK,
+                headers={"Content-Type": "text/plain"},
+                body=res,
+            )
         else:
             response = Response(
                 status_code=status_codes.HTTP_200_OK,
@@ -70,47 +154,184 @@ class Router(BaseRouter):
             )
         return response
 
+    def _format_tuple_response(self, res: tuple) -> Response:
+        """
+        Builds the response of a (status, body) or (status, headers, body) tuple.
+        The body gets the Content-Type of a bare str or bytes unless the headers set one.
+        """
+        if len(res) not in (2, 3):
+            raise TypeError(
+                "A handler can return a (status, body) or a (status, headers, body) tuple, "
+                f"not a tuple of {len(res)} items"
+            )
+        status_code, *rest, body = res
+        headers = rest[0] if rest else {}
+        if isinstance(status_code, bool) or not isinstance(status_code, int):
+            raise TypeError(
+                f"The status of a response tuple is an int, not {type(status_code).__name__}"
+            )
+        if not isinstance(headers, dict) or not all(
+            isinstance(key, str) and isinstance(value, str)
+            for key, value in headers.items()
+        ):
+            raise TypeError("The headers of a response tuple are a dict of str to str")
+        if not isinstance(body, (str, bytes, Iterator)):
+            raise TypeError(
+                "The body of a response tuple is a str, bytes or an iterator, "
+                f"not {type(body).__name__}"
+            )
+        if not any(key.lower() == "content-type" for key in headers):
+            content_type = (
+                "application/octet-stream" if isinstance(body, bytes) else "text/plain"
+            )
+            headers = {"Content-Type": content_type, **headers}
+        return Response(status_code=status_code, headers=headers, body=body)
+
     def add_route(
         self,
         route_type: HttpMethod,
         endpoint: str,
         handler: Callable,
         is_const: bool,
-        exception_handler: Optional[Callable],
+        timeout: Optional[float] = None,
+        stream_body: bool = False,
+        stream_files: bool = False,
+        max_body_size: Optional[int] = None,
+        cache_control: Optional[str] = None,
     ) -> Union[Callable, CoroutineType]:
+        number_of_params = len(signature(handler).parameters)
+
+        # the inner handlers always take the request so it can be given to the error handler
         @wraps(handler)
-        async def async_inner_handler(*args):
+        async def async_inner_handler(request: Request):
+            args = (request,) if number_of_params else ()
+            request_timeout = handler_timeout(request, timeout)
+            if request_timeout is not None and request_timeout <= 0:
+                return gateway_timeout()
             try:
-                response = self._format_response(await handler(*args))
+                response = self._format_response(
+                    await wait_for_handler(handler(*args), request_timeout)
+                )
+            except HandlerTimedOut:
+                return gateway_timeout()
             except Exception as err:
-                if exception_handler is None:
+                if self.error_handler is None:
                     raise
-                response = self._format_response(exception_handler(err))
+                response = self._handle_error(err, request)
             return response
 
         @wraps(handler)
-        def inner_handler(*args):
+        def inner_handler(request: Request):
+            args = (request,) if number_of_params else ()
+            # a sync handler can't be interrupted, it isn't run once the deadline passed
+            request_timeout = handler_timeout(request, None)
+            if request_timeout is not None and request_timeout <= 0:
+                return gateway_timeout()
             try:
                 response = self._format_response(handler(*args))
             except Exception as err:
-                if exception_handler is None:
+                if self.error_handler is None:
                     raise
-                response = self._format_response(exception_handler(err))
+                response = self._handle_error(err, request)
             return response
 
-        number_of_params = len(signature(handler).parameters)
+        if timeout is not None and not iscoroutinefunction(handler):
+            logger.warn(
+                f"The timeout of {endpoint} is ignored, only async handlers can be cancelled"
+            )
+        if stream_body and not iscoroutinefunction(handler):
+            logger.warn(
+                f"The body of {endpoint} is read before calling the handler, only async handlers can stream it"
+            )
+        if stream_body and stream_files:
+            logger.warn(
+                f"The files of {endpoint} are read from the streamed body, stream_files is ignored"
+            )
+        if stream_body and max_body_size is not None:
+            logger.warn(
+                f"The streamed body of {endpoint} isn't limited, max_body_size is ignored"
+            )
         if iscoroutinefunction(handler):
-            function = FunctionInfo(async_inner_handler, True, number_of_params)
+            function = FunctionInfo(
+                async_inner_handler,
+                True,
+                1,
+                stream_body,
+                stream_files,
+                max_body_size,
+                cache_control,
+            )
             self.routes.append(Route(route_type, endpoint, function, is_const))
             return async_inner_handler
         else:
-            function = FunctionInfo(inner_handler, False, number_of_params)
+            function = FunctionInfo(
+                inner_handler,
+                False,
+                1,
+                stream_files=stream_files,
+                max_body_size=max_body_size,
+                cache_control=cache_control,
+            )
             self.routes.append(Route(route_type, endpoint, function, is_const))
             return inner_handler
 
+    def add_fallback(self, handler: Callable) -> Union[Callable, CoroutineType]:
+        """
+        Adds a handler called with the requests that match no route, before they are answered
+        with a 404. The fallbacks are tried in the order they are added, one returning None
+        leaves the request to the next one.
+
+        :param handler Callable: takes optionally the request, and returns a response or None
+        """
+        number_of_params = len(signature(handler).parameters)
+
+        def format_fallback_response(res):
+            return None if res is None else self._format_response(res)
+
+        @wraps(handler)
+        async def async_inner_handler(request: Request):
+            args = (request,) if number_of_params else ()
+            request_timeout = handler_timeout(request, None)
+            if request_timeout is not None and request_timeout <= 0:
+                return gateway_timeout()
+            try:
+                return format_fallback_response(
+                    await wait_for_handler(handler(*args), request_timeout)
+                )
+            except HandlerTimedOut:
+                return gateway_timeout()
+            except Exception as err:
+                if self.error_handler is None:
+                    raise
+                return self._handle_error(err, request)
+
+        @wraps(handler)
+        def inner_handler(request: Request):
+            args = (request,) if number_of_params else ()
+            # a sync handler can't be interrupted, it isn't run once the deadline passed
+            request_timeout = handler_timeout(request, None)
+            if request_timeout is not None and request_timeout <= 0:
+                return gateway_timeout()
+            try:
+                return format_fallback_response(handler(*args))
+            except Exception as err:
+                if self.error_handler is None:
+                    raise
+                return self._handle_error(err, request)
+
+        if iscoroutinefunction(handler):
+            self.fallbacks.append(FunctionInfo(async_inner_handler, True, 1))
+            return async_inner_handler
+        else:
+            self.fallbacks.append(FunctionInfo(inner_handler, False, 1))
+            return inner_handler
+
     def get_routes(self) -> List[Route]:
         return self.routes
 
+    def get_fallbacks(self) -> List[FunctionInfo]:
+        return self.fallbacks
+
 
 class MiddlewareRouter(BaseRouter):
     def __init__(self) -> None:
@@ -1,5 +1,5 @@
 from dataclasses import dataclass
-from typing import Optional
+from typing import List, Optional
 
 
 @dataclass
@@ -7,17 +7,40 @@ class Directory:
     route: str
     directory_path: str
     show_files_listing: bool
-    index_file: Optional[str]
+    show_hidden_files: bool
+    index_files: List[str]
+    missing_index_status: int
+    cache_control: Optional[str] = None
 
     def as_list(self):
         return [
             self.route,
             self.directory_path,
             self.show_files_listing,
-            self.index_file,
+            self.show_hidden_files,
+            self.index_files,
+            self.missing_index_status,
+            self.cache_control,
         ]
 
 
+class CacheControl:
+    """
+    Usual values of the Cache-Control of the routes and the directories
+    """
+
+    # the files whose name changes with their content, e.g. app.3f9a1c.js
+    IMMUTABLE = "public, max-age=31536000, immutable"
+    # the responses that change with every request, e.g. of an API
+    NO_STORE = "no-store"
+    # the responses a cache keeps, but checks with the server before using them
+    NO_CACHE = "no-cache"
+
+    @staticmethod
+    def max_age(seconds: int) -> str:
+        return f"max-age={seconds}"
+
+
 @dataclass
 class Header:
     key: str
@@ -25,3 +48,43 @@ class Header:
 
     def as_list(self):
         return [self.key, self.val]
+
+
+@dataclass
+class SecurityHeaders:
+    """
+    The security headers added to every response. A header is skipped when its value is None.
+    Handlers can still override any of these headers in their own responses.
+    """
+
+    content_type_options: bool = True
+    frame_options: Optional[str] = "DENY"
+    strict_transport_security: Optional[str] = "max-age=31536000; includeSubDomains"
+    content_security_policy: Optional[str] = "default-src 'self'"
+    referrer_policy: Optional[str] = "strict-origin-when-cross-origin"
+    expect_ct: Optional[str] = None
+    # the headers needed by the pages using SharedArrayBuffer, e.g. for threaded WebAssembly
+    cross_origin_isolation: bool = False
+
+    def as_headers(self) -> List[Header]:
+        headers = []
+        if self.content_type_options:
+            headers.append(Header("X-Content-Type-Options", "nosniff"))
+        if self.frame_options is not None:
+            headers.append(Header("X-Frame-Options", self.frame_options))
+        if self.strict_transport_security is not None:
+            headers.append(
+                Header("Strict-Transport-Security", self.strict_transport_security)
+            )
+        if self.content_security_policy is not None:
+            headers.append(
+                Header("Content-Security-Policy", self.content_security_policy)
+            )
+        if self.referrer_policy is not None:
+            headers.append(Header("Referrer-Policy", self.referrer_policy))
+        if self.expect_ct is not None:
+            headers.append(Header("Expect-CT", self.expect_ct))
+        if self.cross_origin_isolation:
+            headers.append(Header("Cross-Origin-Opener-Policy", "same-origin"))
+            headers.append(Header("Cross-Origin-Embedder-Policy", "require-corp"))
+        return headers
@@ -1,16 +1,146 @@
 /// This is the module that has all the executor functions
 /// i.e. the functions that have the responsibility of parsing and executing functions.
-use std::sync::Arc;
+use std::cell::RefCell;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{io, thread};
 
 use anyhow::Result;
-use log::debug;
+use log::{debug, warn};
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
+use pyo3::types::PyTuple;
 use pyo3_asyncio::TaskLocals;
 
 use crate::types::{
-    function_info::FunctionInfo, request::Request, response::Response, MiddlewareReturn,
+    function_info::FunctionInfo, request::Request, response::Response, tunnel::Tunnel,
+    MiddlewareReturn,
 };
 
+// the workers of the process, `0` until the server starts, e.g. in the test client
+static WORKERS: AtomicUsize = AtomicUsize::new(0);
+// the workers running a sync handler or middleware, which blocks the worker and so its other
+// requests
+static BUSY_WORKERS: AtomicUsize = AtomicUsize::new(0);
+// when the workers were last all found busy, to log it once in a while rather than per request
+static ALL_BUSY_WARNED: Mutex<Option<Instant>> = Mutex::new(None);
+const ALL_BUSY_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+// the stack size of the threads running the sync handlers, `0` runs them on their worker
+static SYNC_STACK_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+// a sync function sent to the sync thread of a worker, which sends its result back
+type SyncJob = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    // the thread the sync functions of the worker run on, spawned for the first of them and
+    // stopped with the worker, which drops the sender
+    static SYNC_THREAD: RefCell<Option<mpsc::Sender<SyncJob>>> = RefCell::new(None);
+}
+
+pub fn set_workers(workers: usize) {
+    WORKERS.store(workers, SeqCst);
+}
+
+pub fn busy_workers() -> usize {
+    BUSY_WORKERS.load(SeqCst)
+}
+
+/// The sync handlers and middlewares run on a thread of the stack size, one per worker, `0`
+/// runs them on their worker with its default stack size, as actix doesn't let the one of its
+/// workers be set
+pub fn set_sync_stack_size(size: usize) {
+    SYNC_STACK_SIZE.store(size, SeqCst);
+}
+
+/// Runs a sync function of the input on the sync thread of the worker, which has the stack size
+/// of the sync handlers, and waits for it as if it ran the function itself. The thread is given
+/// copies of the input and the function info, which it owns.
+fn run_sync<I, T>(
+    function: &FunctionInfo,
+    input: &I,
+    run: fn(&FunctionInfo, &I) -> T,
+) -> io::Result<T>
+where
+    I: Clone + Send + 'static,
+    T: Send + 'static,
+{
+    let stack_size = SYNC_STACK_SIZE.load(SeqCst);
+    if stack_size == 0 {
+        return Ok(run(function, input));
+    }
+    let (function, input) = (function.clone(), input.clone());
+    let (result_sender, result) = mpsc::sync_channel(1);
+    let job: SyncJob = Box::new(move || {
+        // a panic of the function is resumed on the worker, which answers it, and the thread
+        // keeps running the next ones
+        let output = catch_unwind(AssertUnwindSafe(|| run(&function, &input)));
+        let _ = result_sender.send(output);
+    });
+    SYNC_THREAD.with(|sync_thread| {
+        let mut sync_thread = sync_thread.borrow_mut();
+        let jobs = match sync_thread.take() {
+            Some(jobs) => jobs,
+            None => spawn_sync_thread(stack_size)?,
+        };
+        jobs.send(job).map_err(|_| sync_thread_stopped())?;
+        *sync_thread = Some(jobs);
+        io::Result::Ok(())
+    })?;
+    match result.recv() {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(payload)) => resume_unwind(payload),
+        Err(_) => Err(sync_thread_stopped()),
+    }
+}
+
+fn spawn_sync_thread(stack_size: usize) -> io::Result<mpsc::Sender<SyncJob>> {
+    let (jobs, received) = mpsc::channel::<SyncJob>();
+    thread::Builder::new()
+        .name("robyn-sync".to_string())
+        .stack_size(stack_size)
+        .spawn(move || {
+            for job in received {
+                job();
+            }
+        })?;
+    Ok(jobs)
+}
+
+fn sync_thread_stopped() -> io::Error {
+    io::Error::other("The thread running the sync handlers of the worker stopped")
+}
+
+/// Counts the worker as busy while it runs a sync handler or middleware
+struct BusyWorker;
+
+impl BusyWorker {
+    fn new() -> Self {
+        let busy_workers = BUSY_WORKERS.fetch_add(1, SeqCst) + 1;
+        let workers = WORKERS.load(SeqCst);
+        if workers != 0 && busy_workers >= workers {
+            let mut warned = ALL_BUSY_WARNED.lock().unwrap_or_else(|e| e.into_inner());
+            if warned.is_none_or(|warned| warned.elapsed() >= ALL_BUSY_WARNING_INTERVAL) {
+                *warned = Some(Instant::now());
+                warn!(
+                    "All the {} workers are running sync handlers, the requests wait for one of \
+                     them to finish. Make the slow handlers async or raise the number of workers",
+                    workers
+                );
+            }
+        }
+        Self
+    }
+}
+
+impl Drop for BusyWorker {
+    fn drop(&mut self) {
+        BUSY_WORKERS.fetch_sub(1, SeqCst);
+    }
+}
+
 fn get_function_output<'a, T>(
     function: &'a FunctionInfo,
     py: Python<'a>,
@@ -38,7 +168,7 @@ pub async fn execute_middleware_function<T>(
     function: &FunctionInfo,
 ) -> Result<MiddlewareReturn>
 where
-    T: for<'a> FromPyObject<'a> + ToPyObject,
+    T: for<'a> FromPyObject<'a> + ToPyObject + Clone + Send + 'static,
 {
     if function.is_async {
         let output: Py<PyAny> = Python::with_gil(|py| {
@@ -54,13 +184,16 @@ where
             }
         })
     } else {
-        Python::with_gil(|py| -> Result<MiddlewareReturn> {
-            let output = get_function_output(function, py, input)?;
-            match output.extract::<Response>() {
-                Ok(o) => Ok(MiddlewareReturn::Response(o)),
-                Err(_) => Ok(MiddlewareReturn::Request(output.extract::<Request>()?)),
-            }
-        })
+        let _busy = BusyWorker::new();
+        run_sync(function, input, |function, input| {
+            Python::with_gil(|py| -> Result<MiddlewareReturn> {
+                let output = get_function_output(function, py, input)?;
+                match output.extract::<Response>() {
+                    Ok(o) => Ok(MiddlewareReturn::Response(o)),
+                    Err(_) => Ok(MiddlewareReturn::Request(output.extract::<Request>()?)),
+                }
+            })
+        })?
     }
 }
 
@@ -69,6 +202,21 @@ pub async fn execute_http_function(
     request: &Request,
     function: &FunctionInfo,
 ) -> PyResult<Response> {
+    execute_request_function(request, function).await
+}
+
+/// Executes a fallback function, which returns `None` when it leaves the request to the next one
+pub async fn execute_fallback_function(
+    request: &Request,
+    function: &FunctionInfo,
+) -> PyResult<Option<Response>> {
+    execute_request_function(request, function).await
+}
+
+async fn execute_request_function<T>(request: &Request, function: &FunctionInfo) -> PyResult<T>
+where
+    T: for<'a> FromPyObject<'a> + Send + 'static,
+{
     if function.is_async {
         let output = Python::with_gil(|py| {
             let function_output = get_function_output(function, py, request)?;
@@ -76,12 +224,15 @@ pub async fn execute_http_function(
         })?
         .await?;
 
-        return Python::with_gil(|py| -> PyResult<Response> { output.extract(py) });
+        return Python::with_gil(|py| -> PyResult<T> { output.extract(py) });
     };
 
-    Python::with_gil(|py| -> PyResult<Response> {
-        get_function_output(function, py, request)?.extract()
-    })
+    let _busy = BusyWorker::new();
+    run_sync(function, request, |function, request| {
+        Python::with_gil(|py| -> PyResult<T> {
+            get_function_output(function, py, request)?.extract()
+        })
+    })?
 }
 
 pub async fn execute_event_handler(
@@ -105,3 +256,50 @@ pub async fn execute_event_handler(
     }
     Ok(())
 }
+
+/// Runs the tunnel handler with the request and its tunnel, until it returns
+pub async fn execute_tunnel_function(
+    request: &Request,
+    tunnel: Tunnel,
+    function: &FunctionInfo,
+) -> PyResult<()> {
+    Python::with_gil(|py| {
+        let output = function
+            .handler
+            .as_ref(py)
+            .call1((request.to_object(py), tunnel.into_py(py)))?;
+        pyo3_asyncio::tokio::into_future(output)
+    })?
+    .await?;
+    Ok(())
+}
+
+/// Runs the startup handler before the server starts, on the event loop of the server when it
+/// is async, as the loop isn't running yet
+pub fn execute_startup_handler(function: &FunctionInfo, event_loop: &PyAny) -> PyResult<()> {
+    let py = event_loop.py();
+    let output = function.handler.as_ref(py).call0()?;
+    if function.is_async {
+        debug!("Startup event handler async");
+        event_loop.call_method1("run_until_complete", (output,))?;
+    } else {
+        debug!("Startup event handler");
+    }
+    Ok(())
+}
+
+/// Runs the function on the blocking thread pool of the runtime and returns an awaitable
+/// of its result, so that the event loop isn't blocked while it runs.
+/// The GIL is held while the function runs Python code, so this is only useful for functions
+/// that spend their time outside of it e.g. in blocking I/O or native extensions.
+#[pyfunction]
+#[pyo3(signature = (function, *args))]
+pub fn offload<'a>(py: Python<'a>, function: Py<PyAny>, args: Py<PyTuple>) -> PyResult<&'a PyAny> {
+    pyo3_asyncio::tokio::future_into_py(py, async move {
+        tokio::task::spawn_blocking(move || {
+            Python::with_gil(|py| function.call1(py, args.as_ref(py)))
+        })
+        .await
+        .map_err(|e| PyRuntimeError::new_err(format!("Offloaded function panicked: {e}")))?
+    })
+}
@@ -0,0 +1,182 @@
+use std::fmt::Write;
+use std::io;
+
+use actix_files::{Files, NamedFile};
+use actix_web::dev::ServiceResponse;
+use actix_web::http::header::{self, HeaderValue, HttpDate};
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::types::Url;
+
+/// A directory of static files served under a route
+#[derive(Clone, Debug)]
+pub struct Directory {
+    pub route: String,
+    pub directory_path: String,
+    pub show_files_listing: bool,
+    // the dotfiles are listed and served, they are hidden otherwise
+    pub show_hidden_files: bool,
+    // the files served for a request to a directory, in order of preference
+    pub index_files: Vec<String>,
+    // the status of the requests to a directory without index files, when it isn't listed
+    pub missing_index_status: StatusCode,
+    // the `Cache-Control` of its files, over the `max-age` of the directories
+    pub cache_control: Option<HeaderValue>,
+}
+
+impl Directory {
+    /// The `Cache-Control` of the files, the one of the directory or a `max-age` of the given
+    /// seconds, none when `0`
+    pub fn cache_control(&self, max_age: u64) -> Option<HeaderValue> {
+        self.cache_control.clone().or_else(|| {
+            (max_age > 0).then(|| HeaderValue::from_str(&format!("max-age={max_age}")).unwrap())
+        })
+    }
+
+    /// The service of the files. The redirects to the trailing slash of a directory have an
+    /// absolute `Location` with `absolute_redirects`, on the host of the request.
+    pub fn files(&self, absolute_redirects: bool, trust_proxy_headers: bool) -> Files {
+        let directory = self.clone();
+        let redirects = absolute_redirects.then_some(trust_proxy_headers);
+        // the requests to directories are always rendered by `index`, which handles
+        // the index files and the listing itself
+        let files = Files::new(&self.route, &self.directory_path)
+            .show_files_listing()
+            .files_listing_renderer(move |dir, req| directory.index(dir, req, redirects));
+        if self.show_hidden_files {
+            files.use_hidden_files()
+        } else {
+            files
+        }
+    }
+
+    /// `absolute_redirects` tells whether the proxy headers are trusted for the absolute
+    /// `Location` of the redirects, which are relative without it
+    fn index(
+        &self,
+        dir: &actix_files::Directory,
+        req: &HttpRequest,
+        absolute_redirects: Option<bool>,
+    ) -> io::Result<ServiceResponse> {
+        // the relative links of the pages only resolve inside the directory with a trailing slash
+        if !req.path().ends_with('/') {
+            let mut location = format!("{}/", req.path());
+            if !req.query_string().is_empty() {
+                location.push('?');
+                location.push_str(req.query_string());
+            }
+            if let Some(trust_proxy_headers) = absolute_redirects {
+                location = Url::of_request(req, trust_proxy_headers).absolute(&location);
+            }
+            let response = HttpResponse::MovedPermanently()
+                .insert_header((header::LOCATION, location))
+                .finish();
+            return Ok(ServiceResponse::new(req.clone(), response));
+        }
+
+        for index_file in &self.index_files {
+            if let Ok(file) = NamedFile::open(dir.path.join(index_file)) {
+                return Ok(ServiceResponse::new(req.clone(), file.into_response(req)));
+            }
+        }
+
+        let response = if self.show_files_listing {
+            self.files_listing(dir, req)?
+        } else {
+            HttpResponse::build(self.missing_index_status).finish()
+        };
+        Ok(ServiceResponse::new(req.clone(), response))
+    }
+
+    /// An html page linking to the entries of the directory, with their size and modification time
+    fn files_listing(
+        &self,
+        dir: &actix_files::Directory,
+        req: &HttpRequest,
+    ) -> io::Result<HttpResponse> {
+        let mut entries = vec![];
+        for entry in dir.path.read_dir()? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') && !self.show_hidden_files {
+                continue;
+            }
+            let metadata = match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() || metadata.is_file() => metadata,
+                _ => continue,
+            };
+            entries.push((name, metadata));
+        }
+        // the directories are listed first
+        entries.sort_by(|(a, a_metadata), (b, b_metadata)| {
+            b_metadata.is_dir().cmp(&a_metadata.is_dir()).then(a.cmp(b))
+        });
+
+        let mut rows = String::new();
+        if req.path().trim_end_matches('/') != self.route.trim_end_matches('/') {
+            rows.push_str("<tr><td><a href=\"../\">../</a></td><td>-</td><td>-</td></tr>");
+        }
+        for (mut name, metadata) in entries {
+            let size = if metadata.is_dir() {
+                name.push('/');
+                "-".to_string()
+            } else {
+                metadata.len().to_string()
+            };
+            let modified = metadata
+                .modified()
+                .map(|modified| HttpDate::from(modified).to_string())
+                .unwrap_or_else(|_| "-".to_string());
+            let _ = write!(
+                rows,
+                "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>",
+                encode_path(&name),
+                escape_html(&name),
+                size,
+                modified
+            );
+        }
+
+        let title = format!("Index of {}", escape_html(req.path()));
+        Ok(HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(format!(
+                "<html><head><title>{}</title></head><body><h1>{}</h1>\
+                 <table><tr><th>Name</th><th>Size</th><th>Last modified</th></tr>{}</table>\
+                 </body></html>\n",
+                title, title, rows
+            )))
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Percent encodes a relative path, leaving the slashes separating its segments
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            byte => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
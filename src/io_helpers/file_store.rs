@@ -0,0 +1,151 @@
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+
+pub const DEFAULT_FILE_BREAKER_COOLDOWN: u64 = 30_000; // 30s
+
+/// The state of the breaker of the file store, as exposed in the metrics
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed = 0,
+    Open = 1,
+    // the cooldown is over, a read is probing the store
+    HalfOpen = 2,
+}
+
+/// Counts the failed reads of the files served, and stops reading them once too many failed in
+/// a row, so a failing disk is answered with a `503` rather than an error for every request
+struct Breaker {
+    // `0` never opens the breaker, the failures are still counted
+    threshold: usize,
+    cooldown: Duration,
+    consecutive_failures: usize,
+    // when the breaker last opened, `None` while it is closed
+    opened: Option<Instant>,
+    probing: bool,
+}
+
+// set by the server as it is created, the files are read by the handlers too
+static BREAKER: Mutex<Breaker> = Mutex::new(Breaker {
+    threshold: 0,
+    cooldown: Duration::from_millis(DEFAULT_FILE_BREAKER_COOLDOWN),
+    consecutive_failures: 0,
+    opened: None,
+    probing: false,
+});
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+static TRIPS: AtomicU64 = AtomicU64::new(0);
+
+/// The counters of the file store, rendered in the metrics
+#[derive(Clone, Copy, Debug)]
+pub struct FileStoreStats {
+    pub state: BreakerState,
+    pub failures: u64,
+    pub trips: u64,
+}
+
+fn breaker() -> MutexGuard<'static, Breaker> {
+    // the state stays consistent when a thread panics while holding it
+    BREAKER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Opens the breaker after `threshold` failed reads in a row, `0` never opens it. It stays open
+/// for the cooldown, then the next read probes the store and closes it if it succeeds.
+pub fn configure_file_breaker(threshold: usize, cooldown: Duration) {
+    let mut breaker = breaker();
+    breaker.threshold = threshold;
+    breaker.cooldown = cooldown;
+}
+
+/// Reads from the file store through the breaker. While it is open the store isn't read and
+/// the time left to its cooldown is returned instead, to be sent in `Retry-After`.
+pub fn read_file_store<T>(read: impl FnOnce() -> io::Result<T>) -> Result<io::Result<T>, Duration> {
+    let probe = admit_file_read()?;
+    let result = read();
+    record_file_read(&result, probe);
+    Ok(result)
+}
+
+/// Whether the file store can be read, and if the read probes it, else the time left to the
+/// cooldown of the breaker. Once it is over a single read probes the store, a probe that never
+/// completes is retried after another cooldown.
+pub fn admit_file_read() -> Result<bool, Duration> {
+    let mut breaker = breaker();
+    let opened = match breaker.opened {
+        Some(opened) => opened,
+        None => return Ok(false),
+    };
+    let elapsed = opened.elapsed();
+    if elapsed < breaker.cooldown {
+        return Err((breaker.cooldown - elapsed).max(Duration::from_secs(1)));
+    }
+    breaker.probing = true;
+    breaker.opened = Some(Instant::now());
+    Ok(true)
+}
+
+/// Counts the result of a read of the file store, `probe` when it was admitted as the probe of
+/// the store. Only the probe closes the breaker, the reads admitted before it opened may still
+/// complete. A missing file tells nothing of the store, it is neither a failure nor a success.
+pub fn record_file_read<T>(result: &io::Result<T>, probe: bool) {
+    let error = match result {
+        Ok(_) => {
+            let mut breaker = breaker();
+            breaker.consecutive_failures = 0;
+            if probe && breaker.probing {
+                breaker.probing = false;
+                breaker.opened = None;
+                warn!("The file store recovered, the files are served again");
+            }
+            return;
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let mut breaker = breaker();
+            if probe && breaker.probing {
+                // the next read probes the store again, without another cooldown
+                let now = Instant::now();
+                breaker.probing = false;
+                breaker.opened = Some(now.checked_sub(breaker.cooldown).unwrap_or(now));
+            }
+            return;
+        }
+        Err(e) => e,
+    };
+
+    FAILURES.fetch_add(1, Relaxed);
+    let mut breaker = breaker();
+    breaker.consecutive_failures += 1;
+    if probe && breaker.probing {
+        // the store still fails, it is given another cooldown
+        breaker.probing = false;
+        breaker.opened = Some(Instant::now());
+    } else if breaker.opened.is_none()
+        && breaker.threshold != 0
+        && breaker.consecutive_failures >= breaker.threshold
+    {
+        breaker.opened = Some(Instant::now());
+        TRIPS.fetch_add(1, Relaxed);
+        error!(
+            "The file store failed {} times in a row, the files are answered with a 503 for {:?}: {}",
+            breaker.consecutive_failures, breaker.cooldown, error
+        );
+    }
+}
+
+pub fn file_store_stats() -> FileStoreStats {
+    let breaker = breaker();
+    let state = match breaker.opened {
+        None => BreakerState::Closed,
+        Some(_) if breaker.probing => BreakerState::HalfOpen,
+        Some(opened) if opened.elapsed() >= breaker.cooldown => BreakerState::HalfOpen,
+        Some(_) => BreakerState::Open,
+    };
+    FileStoreStats {
+        state,
+        failures: FAILURES.load(Relaxed),
+        trips: TRIPS.load(Relaxed),
+    }
+}
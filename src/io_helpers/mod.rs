@@ -1,9 +1,19 @@
+pub mod directory;
+pub mod file_store;
+
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
+use std::sync::OnceLock;
 
+use actix_files::file_extension_to_mime;
 use actix_web::HttpResponseBuilder;
 use anyhow::Result;
+use dashmap::DashMap;
+
+// the content types registered for file extensions, over the default ones
+static MIME_TYPES: OnceLock<DashMap<String, String>> = OnceLock::new();
 
 // this should be something else
 // probably inside the submodule of the http router
@@ -30,3 +40,36 @@ pub fn read_file(file_path: &str) -> Result<String> {
     file.read_to_end(&mut buf)?;
     Ok(String::from_utf8_lossy(&buf).to_string())
 }
+
+/// Registers the content type of the files with the given extension, e.g. `mjs` or `.mjs`.
+/// It takes precedence over the default content type of the extension, for the files served by
+/// the handlers and the ones of the directories.
+pub fn add_mime_type(extension: &str, mime_type: &str) {
+    MIME_TYPES.get_or_init(DashMap::new).insert(
+        extension.trim_start_matches('.').to_ascii_lowercase(),
+        mime_type.to_string(),
+    );
+}
+
+/// The content type registered with `add_mime_type` for the extension of a file, if any
+pub fn registered_mime_type(file_path: &str) -> Option<String> {
+    let mime_types = MIME_TYPES.get()?;
+    let extension = Path::new(file_path).extension()?.to_str()?;
+    mime_types
+        .get(&extension.to_ascii_lowercase())
+        .map(|mime_type| mime_type.value().clone())
+}
+
+/// The content type of a file, derived from its extension.
+/// Files with an unknown extension or without one are `application/octet-stream`.
+pub fn content_type_for(file_path: &str) -> String {
+    if let Some(mime_type) = registered_mime_type(file_path) {
+        return mime_type;
+    }
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    file_extension_to_mime(&extension).to_string()
+}
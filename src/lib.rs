@@ -1,21 +1,40 @@
 mod executors;
 mod io_helpers;
+mod logging;
+mod metrics;
 mod routers;
 mod server;
 mod shared_socket;
+mod test_client;
 mod types;
 mod web_socket_connection;
 
+/// The internals measured by the benchmarks of `benches/`, which aren't part of the API
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::routers::RouteTable;
+    pub use crate::server::Server;
+    pub use crate::types::{request::Request, response::Response, HttpMethod};
+}
+
+use executors::offload;
 use server::Server;
 use shared_socket::SocketHeld;
+use test_client::TestClient;
 
 // pyO3 module
 use pyo3::prelude::*;
 use types::{
+    body_stream::BodyStream,
+    cancellation::CancellationToken,
+    file_stream::FileChunks,
     function_info::{FunctionInfo, MiddlewareType},
     identity::Identity,
+    multipart::UploadedFile,
     request::PyRequest,
     response::PyResponse,
+    route_info::RouteInfo,
+    tunnel::Tunnel,
     HttpMethod,
 };
 
@@ -25,17 +44,30 @@ fn get_version() -> String {
 }
 
 #[pymodule]
-pub fn robyn(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+pub fn robyn(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     // the pymodule class/function to make the rustPyFunctions available
     m.add_function(wrap_pyfunction!(get_version, m)?)?;
+    m.add_function(wrap_pyfunction!(offload, m)?)?;
     m.add_class::<Server>()?;
     m.add_class::<SocketHeld>()?;
+    m.add_class::<TestClient>()?;
     m.add_class::<FunctionInfo>()?;
     m.add_class::<Identity>()?;
+    m.add_class::<BodyStream>()?;
+    m.add_class::<CancellationToken>()?;
+    m.add_class::<FileChunks>()?;
+    m.add_class::<UploadedFile>()?;
     m.add_class::<PyRequest>()?;
     m.add_class::<PyResponse>()?;
+    m.add_class::<RouteInfo>()?;
+    m.add_class::<Tunnel>()?;
     m.add_class::<MiddlewareType>()?;
     m.add_class::<HttpMethod>()?;
+    // raised in Python for the panics of Rust, they resume as panics when they reach it again
+    m.add(
+        "PanicException",
+        py.get_type::<pyo3::panic::PanicException>(),
+    )?;
     pyo3::prepare_freethreaded_python();
     Ok(())
 }
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use pyo3::prelude::*;
+
+// actix answers the requests it can't parse before they reach the server, and only traces why
+const DISPATCHER_TARGET: &str = "actix_http::h1::dispatcher";
+const PARSE_ERROR_PREFIX: &str = "parse error ";
+const HEAD_TOO_LARGE: &str = "request head was too big";
+
+// the headers whose values are never logged, in lowercase
+const DEFAULT_REDACTED_HEADERS: [&str; 3] = ["authorization", "cookie", "set-cookie"];
+const REDACTED: &str = "<redacted>";
+
+// the other headers redacted in the logs, set by the server as it is created
+static REDACTED_HEADERS: RwLock<Vec<String>> = RwLock::new(vec![]);
+// the python function given each message before it is logged
+static REDACTOR: RwLock<Option<Py<PyAny>>> = RwLock::new(None);
+
+/// Redacts the headers in the logs, in addition to `Authorization`, `Cookie` and `Set-Cookie`
+pub fn set_redacted_headers(names: Vec<String>) {
+    *REDACTED_HEADERS.write().unwrap_or_else(|e| e.into_inner()) = names
+        .into_iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect();
+}
+
+/// Gives each message logged by the server to the function, which returns it as it is logged,
+/// e.g. with the tokens of the query strings masked
+pub fn set_redactor(redactor: Py<PyAny>) {
+    *REDACTOR.write().unwrap_or_else(|e| e.into_inner()) = Some(redactor);
+}
+
+fn is_redacted(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    DEFAULT_REDACTED_HEADERS.contains(&name.as_str())
+        || REDACTED_HEADERS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&name)
+}
+
+/// The headers as they are logged, with the values of the redacted ones replaced. They are only
+/// redacted when the log is written.
+pub struct LoggedHeaders<'a>(pub &'a HashMap<String, String>);
+
+impl fmt::Debug for LoggedHeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(name, value)| {
+                let value = if is_redacted(name) { REDACTED } else { value };
+                (name, value)
+            }))
+            .finish()
+    }
+}
+
+/// The message given back by the redactor. A message it fails on is replaced by the name of the
+/// exception, as it may be the one it was meant to redact.
+fn redact(message: String, redactor: &Py<PyAny>) -> String {
+    Python::with_gil(|py| {
+        redactor
+            .call1(py, (message,))
+            .and_then(|message| message.extract::<String>(py))
+            .unwrap_or_else(|e| {
+                format!(
+                    "The log redactor raised a {} on a message",
+                    e.get_type(py).name().unwrap_or("exception")
+                )
+            })
+    })
+}
+
+/// Forwards the logs to the python loggers, like `pyo3_log`. When the dispatcher of actix logs
+/// in debug, its traces of the malformed requests are forwarded as debug logs, which tell the
+/// developers why a client got a `400` or a `431`.
+struct Logger {
+    inner: pyo3_log::Logger,
+    malformed_requests: bool,
+}
+
+impl Logger {
+    fn is_dispatcher_trace(metadata: &Metadata) -> bool {
+        metadata.level() == Level::Trace && metadata.target() == DISPATCHER_TARGET
+    }
+
+    /// Logs the message in place of the one of the record
+    fn forward(&self, record: &Record, level: Level, message: &str) {
+        self.inner.log(
+            &Record::builder()
+                .args(format_args!("{}", message))
+                .level(level)
+                .target(record.target())
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .build(),
+        );
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        if Self::is_dispatcher_trace(metadata) {
+            return self.malformed_requests;
+        }
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !Self::is_dispatcher_trace(record.metadata()) {
+            let redactor = REDACTOR.read().unwrap_or_else(|e| e.into_inner()).clone();
+            return match redactor {
+                Some(redactor) if self.inner.enabled(record.metadata()) => self.forward(
+                    record,
+                    record.level(),
+                    &redact(record.args().to_string(), &redactor),
+                ),
+                Some(_) => (),
+                None => self.inner.log(record),
+            };
+        }
+        if !self.malformed_requests {
+            return;
+        }
+        let message = record.args().to_string();
+        let reason = match message.strip_prefix(PARSE_ERROR_PREFIX) {
+            Some(error) => format!("answered with a 400: {}", error),
+            None if message.starts_with(HEAD_TOO_LARGE) => {
+                "answered with a 431: the head is above the size limit or has more than 96 headers"
+                    .to_string()
+            }
+            None => return,
+        };
+        let message = format!("Malformed request {}", reason);
+        let redactor = REDACTOR.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let message = match redactor {
+            Some(redactor) => redact(message, &redactor),
+            None => message,
+        };
+        self.forward(record, Level::Debug, &message);
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the logger, once per process. The other traces are only let through by the log
+/// macros when the malformed requests are logged.
+pub fn init(py: Python) -> PyResult<()> {
+    let malformed_requests = py
+        .import("logging")?
+        .call_method1("getLogger", (DISPATCHER_TARGET.replace("::", "."),))?
+        .call_method1("isEnabledFor", (10,))?
+        .is_true()?;
+    let logger = Logger {
+        inner: pyo3_log::Logger::new(py, pyo3_log::Caching::LoggersAndLevels)?,
+        malformed_requests,
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(if malformed_requests {
+            LevelFilter::Trace
+        } else {
+            LevelFilter::Debug
+        });
+    }
+    Ok(())
+}
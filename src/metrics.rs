@@ -0,0 +1,267 @@
+use std::fmt::Write;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actix_web::body::{BodySize, BoxBody, MessageBody};
+use actix_web::web::Bytes;
+use dashmap::DashMap;
+
+use crate::io_helpers::file_store::FileStoreStats;
+
+/// The upper bounds, in seconds, of the buckets of the duration histograms
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The label of the requests that didn't match any route
+pub const NOT_FOUND_ROUTE: &str = "<not found>";
+
+/// The pattern of the route matching a request, stored in the request extensions
+#[derive(Clone, Debug)]
+pub struct MatchedRoute(pub String);
+
+#[derive(Default)]
+struct DurationHistogram {
+    buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl DurationHistogram {
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bucket, upper_bound) in self.buckets.iter().zip(DURATION_BUCKETS) {
+            if seconds <= upper_bound {
+                bucket.fetch_add(1, Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Relaxed);
+    }
+
+    /// Renders the samples of the histogram, with the labels given as `route="/",` or empty
+    fn render(&self, output: &mut String, name: &str, labels: &str) {
+        // the buckets are already cumulative as a duration is counted in every bucket it fits
+        for (bucket, upper_bound) in self.buckets.iter().zip(DURATION_BUCKETS) {
+            let _ = writeln!(
+                output,
+                "{}_bucket{{{}le=\"{}\"}} {}",
+                name,
+                labels,
+                upper_bound,
+                bucket.load(Relaxed)
+            );
+        }
+        let count = self.count.load(Relaxed);
+        let _ = writeln!(output, "{}_bucket{{{}le=\"+Inf\"}} {}", name, labels, count);
+        let labels = labels.trim_end_matches(',');
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", labels)
+        };
+        let _ = writeln!(
+            output,
+            "{}_sum{} {}",
+            name,
+            labels,
+            self.sum_micros.load(Relaxed) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(output, "{}_count{} {}", name, labels, count);
+    }
+}
+
+/// The request metrics of a process, exposed in the Prometheus text format.
+/// They are only kept with atomic counters, so recording a request takes no lock
+/// once its route and status code have been seen.
+#[derive(Default)]
+pub struct Metrics {
+    requests: DashMap<(String, u16), AtomicU64>,
+    // from the request to its whole response, and to the head of the response, which only
+    // differ for the streamed bodies
+    durations: DashMap<String, DurationHistogram>,
+    first_bytes: DashMap<String, DurationHistogram>,
+    // how late the timers of the workers fire, which is the time a request ready to be served
+    // waits for its worker, e.g. while it runs a sync handler
+    worker_lag: DurationHistogram,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(
+        &self,
+        route: &str,
+        status_code: u16,
+        time_to_first_byte: Duration,
+        duration: Duration,
+    ) {
+        self.requests
+            .entry((route.to_string(), status_code))
+            .or_default()
+            .fetch_add(1, Relaxed);
+
+        self.first_bytes
+            .entry(route.to_string())
+            .or_default()
+            .observe(time_to_first_byte);
+        self.durations
+            .entry(route.to_string())
+            .or_default()
+            .observe(duration);
+    }
+
+    pub fn record_worker_lag(&self, lag: Duration) {
+        self.worker_lag.observe(lag);
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format
+    pub fn render(
+        &self,
+        open_connections: usize,
+        throttled_accepts: usize,
+        busy_workers: usize,
+        file_store: FileStoreStats,
+    ) -> String {
+        let mut output = String::new();
+
+        output.push_str(
+            "# HELP robyn_requests_total The number of requests answered, by route and status code.\n\
+             # TYPE robyn_requests_total counter\n",
+        );
+        for entry in self.requests.iter() {
+            let (route, status_code) = entry.key();
+            let _ = writeln!(
+                output,
+                "robyn_requests_total{{route=\"{}\",status=\"{}\"}} {}",
+                escape_label(route),
+                status_code,
+                entry.value().load(Relaxed)
+            );
+        }
+
+        output.push_str(
+            "# HELP robyn_request_duration_seconds The time taken to answer requests until their response is sent, by route.\n\
+             # TYPE robyn_request_duration_seconds histogram\n",
+        );
+        for entry in self.durations.iter() {
+            let labels = format!("route=\"{}\",", escape_label(entry.key()));
+            entry
+                .value()
+                .render(&mut output, "robyn_request_duration_seconds", &labels);
+        }
+
+        output.push_str(
+            "# HELP robyn_time_to_first_byte_seconds The time taken to answer requests until the head of their response is sent, by route.\n\
+             # TYPE robyn_time_to_first_byte_seconds histogram\n",
+        );
+        for entry in self.first_bytes.iter() {
+            let labels = format!("route=\"{}\",", escape_label(entry.key()));
+            entry
+                .value()
+                .render(&mut output, "robyn_time_to_first_byte_seconds", &labels);
+        }
+
+        output.push_str(
+            "# HELP robyn_worker_lag_seconds How late the timers of the workers fired, the time a request waits for a busy worker.\n\
+             # TYPE robyn_worker_lag_seconds histogram\n",
+        );
+        self.worker_lag
+            .render(&mut output, "robyn_worker_lag_seconds", "");
+
+        let _ = write!(
+            output,
+            "# HELP robyn_open_connections The number of open connections.\n\
+             # TYPE robyn_open_connections gauge\n\
+             robyn_open_connections {}\n\
+             # HELP robyn_throttled_accepts The number of connections held back by the accept rate until the accepts resume.\n\
+             # TYPE robyn_throttled_accepts gauge\n\
+             robyn_throttled_accepts {}\n",
+            open_connections, throttled_accepts
+        );
+        let _ = write!(
+            output,
+            "# HELP robyn_busy_workers The number of workers running a sync handler or middleware.\n\
+             # TYPE robyn_busy_workers gauge\n\
+             robyn_busy_workers {}\n",
+            busy_workers
+        );
+        let _ = write!(
+            output,
+            "# HELP robyn_file_store_errors_total The number of failed reads of the files served.\n\
+             # TYPE robyn_file_store_errors_total counter\n\
+             robyn_file_store_errors_total {}\n\
+             # HELP robyn_file_store_breaker_trips_total The number of times the breaker of the file store opened.\n\
+             # TYPE robyn_file_store_breaker_trips_total counter\n\
+             robyn_file_store_breaker_trips_total {}\n\
+             # HELP robyn_file_store_breaker_state The state of the breaker of the file store, 0 when closed, 1 when open and 2 when half open.\n\
+             # TYPE robyn_file_store_breaker_state gauge\n\
+             robyn_file_store_breaker_state {}\n",
+            file_store.failures,
+            file_store.trips,
+            file_store.state as u8
+        );
+        output
+    }
+}
+
+/// The body of a response, calling `on_end` once it is sent, or dropped as its client is gone.
+/// The head of the response is sent before the body is polled, so the time to the first byte is
+/// the one taken to build the response, and the whole time is only known here.
+pub struct TimedBody {
+    body: BoxBody,
+    on_end: Option<Box<dyn FnOnce()>>,
+}
+
+impl TimedBody {
+    pub fn new(body: impl MessageBody + 'static, on_end: impl FnOnce() + 'static) -> Self {
+        Self {
+            body: BoxBody::new(body),
+            on_end: Some(Box::new(on_end)),
+        }
+    }
+
+    fn end(&mut self) {
+        if let Some(on_end) = self.on_end.take() {
+            on_end();
+        }
+    }
+}
+
+impl MessageBody for TimedBody {
+    type Error = Box<dyn std::error::Error>;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+        let chunk = Pin::new(&mut this.body).poll_next(cx);
+        if matches!(chunk, Poll::Ready(None) | Poll::Ready(Some(Err(_)))) {
+            this.end();
+        }
+        chunk
+    }
+}
+
+impl Drop for TimedBody {
+    fn drop(&mut self) {
+        self.end();
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
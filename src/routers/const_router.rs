@@ -7,23 +7,24 @@ use crate::types::function_info::FunctionInfo;
 use crate::types::request::Request;
 use crate::types::response::Response;
 use crate::types::HttpMethod;
-use anyhow::Context;
 use log::debug;
-use matchit::Router as MatchItRouter;
 use pyo3::types::PyAny;
 
-use anyhow::{Error, Result};
+use anyhow::{Context, Error, Result};
 
-use crate::routers::Router;
+use crate::routers::http_router::routes_of;
+use crate::routers::{RouteTable, Router};
 
-type RouteMap = RwLock<MatchItRouter<Response>>;
+// the responses are stored with the pattern of their route, e.g. `/users/:id`
+type RouteMap = RouteTable<(Response, String)>;
 
-/// Contains the thread safe hashmaps of different routes
+/// Contains the thread safe hashmaps of different routes.
+/// The maps of all the methods are behind a single lock.
 pub struct ConstRouter {
-    routes: HashMap<HttpMethod, Arc<RouteMap>>,
+    routes: Arc<RwLock<HashMap<HttpMethod, RouteMap>>>,
 }
 
-impl Router<Response, HttpMethod> for ConstRouter {
+impl Router<(Response, String), HttpMethod> for ConstRouter {
     /// Doesn't allow query params/body/etc as variables cannot be "memoized"/"const"ified
     fn add_route(
         &self,
@@ -32,72 +33,73 @@ impl Router<Response, HttpMethod> for ConstRouter {
         function: FunctionInfo,
         event_loop: Option<&PyAny>,
     ) -> Result<(), Error> {
-        let table = self
-            .routes
+        self.routes
+            .read()
+            .unwrap()
             .get(route_type)
-            .context("No relevant map")?
-            .clone();
-
-        let route = route.to_string();
+            .context("No relevant map")?;
         let event_loop =
             event_loop.context("Event loop must be provided to add a route to the const router")?;
 
-        pyo3_asyncio::tokio::run_until_complete(event_loop, async move {
-            let output = execute_http_function(&Request::default(), &function)
+        let output = pyo3_asyncio::tokio::run_until_complete(event_loop, async move {
+            let mut output = execute_http_function(&Request::default(), &function)
                 .await
-                .unwrap();
+                .unwrap()
+                .with_cache_control(function.cache_control.as_deref());
+            // the response is sent to every request, so an iterator body is read once here
+            output.collect_stream()?;
             debug!("This is the result of the output {:?}", output);
-            table.write().unwrap().insert(route, output).unwrap();
-            Ok(())
+            Ok(output)
         })?;
 
-        Ok(())
+        self.add_response(route_type, route, output)
     }
 
-    fn get_route(&self, route_method: &HttpMethod, route: &str) -> Option<Response> {
-        let table = self.routes.get(route_method)?;
-        let route_map = table.read().ok()?;
+    fn get_route(&self, route_method: &HttpMethod, route: &str) -> Option<(Response, String)> {
+        let routes = self.routes.read().ok()?;
+        let route_map = routes.get(route_method)?;
 
         match route_map.at(route) {
             Ok(res) => Some(res.value.clone()),
             Err(_) => None,
         }
     }
+
+    fn routes(&self) -> Vec<(HttpMethod, String)> {
+        routes_of(&self.routes.read().unwrap())
+    }
 }
 
 impl ConstRouter {
     pub fn new() -> Self {
         let mut routes = HashMap::new();
-        routes.insert(HttpMethod::GET, Arc::new(RwLock::new(MatchItRouter::new())));
-        routes.insert(
-            HttpMethod::POST,
-            Arc::new(RwLock::new(MatchItRouter::new())),
-        );
-        routes.insert(HttpMethod::PUT, Arc::new(RwLock::new(MatchItRouter::new())));
-        routes.insert(
-            HttpMethod::DELETE,
-            Arc::new(RwLock::new(MatchItRouter::new())),
-        );
-        routes.insert(
-            HttpMethod::PATCH,
-            Arc::new(RwLock::new(MatchItRouter::new())),
-        );
-        routes.insert(
-            HttpMethod::HEAD,
-            Arc::new(RwLock::new(MatchItRouter::new())),
-        );
-        routes.insert(
-            HttpMethod::OPTIONS,
-            Arc::new(RwLock::new(MatchItRouter::new())),
-        );
-        routes.insert(
-            HttpMethod::CONNECT,
-            Arc::new(RwLock::new(MatchItRouter::new())),
-        );
-        routes.insert(
-            HttpMethod::TRACE,
-            Arc::new(RwLock::new(MatchItRouter::new())),
-        );
-        Self { routes }
+        routes.insert(HttpMethod::GET, RouteTable::new());
+        routes.insert(HttpMethod::POST, RouteTable::new());
+        routes.insert(HttpMethod::PUT, RouteTable::new());
+        routes.insert(HttpMethod::DELETE, RouteTable::new());
+        routes.insert(HttpMethod::PATCH, RouteTable::new());
+        routes.insert(HttpMethod::HEAD, RouteTable::new());
+        routes.insert(HttpMethod::OPTIONS, RouteTable::new());
+        routes.insert(HttpMethod::CONNECT, RouteTable::new());
+        routes.insert(HttpMethod::TRACE, RouteTable::new());
+        Self {
+            routes: Arc::new(RwLock::new(routes)),
+        }
+    }
+
+    /// Adds a route answered with the response, as computed by `add_route` from its handler
+    pub fn add_response(
+        &self,
+        route_type: &HttpMethod,
+        route: &str,
+        response: Response,
+    ) -> Result<(), Error> {
+        self.routes
+            .write()
+            .unwrap()
+            .get_mut(route_type)
+            .context("No relevant map")?
+            .insert(route, (response, route.to_string()))?;
+        Ok(())
     }
 }
@@ -1,23 +1,26 @@
 use std::collections::HashMap;
 use std::sync::RwLock;
 
-use matchit::Router as MatchItRouter;
 use pyo3::types::PyAny;
 
 use anyhow::{Context, Result};
 
-use crate::routers::Router;
+use crate::routers::{RouteTable, Router};
 use crate::types::function_info::FunctionInfo;
 use crate::types::HttpMethod;
 
-type RouteMap = RwLock<MatchItRouter<FunctionInfo>>;
+// the functions are stored with the pattern of their route, e.g. `/users/:id`
+type RouteMap = RouteTable<(FunctionInfo, String)>;
 
-/// Contains the thread safe hashmaps of different routes
+/// Contains the thread safe hashmaps of different routes.
+/// The maps of all the methods are behind a single lock.
 pub struct HttpRouter {
-    routes: HashMap<HttpMethod, RouteMap>,
+    routes: RwLock<HashMap<HttpMethod, RouteMap>>,
+    // tried in order with the requests matching no route
+    fallbacks: RwLock<Vec<FunctionInfo>>,
 }
 
-impl Router<(FunctionInfo, HashMap<String, String>), HttpMethod> for HttpRouter {
+impl Router<(FunctionInfo, HashMap<String, String>, String), HttpMethod> for HttpRouter {
     fn add_route(
         &self,
         route_type: &HttpMethod,
@@ -25,10 +28,10 @@ impl Router<(FunctionInfo, HashMap<String, String>), HttpMethod> for HttpRouter
         function: FunctionInfo,
         _event_loop: Option<&PyAny>,
     ) -> Result<()> {
-        let table = self.routes.get(route_type).context("No relevant map")?;
-
         // try removing unwrap here
-        table.write().unwrap().insert(route.to_string(), function)?;
+        let mut routes = self.routes.write().unwrap();
+        let table = routes.get_mut(route_type).context("No relevant map")?;
+        table.insert(route, (function, route.to_string()))?;
 
         Ok(())
     }
@@ -37,32 +40,61 @@ impl Router<(FunctionInfo, HashMap<String, String>), HttpMethod> for HttpRouter
         &self,
         route_method: &HttpMethod,
         route: &str,
-    ) -> Option<(FunctionInfo, HashMap<String, String>)> {
-        let table = self.routes.get(route_method)?;
-
-        let table_lock = table.read().ok()?;
-        let res = table_lock.at(route).ok()?;
+    ) -> Option<(FunctionInfo, HashMap<String, String>, String)> {
+        let routes = self.routes.read().ok()?;
+        let table = routes.get(route_method)?;
+        let res = table.at(route).ok()?;
         let mut route_params = HashMap::new();
         for (key, value) in res.params.iter() {
             route_params.insert(key.to_string(), value.to_string());
         }
 
-        Some((res.value.to_owned(), route_params))
+        let (function, pattern) = res.value;
+        Some((function.to_owned(), route_params, pattern.to_owned()))
+    }
+
+    fn routes(&self) -> Vec<(HttpMethod, String)> {
+        routes_of(&self.routes.read().unwrap())
     }
 }
 
 impl HttpRouter {
     pub fn new() -> Self {
         let mut routes = HashMap::new();
-        routes.insert(HttpMethod::GET, RwLock::new(MatchItRouter::new()));
-        routes.insert(HttpMethod::POST, RwLock::new(MatchItRouter::new()));
-        routes.insert(HttpMethod::PUT, RwLock::new(MatchItRouter::new()));
-        routes.insert(HttpMethod::DELETE, RwLock::new(MatchItRouter::new()));
-        routes.insert(HttpMethod::PATCH, RwLock::new(MatchItRouter::new()));
-        routes.insert(HttpMethod::HEAD, RwLock::new(MatchItRouter::new()));
-        routes.insert(HttpMethod::OPTIONS, RwLock::new(MatchItRouter::new()));
-        routes.insert(HttpMethod::CONNECT, RwLock::new(MatchItRouter::new()));
-        routes.insert(HttpMethod::TRACE, RwLock::new(MatchItRouter::new()));
-        Self { routes }
+        routes.insert(HttpMethod::GET, RouteTable::new());
+        routes.insert(HttpMethod::POST, RouteTable::new());
+        routes.insert(HttpMethod::PUT, RouteTable::new());
+        routes.insert(HttpMethod::DELETE, RouteTable::new());
+        routes.insert(HttpMethod::PATCH, RouteTable::new());
+        routes.insert(HttpMethod::HEAD, RouteTable::new());
+        routes.insert(HttpMethod::OPTIONS, RouteTable::new());
+        routes.insert(HttpMethod::CONNECT, RouteTable::new());
+        routes.insert(HttpMethod::TRACE, RouteTable::new());
+        Self {
+            routes: RwLock::new(routes),
+            fallbacks: RwLock::new(vec![]),
+        }
     }
+
+    pub fn add_fallback(&self, function: FunctionInfo) {
+        self.fallbacks.write().unwrap().push(function);
+    }
+
+    pub fn get_fallbacks(&self) -> Vec<FunctionInfo> {
+        self.fallbacks.read().unwrap().clone()
+    }
+}
+
+/// The methods and the patterns of the routes of the tables, grouped by method
+pub fn routes_of<T>(tables: &HashMap<HttpMethod, RouteTable<T>>) -> Vec<(HttpMethod, String)> {
+    HttpMethod::ALL
+        .iter()
+        .filter_map(|method| Some((method, tables.get(method)?)))
+        .flat_map(|(method, table)| {
+            table
+                .patterns()
+                .iter()
+                .map(move |pattern| (method.clone(), pattern.clone()))
+        })
+        .collect()
 }
@@ -2,13 +2,12 @@ use std::collections::HashMap;
 use std::sync::RwLock;
 
 use anyhow::{Context, Error, Result};
-use matchit::Router as MatchItRouter;
 use pyo3::types::PyAny;
 
-use crate::routers::Router;
+use crate::routers::{RouteTable, Router};
 use crate::types::function_info::{FunctionInfo, MiddlewareType};
 
-type RouteMap = RwLock<MatchItRouter<FunctionInfo>>;
+type RouteMap = RwLock<RouteTable<FunctionInfo>>;
 
 /// Contains the thread safe hashmaps of different routes
 pub struct MiddlewareRouter {
@@ -26,7 +25,7 @@ impl Router<(FunctionInfo, HashMap<String, String>), MiddlewareType> for Middlew
     ) -> Result<(), Error> {
         let table = self.routes.get(route_type).context("No relevant map")?;
 
-        table.write().unwrap().insert(route.to_string(), function)?;
+        table.write().unwrap().insert(route, function)?;
 
         Ok(())
     }
@@ -47,6 +46,20 @@ impl Router<(FunctionInfo, HashMap<String, String>), MiddlewareType> for Middlew
 
         Some((res.value.to_owned(), route_params))
     }
+
+    fn routes(&self) -> Vec<(MiddlewareType, String)> {
+        [MiddlewareType::BeforeRequest, MiddlewareType::AfterRequest]
+            .iter()
+            .flat_map(|middleware_type| {
+                let table = self.routes[middleware_type].read().unwrap();
+                table
+                    .patterns()
+                    .iter()
+                    .map(|pattern| (middleware_type.clone(), pattern.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
 }
 
 impl MiddlewareRouter {
@@ -57,12 +70,9 @@ impl MiddlewareRouter {
         let mut routes = HashMap::new();
         routes.insert(
             MiddlewareType::BeforeRequest,
-            RwLock::new(MatchItRouter::new()),
-        );
-        routes.insert(
-            MiddlewareType::AfterRequest,
-            RwLock::new(MatchItRouter::new()),
+            RwLock::new(RouteTable::new()),
         );
+        routes.insert(MiddlewareType::AfterRequest, RwLock::new(RouteTable::new()));
         Self { globals, routes }
     }
 
@@ -1,7 +1,13 @@
-use anyhow::Result;
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Context, Result};
+use matchit::{InsertError, Match, MatchError, Router as MatchItRouter};
 use pyo3::PyAny;
 
 use crate::types::function_info::FunctionInfo;
+use const_router::ConstRouter;
+use http_router::HttpRouter;
 
 pub mod const_router;
 pub mod http_router;
@@ -19,6 +25,95 @@ pub trait Router<T, U> {
         event_loop: Option<&PyAny>,
     ) -> Result<()>;
 
+    /// Inserts the routes of a table in order, stopping at the first one that can't be added,
+    /// e.g. as its type and pattern are already taken
+    fn add_routes<'r>(
+        &self,
+        routes: impl IntoIterator<Item = (U, &'r str, FunctionInfo)>,
+        event_loop: Option<&PyAny>,
+    ) -> Result<()>
+    where
+        Self: Sized,
+        U: Debug,
+    {
+        for (route_type, route, function) in routes {
+            self.add_route(&route_type, route, function, event_loop)
+                .with_context(|| format!("Error adding route {:?} {}", route_type, route))?;
+        }
+        Ok(())
+    }
+
     /// Retrieve the correct function from the previously inserted routes
     fn get_route(&self, route_type: &U, route: &str) -> Option<T>;
+
+    /// The types and the patterns of the inserted routes, in the order they were inserted
+    fn routes(&self) -> Vec<(U, String)>;
+}
+
+/// The routers of the HTTP routes, the const ones and the others, which are reloaded together.
+/// A request matches the routers it got from `current`, so it never finds the const routes of
+/// a reload with the other ones of the previous one.
+#[derive(Clone)]
+pub struct HttpRoutes {
+    current: Arc<RwLock<(Arc<HttpRouter>, Arc<ConstRouter>)>>,
+}
+
+impl HttpRoutes {
+    pub fn new() -> Self {
+        let routers = (Arc::new(HttpRouter::new()), Arc::new(ConstRouter::new()));
+        Self {
+            current: Arc::new(RwLock::new(routers)),
+        }
+    }
+
+    /// The routers the routes are added to and the requests are matched with
+    pub fn current(&self) -> (Arc<HttpRouter>, Arc<ConstRouter>) {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Replaces both routers at once. The requests being handled keep the routers they got.
+    pub fn replace(&self, router: HttpRouter, const_router: ConstRouter) {
+        *self.current.write().unwrap() = (Arc::new(router), Arc::new(const_router));
+    }
+}
+
+impl Default for HttpRoutes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A matchit router keeping the patterns inserted in it, as matchit can't list them
+pub struct RouteTable<T> {
+    router: MatchItRouter<T>,
+    patterns: Vec<String>,
+}
+
+impl<T> RouteTable<T> {
+    pub fn new() -> Self {
+        Self {
+            router: MatchItRouter::new(),
+            patterns: vec![],
+        }
+    }
+
+    pub fn insert(&mut self, route: &str, value: T) -> Result<(), InsertError> {
+        self.router.insert(route, value)?;
+        self.patterns.push(route.to_string());
+        Ok(())
+    }
+
+    pub fn at<'m, 'p>(&'m self, path: &'p str) -> Result<Match<'m, 'p, &'m T>, MatchError> {
+        self.router.at(path)
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+}
+
+impl<T> Default for RouteTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
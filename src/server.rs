@@ -1,87 +1,953 @@
-use crate::executors::{execute_event_handler, execute_http_function, execute_middleware_function};
+use crate::executors::{
+    busy_workers, execute_event_handler, execute_fallback_function, execute_http_function,
+    execute_middleware_function, execute_startup_handler, execute_tunnel_function,
+    set_sync_stack_size, set_workers,
+};
+use crate::io_helpers::directory::Directory;
+use crate::io_helpers::file_store::{
+    admit_file_read, configure_file_breaker, file_store_stats, record_file_read,
+    DEFAULT_FILE_BREAKER_COOLDOWN,
+};
+use crate::io_helpers::{add_mime_type, content_type_for, read_file, registered_mime_type};
+use crate::logging;
+use crate::metrics::{MatchedRoute, Metrics, TimedBody, NOT_FOUND_ROUTE};
 
 use crate::routers::const_router::ConstRouter;
-use crate::routers::Router;
+use crate::routers::{HttpRoutes, Router};
 
 use crate::routers::http_router::HttpRouter;
 use crate::routers::{middleware_router::MiddlewareRouter, web_socket_router::WebSocketRouter};
 use crate::shared_socket::SocketHeld;
+use crate::types::accept_rate::AcceptRate;
+use crate::types::body::{ignored_length_header, read_body};
+use crate::types::body_stream::BodyStream;
+use crate::types::cancellation::{is_draining, start_draining, CancelOnDrop, CancellationToken};
+use crate::types::deadline::parse_deadline;
+use crate::types::file_stream::{
+    set_mmap_files, set_response_buffer_size, DEFAULT_RESPONSE_BUFFER_SIZE,
+};
 use crate::types::function_info::{FunctionInfo, MiddlewareType};
+use crate::types::multipart::{form_boundary, read_form};
 use crate::types::request::Request;
-use crate::types::response::Response;
+use crate::types::response::{CloseConnection, Response};
+use crate::types::route_info::RouteInfo;
+use crate::types::tunnel::Tunnel;
 use crate::types::HttpMethod;
 use crate::types::MiddlewareReturn;
+use crate::types::SUPPORTED_METHODS;
 use crate::web_socket_connection::start_web_socket;
 
-use std::convert::TryInto;
-use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering::{Relaxed, SeqCst};
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, RwLock};
 
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::panic::AssertUnwindSafe;
 use std::process::abort;
+use std::str::FromStr;
+use std::time::{Instant, SystemTime};
 use std::{env, thread};
 
-use actix_files::Files;
 use actix_http::KeepAlive;
-use actix_web::web::Bytes;
+use actix_web::dev::{Extensions, Service};
+use actix_web::http::{
+    header::{HeaderName, HeaderValue},
+    StatusCode,
+};
+use actix_web::middleware::DefaultHeaders;
 use actix_web::*;
+use core_affinity::CoreId;
 use dashmap::DashMap;
+use futures_util::FutureExt;
+use socket2::SockRef;
+use tracing::{field, Instrument, Span};
+use uuid::Uuid;
 
 // pyO3 module
-use log::{debug, error};
+use log::{debug, error, warn};
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 
 const MAX_PAYLOAD_SIZE: &str = "ROBYN_MAX_PAYLOAD_SIZE";
 const DEFAULT_MAX_PAYLOAD_SIZE: usize = 1_000_000; // 1Mb
+const CLIENT_DISCONNECT_TIMEOUT: &str = "ROBYN_CLIENT_DISCONNECT_TIMEOUT";
+const DEFAULT_CLIENT_DISCONNECT_TIMEOUT: u64 = 1_000; // 1s
+const CLIENT_REQUEST_TIMEOUT: &str = "ROBYN_CLIENT_REQUEST_TIMEOUT";
+const DEFAULT_CLIENT_REQUEST_TIMEOUT: u64 = 5_000; // 5s
+const KEEP_ALIVE_TIMEOUT: &str = "ROBYN_KEEP_ALIVE_TIMEOUT";
+const DEFAULT_KEEP_ALIVE_TIMEOUT: u64 = 5_000; // 5s
+const KEEP_ALIVE: &str = "ROBYN_KEEP_ALIVE";
+const STATIC_MAX_AGE: &str = "ROBYN_STATIC_MAX_AGE";
+const DEFAULT_STATIC_MAX_AGE: u64 = 0; // no `Cache-Control`
+const FAVICON_FALLBACK: &str = "ROBYN_FAVICON_FALLBACK";
+const FAVICON: &str = "ROBYN_FAVICON";
+const MAX_URI_LENGTH: &str = "ROBYN_MAX_URI_LENGTH";
+const DEFAULT_MAX_URI_LENGTH: usize = 8_192; // 8Kb
+const MAX_HEADERS: &str = "ROBYN_MAX_HEADERS";
+// the parser of actix stops at this many headers, answering the request with a 431
+const PARSER_MAX_HEADERS: usize = 96;
+const CONFLICTING_LENGTH: &str = "ROBYN_CONFLICTING_LENGTH";
+const DEFAULT_CONFLICTING_LENGTH: &str = "reject";
+const MAX_CONNECTIONS: &str = "ROBYN_MAX_CONNECTIONS";
+const DEFAULT_MAX_CONNECTIONS: usize = 0; // no limit
+const MAX_CONNECTIONS_PER_IP: &str = "ROBYN_MAX_CONNECTIONS_PER_IP";
+const DEFAULT_MAX_CONNECTIONS_PER_IP: usize = 0; // no limit
+const SERVER_HEADER: &str = "ROBYN_SERVER_HEADER";
+const SOCKET_RECV_BUFFER_SIZE: &str = "ROBYN_SOCKET_RECV_BUFFER_SIZE";
+const SOCKET_SEND_BUFFER_SIZE: &str = "ROBYN_SOCKET_SEND_BUFFER_SIZE";
+const TRACE_ECHO: &str = "ROBYN_TRACE_ECHO";
+const WORKER_STACK_SIZE: &str = "ROBYN_WORKER_STACK_SIZE";
+const DEFAULT_WORKER_STACK_SIZE: usize = 0; // platform default
+const METRICS_PATH: &str = "ROBYN_METRICS_PATH";
+const TRUST_PROXY_HEADERS: &str = "ROBYN_TRUST_PROXY_HEADERS";
+const MAX_REQUESTS_PER_CONNECTION: &str = "ROBYN_MAX_REQUESTS_PER_CONNECTION";
+const PIN_WORKERS: &str = "ROBYN_PIN_WORKERS";
+const BROTLI_QUALITY: &str = "ROBYN_BROTLI_QUALITY";
+const RESPONSE_BUFFER_SIZE: &str = "ROBYN_RESPONSE_BUFFER_SIZE";
+const MMAP_FILES: &str = "ROBYN_MMAP_FILES";
+const ALLOWED_HOSTS: &str = "ROBYN_ALLOWED_HOSTS";
+const UNKNOWN_HOST_STATUS: &str = "ROBYN_UNKNOWN_HOST_STATUS";
+const DEFAULT_UNKNOWN_HOST_STATUS: u16 = 421;
+const DEADLINE_HEADER: &str = "ROBYN_DEADLINE_HEADER";
+const DRAIN_TIMEOUT: &str = "ROBYN_DRAIN_TIMEOUT";
+const DEFAULT_DRAIN_TIMEOUT: u64 = 0; // stopped right away
+const FILE_BREAKER_THRESHOLD: &str = "ROBYN_FILE_BREAKER_THRESHOLD";
+const FILE_BREAKER_COOLDOWN: &str = "ROBYN_FILE_BREAKER_COOLDOWN";
+const SLOW_REQUEST_THRESHOLD: &str = "ROBYN_SLOW_REQUEST_THRESHOLD";
+const DEFAULT_SLOW_REQUEST_THRESHOLD: u64 = 0; // not logged
+const OVERLOAD_RETRY_AFTER: &str = "ROBYN_OVERLOAD_RETRY_AFTER";
+const DEFAULT_OVERLOAD_RETRY_AFTER: u64 = 1; // 1s
+const DRAIN_RETRY_AFTER: &str = "ROBYN_DRAIN_RETRY_AFTER";
+const DEFAULT_DRAIN_RETRY_AFTER: u64 = 10; // 10s
+const OVERLOAD_PAGE: &str = "ROBYN_OVERLOAD_PAGE";
+const REDACTED_HEADERS: &str = "ROBYN_REDACTED_HEADERS";
+const ACCEPT_RATE: &str = "ROBYN_ACCEPT_RATE";
+const DEFAULT_ACCEPT_RATE: u32 = 0; // no limit
+const ACCEPT_BURST: &str = "ROBYN_ACCEPT_BURST";
+const ABSOLUTE_REDIRECTS: &str = "ROBYN_ABSOLUTE_REDIRECTS";
+const DEFAULT_HEADERS: &str = "ROBYN_DEFAULT_HEADERS";
+const REFUSE_ABSOLUTE_FORM: &str = "ROBYN_REFUSE_ABSOLUTE_FORM";
+const DEFAULT_MAX_REQUESTS_PER_CONNECTION: usize = 100;
+const REQUEST_ID_HEADER: &str = "x-request-id";
+// the longer ids sent by the clients are replaced, so they can't fill the logs
+const MAX_REQUEST_ID_LENGTH: usize = 200;
+
+// the settings which can be given in the config dict of the server
+const SETTINGS: [&str; 41] = [
+    MAX_PAYLOAD_SIZE,
+    CLIENT_DISCONNECT_TIMEOUT,
+    CLIENT_REQUEST_TIMEOUT,
+    KEEP_ALIVE_TIMEOUT,
+    MAX_URI_LENGTH,
+    MAX_CONNECTIONS,
+    SERVER_HEADER,
+    SOCKET_RECV_BUFFER_SIZE,
+    SOCKET_SEND_BUFFER_SIZE,
+    TRACE_ECHO,
+    WORKER_STACK_SIZE,
+    METRICS_PATH,
+    TRUST_PROXY_HEADERS,
+    MAX_REQUESTS_PER_CONNECTION,
+    PIN_WORKERS,
+    BROTLI_QUALITY,
+    RESPONSE_BUFFER_SIZE,
+    MMAP_FILES,
+    ALLOWED_HOSTS,
+    UNKNOWN_HOST_STATUS,
+    DEADLINE_HEADER,
+    DRAIN_TIMEOUT,
+    FILE_BREAKER_THRESHOLD,
+    FILE_BREAKER_COOLDOWN,
+    SLOW_REQUEST_THRESHOLD,
+    OVERLOAD_RETRY_AFTER,
+    DRAIN_RETRY_AFTER,
+    DEFAULT_HEADERS,
+    REFUSE_ABSOLUTE_FORM,
+    MAX_CONNECTIONS_PER_IP,
+    KEEP_ALIVE,
+    STATIC_MAX_AGE,
+    FAVICON_FALLBACK,
+    FAVICON,
+    MAX_HEADERS,
+    CONFLICTING_LENGTH,
+    OVERLOAD_PAGE,
+    REDACTED_HEADERS,
+    ACCEPT_RATE,
+    ACCEPT_BURST,
+    ABSOLUTE_REDIRECTS,
+];
+// not a setting of the server config, as the workers are given to `start`
+const WORKERS: &str = "ROBYN_WORKERS";
+// the number of workers when the cores the process may run on can't be known
+const FALLBACK_WORKERS: usize = 4;
 
 static STARTED: AtomicBool = AtomicBool::new(false);
 
-#[derive(Clone)]
-struct Directory {
-    route: String,
-    directory_path: String,
-    show_files_listing: bool,
-    index_file: Option<String>,
+/// The settings of the server, given in its config dict by the name of their environment
+/// variable in lowercase without the `ROBYN_` prefix, e.g. `max_payload_size`. The settings
+/// missing from the dict are read from their environment variable.
+#[derive(Clone, Copy)]
+struct Settings<'a>(Option<&'a PyDict>);
+
+impl<'a> Settings<'a> {
+    fn new(config: Option<&'a PyDict>) -> PyResult<Self> {
+        for key in config.map_or(vec![], |config| config.keys().iter().collect()) {
+            let key: String = key.extract()?;
+            if !SETTINGS.iter().any(|setting| config_key(setting) == key) {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown server config {key} - the settings are {}",
+                    SETTINGS.map(config_key).join(", ")
+                )));
+            }
+        }
+        Ok(Self(config))
+    }
+
+    fn get<T>(&self, key: &str, default: T) -> PyResult<T>
+    where
+        T: FromStr + for<'p> FromPyObject<'p>,
+        T::Err: Display,
+    {
+        match self.0.and_then(|config| config.get_item(config_key(key))) {
+            Some(value) => value.extract().map_err(|e| {
+                PyValueError::new_err(format!("Invalid server config {} - {e}", config_key(key)))
+            }),
+            None => get_env_var(key, default),
+        }
+    }
+
+    /// How a setting is named in the errors, by where it is read from
+    fn describe(&self, key: &str) -> String {
+        match self.0.and_then(|config| config.get_item(config_key(key))) {
+            Some(_) => format!("server config {}", config_key(key)),
+            None => format!("environment variable {key}"),
+        }
+    }
+}
+
+/// The headers of a setting, a dict of their names to their values in the config dict and
+/// `Name: value` lines in the environment variable
+#[derive(Clone, Debug, Default)]
+struct HeaderList(Vec<(String, String)>);
+
+impl HeaderList {
+    fn push(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let name = name.trim();
+        HeaderName::from_str(name).map_err(|e| format!("invalid header name `{name}`: {e}"))?;
+        let value = value.trim();
+        http::header::HeaderValue::from_str(value)
+            .map_err(|e| format!("invalid value of the header {name}: {e}"))?;
+        self.0.push((name.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+impl FromStr for HeaderList {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut headers = Self::default();
+        for line in value.lines().filter(|line| !line.trim().is_empty()) {
+            match line.split_once(':') {
+                Some((name, value)) => headers.push(name, value)?,
+                None => return Err(format!("expected a `Name: value` line, got `{line}`")),
+            }
+        }
+        Ok(headers)
+    }
+}
+
+impl<'p> FromPyObject<'p> for HeaderList {
+    fn extract(ob: &'p PyAny) -> PyResult<Self> {
+        let mut headers = Self::default();
+        for (name, value) in ob.downcast::<PyDict>()? {
+            headers
+                .push(name.extract()?, value.extract()?)
+                .map_err(PyValueError::new_err)?;
+        }
+        Ok(headers)
+    }
+}
+
+/// Drains the open connections once the process gets a SIGTERM: actix stops accepting, while the
+/// requests in flight are answered with `Connection: close`. The connections still open after the
+/// drain timeout are closed as the process exits.
+#[cfg(unix)]
+async fn drain_on_terminate(drain_timeout: std::time::Duration, event_loop: Py<PyAny>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            error!("Could not listen to SIGTERM, the connections won't be drained: {e}");
+            return;
+        }
+    };
+    terminate.recv().await;
+    debug!("Draining the connections for {drain_timeout:?}");
+    start_draining();
+    actix_web::rt::time::sleep(drain_timeout).await;
+    stop_event_loop(&event_loop);
+}
+
+/// Stops the event loop run by `Server.start` from another thread
+fn stop_event_loop(event_loop: &Py<PyAny>) {
+    Python::with_gil(|py| {
+        let event_loop = event_loop.as_ref(py);
+        if let Err(e) = event_loop
+            .getattr("stop")
+            .and_then(|stop| event_loop.call_method1("call_soon_threadsafe", (stop,)))
+        {
+            debug!("Could not stop the event loop: {e}");
+        }
+    })
+}
+
+/// Whether the path is the route of a directory or one of its files
+fn is_under_route(path: &str, route: &str) -> bool {
+    let route = route.trim_end_matches('/');
+    path.strip_prefix(route)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The key of a setting in the config dict, e.g. `max_payload_size` for `ROBYN_MAX_PAYLOAD_SIZE`
+fn config_key(setting: &str) -> String {
+    setting.trim_start_matches("ROBYN_").to_lowercase()
+}
+
+/// The settings of a server, read when it is created
+#[derive(Clone, Debug)]
+struct ServerConfig {
+    request: RequestConfig,
+    // in milliseconds
+    client_disconnect_timeout: u64,
+    client_request_timeout: u64,
+    // `0` leaves the idle connections to the keep alive of the OS
+    keep_alive_timeout: u64,
+    // every connection is closed after its first request when disabled
+    keep_alive: bool,
+    server_header: Option<String>,
+    max_connections: usize,
+    // the limit of the connections opened from the same IP address, in each process
+    max_connections_per_ip: usize,
+    // the connections accepted a second by each process, `0` accepts them as they come, and the
+    // ones accepted at once after an idle time
+    accept_rate: u32,
+    accept_burst: u32,
+    socket_buffer_sizes: SocketBufferSizes,
+    // the metrics are only kept when they are served
+    metrics_path: Option<String>,
+    max_requests_per_connection: usize,
+    pin_workers: bool,
+    // in milliseconds, the time given to the open connections once the process is asked to
+    // terminate, `0` stops it right away
+    drain_timeout: u64,
+    // in milliseconds, the requests answered in more time are logged, `0` logs none
+    slow_request_threshold: u64,
+    // in seconds, the `Retry-After` of the 503 answered to the connections above the limit, and
+    // to them while the connections are drained, `0` sends none
+    overload_retry_after: u64,
+    drain_retry_after: u64,
+    overload_page: OverloadPage,
+    // in seconds, the `max-age` of the files of the directories without a `Cache-Control` of
+    // their own, `0` sends none
+    static_max_age: u64,
+    // in bytes, the stack size of the threads running the handlers, `0` keeps the default one
+    worker_stack_size: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            request: RequestConfig::default(),
+            client_disconnect_timeout: DEFAULT_CLIENT_DISCONNECT_TIMEOUT,
+            client_request_timeout: DEFAULT_CLIENT_REQUEST_TIMEOUT,
+            keep_alive_timeout: DEFAULT_KEEP_ALIVE_TIMEOUT,
+            keep_alive: true,
+            server_header: Some(format!("robyn/{}", env!("CARGO_PKG_VERSION"))),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_connections_per_ip: DEFAULT_MAX_CONNECTIONS_PER_IP,
+            accept_rate: DEFAULT_ACCEPT_RATE,
+            accept_burst: DEFAULT_ACCEPT_RATE,
+            socket_buffer_sizes: SocketBufferSizes { recv: 0, send: 0 },
+            metrics_path: None,
+            max_requests_per_connection: DEFAULT_MAX_REQUESTS_PER_CONNECTION,
+            pin_workers: false,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            slow_request_threshold: DEFAULT_SLOW_REQUEST_THRESHOLD,
+            overload_retry_after: DEFAULT_OVERLOAD_RETRY_AFTER,
+            drain_retry_after: DEFAULT_DRAIN_RETRY_AFTER,
+            overload_page: OverloadPage::default(),
+            static_max_age: DEFAULT_STATIC_MAX_AGE,
+            worker_stack_size: DEFAULT_WORKER_STACK_SIZE,
+        }
+    }
+}
+
+impl ServerConfig {
+    fn new(settings: Settings) -> PyResult<Self> {
+        let default = Self::default();
+        let metrics_path = settings.get(METRICS_PATH, String::new())?;
+        let overload_page = match settings.get(OVERLOAD_PAGE, String::new())?.trim() {
+            "" => default.overload_page,
+            path => OverloadPage::read(path).map_err(|e| {
+                PyValueError::new_err(format!(
+                    "Invalid {} - {e}",
+                    settings.describe(OVERLOAD_PAGE)
+                ))
+            })?,
+        };
+        let accept_rate = settings.get(ACCEPT_RATE, default.accept_rate)?;
+        // the burst is the rate by default, a second of connections
+        let accept_burst = settings.get(ACCEPT_BURST, accept_rate)?;
+        if accept_rate != 0 && accept_burst == 0 {
+            return Err(PyValueError::new_err(format!(
+                "Invalid {} - at least one connection is accepted at once",
+                settings.describe(ACCEPT_BURST)
+            )));
+        }
+        Ok(Self {
+            request: RequestConfig::new(settings)?,
+            client_disconnect_timeout: settings
+                .get(CLIENT_DISCONNECT_TIMEOUT, default.client_disconnect_timeout)?,
+            client_request_timeout: settings
+                .get(CLIENT_REQUEST_TIMEOUT, default.client_request_timeout)?,
+            keep_alive_timeout: settings.get(KEEP_ALIVE_TIMEOUT, default.keep_alive_timeout)?,
+            keep_alive: settings.get(KEEP_ALIVE, default.keep_alive)?,
+            server_header: server_header(settings, default.server_header.unwrap_or_default())?,
+            max_connections: settings.get(MAX_CONNECTIONS, default.max_connections)?,
+            max_connections_per_ip: settings
+                .get(MAX_CONNECTIONS_PER_IP, default.max_connections_per_ip)?,
+            accept_rate,
+            accept_burst,
+            socket_buffer_sizes: SocketBufferSizes {
+                recv: settings.get(SOCKET_RECV_BUFFER_SIZE, default.socket_buffer_sizes.recv)?,
+                send: settings.get(SOCKET_SEND_BUFFER_SIZE, default.socket_buffer_sizes.send)?,
+            },
+            metrics_path: Some(metrics_path.trim().to_string()).filter(|path| !path.is_empty()),
+            max_requests_per_connection: settings.get(
+                MAX_REQUESTS_PER_CONNECTION,
+                default.max_requests_per_connection,
+            )?,
+            pin_workers: settings.get(PIN_WORKERS, default.pin_workers)?,
+            drain_timeout: settings.get(DRAIN_TIMEOUT, default.drain_timeout)?,
+            slow_request_threshold: settings
+                .get(SLOW_REQUEST_THRESHOLD, default.slow_request_threshold)?,
+            overload_retry_after: settings
+                .get(OVERLOAD_RETRY_AFTER, default.overload_retry_after)?,
+            drain_retry_after: settings.get(DRAIN_RETRY_AFTER, default.drain_retry_after)?,
+            overload_page,
+            static_max_age: settings.get(STATIC_MAX_AGE, default.static_max_age)?,
+            worker_stack_size: settings.get(WORKER_STACK_SIZE, default.worker_stack_size)?,
+        })
+    }
+}
+
+/// The configuration applied to every request before it is routed
+#[derive(Clone, Debug)]
+struct RequestConfig {
+    max_uri_length: usize,
+    // at most the number of headers parsed by actix
+    max_headers: usize,
+    // the requests with both a `Content-Length` and a `Transfer-Encoding` are answered with a
+    // 400, unless the header ignored by actix is removed and they are served
+    strip_conflicting_length: bool,
+    max_payload_size: usize,
+    trust_proxy_headers: bool,
+    // the redirects of the server, e.g. to the trailing slash of a directory, have an absolute
+    // `Location` on the host of the request rather than a path
+    absolute_redirects: bool,
+    trace_echo: bool,
+    // `0` when the responses aren't compressed on the fly
+    brotli_quality: u32,
+    // in lowercase and without their port, every host is served when empty
+    allowed_hosts: Vec<String>,
+    // the status of the responses to the hosts which aren't allowed, `421` or `400`
+    unknown_host_status: u16,
+    // the header giving the deadline of a request, which limits the time of its handler
+    deadline_header: Option<HeaderName>,
+    // the requests with an absolute URI, which are meant for a proxy, are answered with a 400
+    refuse_absolute_form: bool,
+    // the requests for `/favicon.ico` that nothing serves get the icon, or a 204 without one,
+    // rather than a 404
+    favicon_fallback: bool,
+    favicon: Option<String>,
+}
+
+impl Default for RequestConfig {
+    fn default() -> Self {
+        Self {
+            max_uri_length: DEFAULT_MAX_URI_LENGTH,
+            max_headers: PARSER_MAX_HEADERS,
+            strip_conflicting_length: false,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            trust_proxy_headers: false,
+            absolute_redirects: false,
+            trace_echo: false,
+            brotli_quality: 0,
+            allowed_hosts: vec![],
+            unknown_host_status: DEFAULT_UNKNOWN_HOST_STATUS,
+            deadline_header: None,
+            refuse_absolute_form: false,
+            favicon_fallback: true,
+            favicon: None,
+        }
+    }
+}
+
+impl RequestConfig {
+    fn new(settings: Settings) -> PyResult<Self> {
+        let default = Self::default();
+        let brotli_quality = settings.get(BROTLI_QUALITY, default.brotli_quality)?;
+        if brotli_quality > 11 {
+            return Err(PyValueError::new_err(format!(
+                "Invalid {} - the quality goes from 1 to 11, or 0 to not compress the responses",
+                settings.describe(BROTLI_QUALITY)
+            )));
+        }
+        let unknown_host_status = settings.get(UNKNOWN_HOST_STATUS, default.unknown_host_status)?;
+        if unknown_host_status != 421 && unknown_host_status != 400 {
+            return Err(PyValueError::new_err(format!(
+                "Invalid {} - the unknown hosts are answered with a 421 or a 400",
+                settings.describe(UNKNOWN_HOST_STATUS)
+            )));
+        }
+        let allowed_hosts = settings
+            .get(ALLOWED_HOSTS, String::new())?
+            .split(',')
+            .map(|host| host.trim().to_ascii_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        let deadline_header = settings.get(DEADLINE_HEADER, String::new())?;
+        let deadline_header = match deadline_header.trim() {
+            "" => None,
+            name => Some(name.parse::<HeaderName>().map_err(|e| {
+                PyValueError::new_err(format!(
+                    "Invalid {} - {e}",
+                    settings.describe(DEADLINE_HEADER)
+                ))
+            })?),
+        };
+        let max_headers = settings.get(MAX_HEADERS, default.max_headers)?;
+        if max_headers == 0 || max_headers > PARSER_MAX_HEADERS {
+            return Err(PyValueError::new_err(format!(
+                "Invalid {} - the requests can have from 1 to {PARSER_MAX_HEADERS} headers",
+                settings.describe(MAX_HEADERS)
+            )));
+        }
+        let strip_conflicting_length = match settings
+            .get(CONFLICTING_LENGTH, DEFAULT_CONFLICTING_LENGTH.to_string())?
+            .trim()
+        {
+            "reject" => false,
+            "strip" => true,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Invalid {} - the conflicting lengths are handled with `reject` or `strip`",
+                    settings.describe(CONFLICTING_LENGTH)
+                )))
+            }
+        };
+        let favicon = settings.get(FAVICON, String::new())?;
+        Ok(Self {
+            max_uri_length: settings.get(MAX_URI_LENGTH, default.max_uri_length)?,
+            max_headers,
+            strip_conflicting_length,
+            max_payload_size: settings.get(MAX_PAYLOAD_SIZE, default.max_payload_size)?,
+            trust_proxy_headers: settings.get(TRUST_PROXY_HEADERS, default.trust_proxy_headers)?,
+            absolute_redirects: settings.get(ABSOLUTE_REDIRECTS, default.absolute_redirects)?,
+            trace_echo: settings.get(TRACE_ECHO, default.trace_echo)?,
+            brotli_quality,
+            allowed_hosts,
+            unknown_host_status,
+            deadline_header,
+            refuse_absolute_form: settings
+                .get(REFUSE_ABSOLUTE_FORM, default.refuse_absolute_form)?,
+            favicon_fallback: settings.get(FAVICON_FALLBACK, default.favicon_fallback)?,
+            favicon: Some(favicon.trim().to_string()).filter(|path| !path.is_empty()),
+        })
+    }
+}
+
+/// Adds the routes to the routers, the const ones to the const router. The routers refuse the
+/// patterns already taken in their table, the routes taken in the other one are refused here:
+/// the const routes are matched first, so a route of both would never reach its handler.
+fn add_route_table(
+    router: &HttpRouter,
+    const_router: &ConstRouter,
+    event_loop: &PyAny,
+    routes: Vec<(HttpMethod, String, FunctionInfo, bool)>,
+) -> PyResult<()> {
+    let mut taken: HashSet<(HttpMethod, String)> = router
+        .routes()
+        .into_iter()
+        .chain(const_router.routes())
+        .collect();
+    if let Some((route_type, route, ..)) = routes
+        .iter()
+        .find(|(route_type, route, ..)| !taken.insert((route_type.clone(), route.clone())))
+    {
+        return Err(PyValueError::new_err(format!(
+            "Error adding route {:?} {}: it is already registered",
+            route_type, route
+        )));
+    }
+    let (const_routes, routes): (Vec<_>, Vec<_>) =
+        routes.into_iter().partition(|(.., is_const)| *is_const);
+    const_router
+        .add_routes(
+            const_routes.iter().map(|(route_type, route, function, _)| {
+                (route_type.clone(), route.as_str(), function.clone())
+            }),
+            Some(event_loop),
+        )
+        .and_then(|()| {
+            router.add_routes(
+                routes.iter().map(|(route_type, route, function, _)| {
+                    (route_type.clone(), route.as_str(), function.clone())
+                }),
+                None,
+            )
+        })
+        .map_err(|e| PyValueError::new_err(format!("{:#}", e)))
+}
+
+/// The number of requests served on a connection so far, stored in the connection data
+struct ConnectionRequests {
+    served: Cell<usize>,
+    // set by a request with both a `Content-Length` and a `Transfer-Encoding`, the requests read
+    // after it on the connection may have been smuggled in its body
+    conflicting_length: Cell<bool>,
+    opened: Instant,
+    peer_addr: Option<SocketAddr>,
+    // in milliseconds, `0` when the head of the first request can take any time to arrive
+    client_request_timeout: u64,
+}
+
+impl ConnectionRequests {
+    fn new(io: &dyn Any, client_request_timeout: u64) -> Self {
+        let peer_addr = io
+            .downcast_ref::<actix_web::rt::net::TcpStream>()
+            .and_then(|stream| stream.peer_addr().ok());
+        Self {
+            served: Cell::new(0),
+            conflicting_length: Cell::new(false),
+            opened: Instant::now(),
+            peer_addr,
+            client_request_timeout,
+        }
+    }
+}
+
+impl Drop for ConnectionRequests {
+    fn drop(&mut self) {
+        // actix answers a 408 and closes the connections whose first request head isn't received
+        // in time. It doesn't expose its reads, so a head sent too slowly can't be told from a
+        // connection opened ahead of time and left idle, e.g. by a browser, and both are logged
+        // the deadline is computed from a clock actix updates every 500ms, so the connections
+        // can be closed up to 500ms before the timeout
+        let deadline = self.client_request_timeout.saturating_sub(500);
+        if self.served.get() == 0
+            && self.client_request_timeout != 0
+            && self.opened.elapsed().as_millis() >= deadline as u128
+        {
+            debug!(
+                "Closed the connection of {} as no request head was received on it within {}ms",
+                self.peer_addr
+                    .map_or("an unknown client".to_string(), |addr| addr.to_string()),
+                self.client_request_timeout
+            );
+        }
+    }
+}
+
+// the connections open in the process, which each use a file descriptor
+static OPEN_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+// the connections open in the process by IP address, only counted with a limit per address
+static IP_CONNECTIONS: OnceLock<Mutex<HashMap<IpAddr, usize>>> = OnceLock::new();
+
+fn ip_connections() -> MutexGuard<'static, HashMap<IpAddr, usize>> {
+    IP_CONNECTIONS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Counts a connection as open until the connection data is dropped with it.
+/// The requests of the connections opened above the soft limit, or above the limit of their IP
+/// address, are answered with a 503.
+struct OpenConnection {
+    over_limit: bool,
+    // the address the connection is counted for
+    ip: Option<IpAddr>,
+}
+
+impl OpenConnection {
+    fn new(io: &dyn Any, max_connections: usize, max_connections_per_ip: usize) -> Self {
+        let open_connections = OPEN_CONNECTIONS.fetch_add(1, SeqCst) + 1;
+        let mut over_limit = max_connections != 0 && open_connections > max_connections;
+        // logged once each time the limit is crossed
+        if max_connections != 0 && open_connections == max_connections + 1 {
+            warn!(
+                "Reached the limit of {} open connections, the requests of new connections are \
+                 answered with a 503 until some are closed. Raise {} and the file descriptor \
+                 limit if needed",
+                max_connections, MAX_CONNECTIONS
+            );
+        }
+
+        let ip = io
+            .downcast_ref::<actix_web::rt::net::TcpStream>()
+            .and_then(|stream| stream.peer_addr().ok())
+            .map(|addr| addr.ip())
+            .filter(|_| max_connections_per_ip != 0);
+        if let Some(ip) = ip {
+            let mut ip_connections = ip_connections();
+            let open_connections = ip_connections.entry(ip).or_default();
+            *open_connections += 1;
+            over_limit |= *open_connections > max_connections_per_ip;
+            if *open_connections == max_connections_per_ip + 1 {
+                warn!(
+                    "{} reached the limit of {} open connections by IP address, the requests of \
+                     its new connections are answered with a 503 until some are closed",
+                    ip, max_connections_per_ip
+                );
+            }
+        }
+        Self { over_limit, ip }
+    }
+}
+
+impl Drop for OpenConnection {
+    fn drop(&mut self) {
+        OPEN_CONNECTIONS.fetch_sub(1, SeqCst);
+        if let Some(ip) = self.ip {
+            let mut ip_connections = ip_connections();
+            if let Some(open_connections) = ip_connections.get_mut(&ip) {
+                *open_connections -= 1;
+                if *open_connections == 0 {
+                    ip_connections.remove(&ip);
+                }
+            }
+        }
+    }
+}
+
+const WORKER_LAG_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Records how late a timer of the worker fires, as its timers and ready connections are only
+/// polled between the requests it runs, e.g. after a sync handler returns
+async fn monitor_worker_lag(metrics: Arc<Metrics>) {
+    loop {
+        let slept = Instant::now();
+        actix_web::rt::time::sleep(WORKER_LAG_INTERVAL).await;
+        metrics.record_worker_lag(slept.elapsed().saturating_sub(WORKER_LAG_INTERVAL));
+    }
+}
+
+/// Pins each worker thread to a core of the machine, round-robin across the cores
+struct WorkerPinning {
+    cores: Vec<CoreId>,
+    next: AtomicUsize,
+}
+
+thread_local! {
+    static PINNED: Cell<bool> = const { Cell::new(false) };
+}
+
+impl WorkerPinning {
+    /// `None` when the cores can't be listed on the platform
+    fn new() -> Option<Self> {
+        match core_affinity::get_core_ids() {
+            Some(cores) if !cores.is_empty() => Some(Self {
+                cores,
+                next: AtomicUsize::new(0),
+            }),
+            _ => {
+                warn!("The worker threads can't be pinned to cores on this platform");
+                None
+            }
+        }
+    }
+
+    /// Pins the current thread to the next core, once. A failure leaves the thread unpinned.
+    fn pin_current_thread(&self) {
+        if PINNED.with(|pinned| pinned.replace(true)) {
+            return;
+        }
+        let core = self.cores[self.next.fetch_add(1, SeqCst) % self.cores.len()];
+        if core_affinity::set_for_current(core) {
+            debug!("Pinned a worker thread to the core {}", core.id);
+        } else {
+            warn!("Failed to pin a worker thread to the core {}", core.id);
+        }
+    }
+}
+
+/// The body of the 503 answered to the connections above the limits. It is read once as the
+/// server is created, so turning a connection away doesn't read the disk.
+#[derive(Clone, Debug)]
+struct OverloadPage {
+    body: web::Bytes,
+    content_type: Option<HeaderValue>,
+}
+
+impl Default for OverloadPage {
+    fn default() -> Self {
+        Self {
+            body: web::Bytes::from_static(b"Service unavailable"),
+            content_type: None,
+        }
+    }
+}
+
+impl OverloadPage {
+    fn read(path: &str) -> std::io::Result<Self> {
+        Ok(Self {
+            body: std::fs::read(path)?.into(),
+            content_type: HeaderValue::from_str(&content_type_for(path)).ok(),
+        })
+    }
+}
+
+/// The sizes of the kernel buffers of the accepted sockets, `0` keeps the size of the OS
+#[derive(Clone, Copy, Debug)]
+struct SocketBufferSizes {
+    recv: usize,
+    send: usize,
+}
+
+impl SocketBufferSizes {
+    /// A failure to size a buffer doesn't keep the connection from being served
+    fn apply(&self, io: &dyn Any) {
+        if self.recv == 0 && self.send == 0 {
+            return;
+        }
+        let stream = match io.downcast_ref::<actix_web::rt::net::TcpStream>() {
+            Some(stream) => stream,
+            None => return,
+        };
+        // the stream outlives the borrowed handle, which doesn't close the socket when dropped
+        #[cfg(not(target_os = "windows"))]
+        let handle = unsafe {
+            use std::os::fd::{AsRawFd, BorrowedFd};
+            BorrowedFd::borrow_raw(stream.as_raw_fd())
+        };
+        #[cfg(target_os = "windows")]
+        let handle = unsafe {
+            use std::os::windows::io::{AsRawSocket, BorrowedSocket};
+            BorrowedSocket::borrow_raw(stream.as_raw_socket())
+        };
+        let socket = SockRef::from(&handle);
+        if self.recv != 0 {
+            if let Err(e) = socket.set_recv_buffer_size(self.recv) {
+                warn!("Failed to set the receive buffer size of a socket: {}", e);
+            }
+        }
+        if self.send != 0 {
+            if let Err(e) = socket.set_send_buffer_size(self.send) {
+                warn!("Failed to set the send buffer size of a socket: {}", e);
+            }
+        }
+    }
 }
 
 #[pyclass]
+#[derive(Clone)]
 pub struct Server {
-    router: Arc<HttpRouter>,
-    const_router: Arc<ConstRouter>,
+    http_routes: HttpRoutes,
     websocket_router: Arc<WebSocketRouter>,
     middleware_router: Arc<MiddlewareRouter>,
     global_request_headers: Arc<DashMap<String, String>>,
     global_response_headers: Arc<DashMap<String, String>>,
+    default_response_headers: Arc<DashMap<String, String>>,
+    error_pages: Arc<DashMap<u16, String>>,
     directories: Arc<RwLock<Vec<Directory>>>,
     startup_handler: Option<Arc<FunctionInfo>>,
     shutdown_handler: Option<Arc<FunctionInfo>>,
+    tunnel_handler: Option<Arc<FunctionInfo>>,
+    config: ServerConfig,
 }
 
 #[pymethods]
 impl Server {
+    /// The settings missing from the config dict are read from the environment variables
     #[new]
-    pub fn new() -> Self {
-        Self {
-            router: Arc::new(HttpRouter::new()),
-            const_router: Arc::new(ConstRouter::new()),
+    #[pyo3(signature = (config = None))]
+    pub fn new(config: Option<&PyDict>) -> PyResult<Self> {
+        let settings = Settings::new(config)?;
+        // the runtime of the async handlers is built as the first const route is added, so its
+        // builder is set this early. It is only built once, the first server sets its size.
+        let worker_stack_size = settings.get(WORKER_STACK_SIZE, DEFAULT_WORKER_STACK_SIZE)?;
+        if worker_stack_size > 0 {
+            let mut runtime = tokio::runtime::Builder::new_multi_thread();
+            runtime.enable_all().thread_stack_size(worker_stack_size);
+            pyo3_asyncio::tokio::init(runtime);
+        }
+        set_sync_stack_size(worker_stack_size);
+        // the file responses built from then on are streamed above this size
+        set_response_buffer_size(settings.get(RESPONSE_BUFFER_SIZE, DEFAULT_RESPONSE_BUFFER_SIZE)?);
+        set_mmap_files(settings.get(MMAP_FILES, false)?);
+        // redacted in the logs, in addition to `Authorization`, `Cookie` and `Set-Cookie`
+        let redacted_headers = settings
+            .get(REDACTED_HEADERS, String::new())?
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                name.parse::<HeaderName>()
+                    .map(|name| name.to_string())
+                    .map_err(|e| {
+                        PyValueError::new_err(format!(
+                            "Invalid {} - invalid header name `{name}`: {e}",
+                            settings.describe(REDACTED_HEADERS)
+                        ))
+                    })
+            })
+            .collect::<PyResult<_>>()?;
+        logging::set_redacted_headers(redacted_headers);
+        configure_file_breaker(
+            settings.get(FILE_BREAKER_THRESHOLD, 0)?,
+            std::time::Duration::from_millis(
+                settings.get(FILE_BREAKER_COOLDOWN, DEFAULT_FILE_BREAKER_COOLDOWN)?,
+            ),
+        );
+
+        // the handlers and the headers added later take precedence over them
+        let default_response_headers = DashMap::new();
+        for (name, value) in settings.get(DEFAULT_HEADERS, HeaderList::default())?.0 {
+            default_response_headers.insert(name, value);
+        }
+
+        Ok(Self {
+            http_routes: HttpRoutes::new(),
             websocket_router: Arc::new(WebSocketRouter::new()),
             middleware_router: Arc::new(MiddlewareRouter::new()),
             global_request_headers: Arc::new(DashMap::new()),
             global_response_headers: Arc::new(DashMap::new()),
+            default_response_headers: Arc::new(default_response_headers),
+            error_pages: Arc::new(DashMap::new()),
             directories: Arc::new(RwLock::new(Vec::new())),
             startup_handler: None,
             shutdown_handler: None,
-        }
+            tunnel_handler: None,
+            config: ServerConfig::new(settings)?,
+        })
     }
 
     pub fn start(
         &mut self,
         py: Python,
         socket: &PyCell<SocketHeld>,
-        workers: usize,
+        workers: Option<usize>,
     ) -> PyResult<()> {
-        pyo3_log::init();
+        logging::init(py)?;
 
         if STARTED
             .compare_exchange(false, true, SeqCst, Relaxed)
@@ -93,77 +959,153 @@ impl Server {
 
         let raw_socket = socket.try_borrow_mut()?.get_socket();
 
-        let router = self.router.clone();
-        let const_router = self.const_router.clone();
+        let http_routes = self.http_routes.clone();
         let middleware_router = self.middleware_router.clone();
         let web_socket_router = self.websocket_router.clone();
         let global_request_headers = self.global_request_headers.clone();
         let global_response_headers = self.global_response_headers.clone();
+        let default_response_headers = self.default_response_headers.clone();
+        let error_pages = self.error_pages.clone();
         let directories = self.directories.clone();
+        let workers = resolve_worker_count(
+            workers,
+            |key| env::var(key).ok(),
+            thread::available_parallelism,
+        );
+        set_workers(workers);
         let workers = Arc::new(workers);
 
         let asyncio = py.import("asyncio")?;
         let event_loop = asyncio.call_method0("new_event_loop")?;
         asyncio.call_method1("set_event_loop", (event_loop,))?;
 
-        let startup_handler = self.startup_handler.clone();
+        // an exception raised by the startup handler aborts the start, before any request is
+        // accepted
+        if let Some(startup_handler) = &self.startup_handler {
+            if let Err(e) = execute_startup_handler(startup_handler, event_loop) {
+                error!(
+                    "Error while executing the startup handler, the server isn't started: {}",
+                    get_traceback(&e)
+                );
+                return Err(e);
+            }
+        }
         let shutdown_handler = self.shutdown_handler.clone();
+        let tunnel_handler = self.tunnel_handler.clone();
 
         let task_locals = pyo3_asyncio::TaskLocals::new(event_loop).copy_context(py)?;
         let task_locals_copy = task_locals.clone();
 
-        let max_payload_size = env::var(MAX_PAYLOAD_SIZE)
-            .unwrap_or(DEFAULT_MAX_PAYLOAD_SIZE.to_string())
-            .trim()
-            .parse::<usize>()
-            .map_err(|e| {
-                PyValueError::new_err(format!(
-                    "Failed to parse environment variable {MAX_PAYLOAD_SIZE} - {e}"
-                ))
-            })?;
-
-        thread::spawn(move || {
+        let ServerConfig {
+            request: request_config,
+            client_disconnect_timeout,
+            client_request_timeout,
+            keep_alive_timeout,
+            keep_alive,
+            server_header,
+            max_connections,
+            max_connections_per_ip,
+            accept_rate,
+            accept_burst,
+            socket_buffer_sizes,
+            metrics_path,
+            max_requests_per_connection,
+            pin_workers,
+            drain_timeout,
+            slow_request_threshold,
+            overload_retry_after,
+            drain_retry_after,
+            overload_page,
+            static_max_age,
+            worker_stack_size,
+        } = self.config.clone();
+        // actix answers every request with a `Connection: close` when it is disabled, whatever
+        // the client asked for
+        let keep_alive = match keep_alive_timeout {
+            _ if !keep_alive => KeepAlive::Disabled,
+            0 => KeepAlive::Os,
+            timeout => KeepAlive::Timeout(std::time::Duration::from_millis(timeout)),
+        };
+        let metrics = metrics_path.map(|path| (path, Arc::new(Metrics::new())));
+        let worker_pinning = pin_workers.then(WorkerPinning::new).flatten().map(Arc::new);
+        let accept_rate =
+            (accept_rate != 0).then(|| Arc::new(AcceptRate::new(accept_rate, accept_burst)));
+        let connected_accept_rate = accept_rate.clone();
+        let metrics_accept_rate = accept_rate.clone();
+        // the loop is stopped once the connections are drained, which ends the start
+        let drained_loop: Py<PyAny> = event_loop.into();
+        let mut server_thread = thread::Builder::new();
+        if worker_stack_size > 0 {
+            server_thread = server_thread.stack_size(worker_stack_size);
+        }
+        server_thread.spawn(move || {
             actix_web::rt::System::new().block_on(async move {
                 debug!("The number of workers is {}", workers.clone());
-                execute_event_handler(startup_handler, &task_locals_copy)
-                    .await
-                    .unwrap();
 
-                HttpServer::new(move || {
+                let mut server = HttpServer::new(move || {
+                    // the app is built on each worker thread as it starts
+                    if let Some(worker_pinning) = &worker_pinning {
+                        worker_pinning.pin_current_thread();
+                    }
                     let mut app = App::new();
 
                     let task_locals = task_locals_copy.clone();
+                    // the maps of headers have the same type, so they are given to the requests
+                    // directly rather than as app data, which is looked up by type
+                    let global_request_headers = global_request_headers.clone();
+                    let global_response_headers = global_response_headers.clone();
+                    let tunnel_handler = tunnel_handler.clone();
+                    let overload_page = overload_page.clone();
+                    let accept_rate = metrics_accept_rate.clone();
                     let directories = directories.read().unwrap();
 
-                    // this loop matches three types of directory serving
-                    // 1. Serves a build folder. e.g. the build folder generated from yarn build
-                    // 2. Shows file listing
-                    // 3. Just serves the file without any redirection to sub links
+                    let request_metrics = metrics.as_ref().map(|(_, metrics)| metrics.clone());
+                    if let Some(metrics) = request_metrics.clone() {
+                        actix_web::rt::spawn(monitor_worker_lag(metrics));
+                    }
+                    if let Some((path, metrics)) = metrics.clone() {
+                        app = app.route(
+                            &path.clone(),
+                            web::get().to(move |req: HttpRequest| {
+                                req.extensions_mut().insert(MatchedRoute(path.clone()));
+                                let body = metrics.render(
+                                    OPEN_CONNECTIONS.load(SeqCst),
+                                    accept_rate
+                                        .as_ref()
+                                        .map_or(0, |accept_rate| accept_rate.throttled_accepts()),
+                                    busy_workers(),
+                                    file_store_stats(),
+                                );
+                                async move {
+                                    HttpResponse::Ok()
+                                        .content_type("text/plain; version=0.0.4")
+                                        .body(body)
+                                }
+                            }),
+                        );
+                    }
+
                     for directory in directories.iter() {
-                        if let Some(index_file) = &directory.index_file {
-                            app = app.service(
-                                Files::new(&directory.route, &directory.directory_path)
-                                    .index_file(index_file)
-                                    .redirect_to_slash_directory(),
-                            );
-                        } else if directory.show_files_listing {
-                            app = app.service(
-                                Files::new(&directory.route, &directory.directory_path)
-                                    .redirect_to_slash_directory()
-                                    .show_files_listing(),
-                            );
-                        } else {
-                            app = app
-                                .service(Files::new(&directory.route, &directory.directory_path));
-                        }
+                        app = app.service(directory.files(
+                            request_config.absolute_redirects,
+                            request_config.trust_proxy_headers,
+                        ));
                     }
+                    let directory_routes: Vec<(String, Option<HeaderValue>)> = directories
+                        .iter()
+                        .map(|directory| {
+                            (
+                                directory.route.clone(),
+                                directory.cache_control(static_max_age),
+                            )
+                        })
+                        .collect();
 
                     app = app
-                        .app_data(web::Data::new(router.clone()))
-                        .app_data(web::Data::new(const_router.clone()))
+                        .app_data(web::Data::new(http_routes.clone()))
                         .app_data(web::Data::new(middleware_router.clone()))
-                        .app_data(web::Data::new(global_request_headers.clone()))
-                        .app_data(web::Data::new(global_response_headers.clone()));
+                        .app_data(web::Data::new(error_pages.clone()))
+                        .app_data(web::Data::new(request_config.clone()));
 
                     let web_socket_map = web_socket_router.get_web_socket_map();
                     for (elem, value) in (web_socket_map.read().unwrap()).iter() {
@@ -183,72 +1125,409 @@ impl Server {
                         );
                     }
 
-                    debug!("Max payload size is {}", max_payload_size);
+                    debug!("Max payload size is {}", request_config.max_payload_size);
+
+                    // default headers are only added when the response doesn't already have them
+                    // so the handlers are always able to override them
+                    let mut default_headers = DefaultHeaders::new();
+                    if let Some(server_header) = &server_header {
+                        default_headers =
+                            default_headers.add((http::header::SERVER, server_header.clone()));
+                    }
+                    for header in default_response_headers.iter() {
+                        default_headers =
+                            default_headers.add((header.key().clone(), header.value().clone()));
+                    }
 
-                    app.app_data(web::PayloadConfig::new(max_payload_size))
+                    let static_error_pages = error_pages.clone();
+                    app
+                        // the static files are read through the breaker of the file store, actix
+                        // answers a failed read with a 500. Their error responses get the error
+                        // pages like the ones of the routes, the others the `Cache-Control` of
+                        // their directory and the content types registered for their extension.
+                        .wrap_fn(move |req, srv| {
+                            let error_pages = static_error_pages.clone();
+                            let directory = directory_routes
+                                .iter()
+                                .find(|(route, _)| is_under_route(req.path(), route));
+                            let static_file = directory.is_some();
+                            let cache_control =
+                                directory.and_then(|(_, cache_control)| cache_control.clone());
+                            let admission = static_file.then(admit_file_read);
+                            let probe = matches!(admission, Some(Ok(true)));
+                            let response = match admission {
+                                Some(Err(retry_after)) => Err((req, retry_after)),
+                                _ => Ok(srv.call(req)),
+                            };
+                            async move {
+                                match response {
+                                    Ok(response) => {
+                                        let mut response = response.await?;
+                                        if static_file {
+                                            let read = match response.status() {
+                                                StatusCode::INTERNAL_SERVER_ERROR => {
+                                                    Err(std::io::Error::other(
+                                                        "A static file couldn't be read",
+                                                    ))
+                                                }
+                                                StatusCode::NOT_FOUND => {
+                                                    Err(std::io::ErrorKind::NotFound.into())
+                                                }
+                                                _ => Ok(()),
+                                            };
+                                            record_file_read(&read, probe);
+                                        }
+                                        if let Some(cache_control) = cache_control
+                                            .filter(|_| response.status().as_u16() < 400)
+                                        {
+                                            let headers = response.headers_mut();
+                                            if !headers.contains_key(http::header::CACHE_CONTROL) {
+                                                headers.insert(
+                                                    http::header::CACHE_CONTROL,
+                                                    cache_control,
+                                                );
+                                            }
+                                        }
+                                        let mime_type =
+                                            registered_mime_type(response.request().path())
+                                                .filter(|_| {
+                                                    static_file && response.status().is_success()
+                                                })
+                                                .and_then(|mime_type| {
+                                                    HeaderValue::from_str(&mime_type).ok()
+                                                });
+                                        if let Some(mime_type) = mime_type {
+                                            response
+                                                .headers_mut()
+                                                .insert(http::header::CONTENT_TYPE, mime_type);
+                                        }
+                                        let error_page = match response.status() {
+                                            status if static_file && status.as_u16() >= 400 => {
+                                                Response::error_page(status.as_u16(), &error_pages)
+                                            }
+                                            _ => None,
+                                        };
+                                        Ok(match error_page {
+                                            Some(page) => {
+                                                let page = page.respond_to(response.request());
+                                                response.into_response(page).map_into_right_body()
+                                            }
+                                            None => response.map_into_left_body(),
+                                        })
+                                    }
+                                    Err((req, retry_after)) => {
+                                        let response =
+                                            Response::file_store_unavailable(retry_after)
+                                                .with_error_page(&error_pages)
+                                                .respond_to(req.request());
+                                        Ok(req.into_response(response).map_into_right_body())
+                                    }
+                                }
+                            }
+                        })
+                        .wrap(default_headers)
+                        // the last allowed request on a connection is answered with
+                        // `Connection: close` and the connection is closed after it, as are the
+                        // requests answered while the connections are drained
+                        .wrap_fn(move |req, srv| {
+                            let last_request =
+                                req.conn_data::<ConnectionRequests>()
+                                    .is_some_and(|requests| {
+                                        requests.served.set(requests.served.get() + 1);
+                                        max_requests_per_connection != 0
+                                            && requests.served.get() >= max_requests_per_connection
+                                    });
+                            let response = srv.call(req);
+                            async move {
+                                let mut response = response.await?;
+                                if last_request || is_draining() {
+                                    response
+                                        .response_mut()
+                                        .head_mut()
+                                        .set_connection_type(http::ConnectionType::Close);
+                                }
+                                Ok(response)
+                            }
+                        })
+                        // connections above the soft limit are turned away right away, so they
+                        // can be closed before the process runs out of file descriptors, and so
+                        // are the ones above the limit of their IP address. The clients are told
+                        // to retry later, after a drain for a new process.
+                        .wrap_fn(move |req, srv| {
+                            let over_limit = req
+                                .conn_data::<OpenConnection>()
+                                .is_some_and(|connection| connection.over_limit);
+                            let response = if over_limit {
+                                Err((req, overload_page.clone()))
+                            } else {
+                                Ok(srv.call(req))
+                            };
+                            async move {
+                                match response {
+                                    Ok(response) => Ok(response.await?.map_into_left_body()),
+                                    Err((req, overload_page)) => {
+                                        let retry_after = if is_draining() {
+                                            drain_retry_after
+                                        } else {
+                                            overload_retry_after
+                                        };
+                                        let mut response = HttpResponse::ServiceUnavailable();
+                                        response.force_close();
+                                        if let Some(content_type) = &overload_page.content_type {
+                                            response.insert_header((
+                                                http::header::CONTENT_TYPE,
+                                                content_type.clone(),
+                                            ));
+                                        }
+                                        if retry_after != 0 {
+                                            response.insert_header((
+                                                http::header::RETRY_AFTER,
+                                                retry_after,
+                                            ));
+                                        }
+                                        Ok(req
+                                            .into_response(
+                                                response.body(overload_page.body.clone()),
+                                            )
+                                            .map_into_right_body())
+                                    }
+                                }
+                            }
+                        })
+                        // each request runs in a span, which gets its route, status and time to
+                        // the first byte once it is answered, and its duration once its body is
+                        // sent. The slow requests are logged with them.
+                        .wrap_fn(move |req, srv| {
+                            let span = request_span(req.request());
+                            let start = Instant::now();
+                            let response = span.in_scope(|| srv.call(req));
+                            let answered = span.clone();
+                            async move {
+                                let response = response.await?;
+                                if let Some(route) =
+                                    response.request().extensions().get::<MatchedRoute>()
+                                {
+                                    answered.record("route", &route.0.as_str());
+                                }
+                                let status = response.status().as_u16();
+                                answered.record("status", &status);
+                                let time_to_first_byte = milliseconds(start.elapsed());
+                                answered.record("ttfb_ms", &time_to_first_byte);
+                                let request = response.request();
+                                let (method, path) =
+                                    (request.method().to_string(), request.path().to_string());
+                                let route = request
+                                    .extensions()
+                                    .get::<MatchedRoute>()
+                                    .map_or(NOT_FOUND_ROUTE.to_string(), |route| route.0.clone());
+                                // the duration includes the streamed body, until it is sent
+                                Ok(response.map_body(move |_, body| {
+                                    TimedBody::new(body, move || {
+                                        let duration = start.elapsed();
+                                        let duration_ms = milliseconds(duration);
+                                        answered.record("duration_ms", &duration_ms);
+                                        if slow_request_threshold != 0
+                                            && duration.as_millis()
+                                                >= u128::from(slow_request_threshold)
+                                        {
+                                            warn!(
+                                                "Slow request: {} {} (route {}) answered with {} \
+                                                 in {:.1}ms, its first byte in {:.1}ms",
+                                                method,
+                                                path,
+                                                route,
+                                                status,
+                                                duration_ms,
+                                                time_to_first_byte
+                                            );
+                                        }
+                                    })
+                                }))
+                            }
+                            .instrument(span)
+                        })
+                        // the outermost wrapper, so every request is measured
+                        .wrap_fn(move |req, srv| {
+                            let metrics = request_metrics.clone();
+                            let start = Instant::now();
+                            let response = srv.call(req);
+                            async move {
+                                let response = response.await?;
+                                let metrics = match metrics {
+                                    Some(metrics) => metrics,
+                                    None => return Ok(response.map_into_right_body()),
+                                };
+                                let time_to_first_byte = start.elapsed();
+                                let status = response.status().as_u16();
+                                let route = response
+                                    .request()
+                                    .extensions()
+                                    .get::<MatchedRoute>()
+                                    .map_or(NOT_FOUND_ROUTE.to_string(), |route| route.0.clone());
+                                Ok(response
+                                    .map_body(move |_, body| {
+                                        TimedBody::new(body, move || {
+                                            metrics.record(
+                                                &route,
+                                                status,
+                                                time_to_first_byte,
+                                                start.elapsed(),
+                                            )
+                                        })
+                                    })
+                                    .map_into_left_body())
+                            }
+                        })
                         .default_service(web::route().to(
-                            move |router: web::Data<Arc<HttpRouter>>,
-                                  const_router: web::Data<Arc<ConstRouter>>,
+                            move |http_routes: web::Data<HttpRoutes>,
                                   middleware_router: web::Data<Arc<MiddlewareRouter>>,
-                                  global_request_headers,
-                                  global_response_headers,
-                                  body,
-                                  req| {
+                                  error_pages: web::Data<Arc<DashMap<u16, String>>>,
+                                  request_config: web::Data<RequestConfig>,
+                                  payload: web::Payload,
+                                  req: HttpRequest| {
+                                let global_request_headers = global_request_headers.clone();
+                                let global_response_headers = global_response_headers.clone();
+                                let tunnel_handler = tunnel_handler.clone();
+                                let task_locals = task_locals.clone();
                                 pyo3_asyncio::tokio::scope_local(task_locals.clone(), async move {
-                                    index(
-                                        router,
-                                        const_router,
-                                        middleware_router,
-                                        global_request_headers,
-                                        global_response_headers,
-                                        body,
-                                        req,
+                                    // a `CONNECT host:port` request asks for a tunnel, rather
+                                    // than a resource of the server
+                                    if req.method() == http::Method::CONNECT
+                                        && req.uri().scheme().is_none()
+                                        && req.uri().authority().is_some()
+                                    {
+                                        return Either::Left(open_tunnel(
+                                            tunnel_handler,
+                                            &global_request_headers,
+                                            &error_pages,
+                                            &request_config,
+                                            task_locals,
+                                            payload,
+                                            req,
+                                        ));
+                                    }
+                                    let panic_error_pages = error_pages.clone();
+                                    Either::Right(
+                                        answer_panics(
+                                            index(
+                                                http_routes,
+                                                middleware_router,
+                                                global_request_headers,
+                                                global_response_headers,
+                                                error_pages,
+                                                request_config,
+                                                payload,
+                                                req,
+                                            ),
+                                            &panic_error_pages,
+                                        )
+                                        .await,
                                     )
-                                    .await
                                 })
                             },
                         ))
                 })
-                .keep_alive(KeepAlive::Os)
+                // an idle kept alive connection is closed once no request came in for this
+                // long, independently of the time given to a client to send a request
+                .keep_alive(keep_alive)
+                .on_connect(move |io: &dyn Any, data: &mut Extensions| {
+                    if let Some(accept_rate) = &connected_accept_rate {
+                        accept_rate.accepted();
+                    }
+                    socket_buffer_sizes.apply(io);
+                    data.insert(ConnectionRequests::new(io, client_request_timeout));
+                    data.insert(OpenConnection::new(
+                        io,
+                        max_connections,
+                        max_connections_per_ip,
+                    ));
+                })
                 .workers(*workers.clone())
-                .client_request_timeout(std::time::Duration::from_secs(0))
-                .listen(raw_socket.try_into().unwrap())
-                .unwrap()
-                .run()
-                .await
-                .unwrap();
+                // the head of the first request on a connection has to be received in this
+                // time, actix doesn't limit the time taken by the heads of the next ones
+                .client_request_timeout(std::time::Duration::from_millis(client_request_timeout))
+                // once a response is written on a connection that isn't kept alive, the write half
+                // is shut down first so the client can finish reading before the socket is closed.
+                // This is the time given to the client to do so.
+                .client_disconnect_timeout(std::time::Duration::from_millis(
+                    client_disconnect_timeout,
+                ));
+                if drain_timeout > 0 {
+                    // on a SIGTERM, actix stops accepting and waits for the open connections to
+                    // be closed for this long, in whole seconds
+                    server = server.shutdown_timeout(drain_timeout.div_ceil(1000));
+                    #[cfg(unix)]
+                    actix_web::rt::spawn(drain_on_terminate(
+                        std::time::Duration::from_millis(drain_timeout),
+                        Python::with_gil(|py| drained_loop.clone_ref(py)),
+                    ));
+                }
+                let server = server.listen(raw_socket.into()).unwrap().run();
+                if let Some(accept_rate) = &accept_rate {
+                    accept_rate.set_server(server.handle());
+                }
+                server.await.unwrap();
+                if is_draining() {
+                    stop_event_loop(&drained_loop);
+                }
             });
-        });
+        })?;
 
         let event_loop = (*event_loop).call_method0("run_forever");
-        if event_loop.is_err() {
+        // the connections have been drained, the process exits once the shutdown handler ran
+        let drained = event_loop.is_ok() && is_draining();
+        if event_loop.is_err() || drained {
             debug!("Ctrl c handler");
             Python::with_gil(|py| {
                 pyo3_asyncio::tokio::run(py, async move {
-                    execute_event_handler(shutdown_handler, &task_locals.clone())
-                        .await
-                        .unwrap();
+                    // the server is shut down anyway
+                    if let Err(e) = execute_event_handler(shutdown_handler, &task_locals).await {
+                        error!(
+                            "Error while executing the shutdown handler: {}",
+                            match e.downcast_ref::<PyErr>() {
+                                Some(e) => get_traceback(e),
+                                None => e.to_string(),
+                            }
+                        );
+                    }
                     Ok(())
                 })
             })?;
+            if drained {
+                std::process::exit(0);
+            }
             abort();
         }
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn add_directory(
         &mut self,
         route: String,
         directory_path: String,
         show_files_listing: bool,
-        index_file: Option<String>,
-    ) {
+        show_hidden_files: bool,
+        index_files: Vec<String>,
+        missing_index_status: u16,
+        cache_control: Option<String>,
+    ) -> PyResult<()> {
+        let missing_index_status = StatusCode::from_u16(missing_index_status)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let cache_control = cache_control
+            .map(|cache_control| HeaderValue::from_str(&cache_control))
+            .transpose()
+            .map_err(|e| PyValueError::new_err(format!("Invalid Cache-Control - {e}")))?;
         self.directories.write().unwrap().push(Directory {
             route,
             directory_path,
             show_files_listing,
-            index_file,
+            show_hidden_files,
+            index_files,
+            missing_index_status,
+            cache_control,
         });
+        Ok(())
     }
 
     /// Adds a new request header to our concurrent hashmap
@@ -265,6 +1544,14 @@ impl Server {
             .insert(key.to_string(), value.to_string());
     }
 
+    /// Adds a new default response header to our concurrent hashmap
+    /// unlike the global response headers, these are only added when the response
+    /// doesn't contain the header already. They are applied when the server starts.
+    pub fn add_default_response_header(&self, key: &str, value: &str) {
+        self.default_response_headers
+            .insert(key.to_string(), value.to_string());
+    }
+
     /// Removes a new request header to our concurrent hashmap
     /// this can be called after the server has started.
     pub fn remove_header(&self, key: &str) {
@@ -277,6 +1564,31 @@ impl Server {
         self.global_response_headers.remove(key);
     }
 
+    /// Adds a custom page that is served when the server produces an error with the status code.
+    /// The page is read once, if it can't be read the default error response is used instead.
+    pub fn add_error_page(&self, status_code: u16, file_path: &str) {
+        match read_file(file_path) {
+            Ok(page) => {
+                self.error_pages.insert(status_code, page);
+            }
+            Err(e) => {
+                error!(
+                    "Could not read the error page `{}` for status code {}: {}",
+                    file_path, status_code, e
+                );
+            }
+        }
+    }
+
+    /// Add the content type served for the files with the given extension
+    pub fn add_mime_type(&self, extension: &str, mime_type: &str) {
+        add_mime_type(extension, mime_type);
+        debug!(
+            "Files with the extension {} are served as {}",
+            extension, mime_type
+        );
+    }
+
     /// Add a new route to the routing tables
     /// can be called after the server has been started
     pub fn add_route(
@@ -291,18 +1603,16 @@ impl Server {
         let asyncio = py.import("asyncio").unwrap();
         let event_loop = asyncio.call_method0("get_event_loop").unwrap();
 
+        let (router, const_router) = self.http_routes.current();
         if is_const {
-            match self
-                .const_router
-                .add_route(route_type, route, function, Some(event_loop))
-            {
+            match const_router.add_route(route_type, route, function, Some(event_loop)) {
                 Ok(_) => (),
                 Err(e) => {
                     debug!("Error adding const route {}", e);
                 }
             }
         } else {
-            match self.router.add_route(route_type, route, function, None) {
+            match router.add_route(route_type, route, function, None) {
                 Ok(_) => (),
                 Err(e) => {
                     debug!("Error adding route {}", e);
@@ -311,6 +1621,78 @@ impl Server {
         }
     }
 
+    /// Add a handler tried, after the previous ones, with the requests matching no route
+    pub fn add_fallback(&self, function: FunctionInfo) {
+        debug!("Fallback added {:?}", function);
+        let (router, _) = self.http_routes.current();
+        router.add_fallback(function);
+    }
+
+    /// Adds the routes, given as tuples of their method, path, function and whether they are
+    /// const, e.g. the routes of an app as it starts. A route whose method and path are already
+    /// taken is an error, rather than one of the two functions never being called. It is found
+    /// before any of the routes is added.
+    pub fn add_routes(
+        &self,
+        py: Python,
+        routes: Vec<(HttpMethod, String, FunctionInfo, bool)>,
+    ) -> PyResult<()> {
+        let event_loop = py.import("asyncio")?.call_method0("get_event_loop")?;
+        let (router, const_router) = self.http_routes.current();
+        add_route_table(&router, &const_router, event_loop, routes)
+    }
+
+    /// Replaces all the routes, given as tuples of their method, path, function and whether
+    /// they are const. Can be called after the server has been started: the requests being
+    /// handled keep the functions of the routes they matched, the next ones use the new routes.
+    /// The routes aren't replaced if one of them can't be added.
+    pub fn reload_routes(
+        &self,
+        py: Python,
+        routes: Vec<(HttpMethod, String, FunctionInfo, bool)>,
+    ) -> PyResult<()> {
+        let router = HttpRouter::new();
+        let const_router = ConstRouter::new();
+        let event_loop = py.import("asyncio")?.call_method0("get_event_loop")?;
+        add_route_table(&router, &const_router, event_loop, routes)?;
+
+        // the fallbacks aren't routes, they are kept
+        let (previous_router, _) = self.http_routes.current();
+        for fallback in previous_router.get_fallbacks() {
+            router.add_fallback(fallback);
+        }
+        self.http_routes.replace(router, const_router);
+        debug!("Routes reloaded");
+        Ok(())
+    }
+
+    /// The registered routes, with the types of the middlewares of their path. The websocket
+    /// routes and the directories aren't listed.
+    pub fn routes(&self) -> Vec<RouteInfo> {
+        let (router, const_router) = self.http_routes.current();
+        let routes = router.routes().into_iter().map(|route| (route, false));
+        let const_routes = const_router.routes().into_iter().map(|route| (route, true));
+        routes
+            .chain(const_routes)
+            .map(|((method, path), is_const)| RouteInfo {
+                // the middlewares are matched against the pattern of the route, a middleware
+                // of `/users/:name` is run with the route `/users/:id`
+                middlewares: [MiddlewareType::BeforeRequest, MiddlewareType::AfterRequest]
+                    .iter()
+                    .filter(|middleware_type| {
+                        self.middleware_router
+                            .get_route(middleware_type, &path)
+                            .is_some()
+                    })
+                    .cloned()
+                    .collect(),
+                method,
+                path,
+                is_const,
+            })
+            .collect()
+    }
+
     /// Add a new global middleware
     /// can be called after the server has been started
     pub fn add_global_middleware(&self, middleware_type: &MiddlewareType, function: FunctionInfo) {
@@ -360,26 +1742,456 @@ impl Server {
         self.shutdown_handler = Some(Arc::new(function));
         debug!("Added shutdown handler {:?}", self.shutdown_handler);
     }
+
+    /// The handler given the tunnel of the `CONNECT` requests, which are refused without one
+    pub fn set_tunnel_handler(&mut self, function: FunctionInfo) {
+        self.tunnel_handler = Some(Arc::new(function));
+        debug!("Added tunnel handler {:?}", self.tunnel_handler);
+    }
+
+    /// The function given each message logged by the server, which returns the message to log
+    pub fn set_log_redactor(&self, redactor: Py<PyAny>) {
+        logging::set_redactor(redactor);
+    }
 }
 
-impl Default for Server {
-    fn default() -> Self {
-        Self::new()
+impl Server {
+    /// Adds a const route answered with the response, which isn't computed by a python handler,
+    /// so the requests to it can be answered without the interpreter, e.g. in the benchmarks
+    pub fn add_const_response(
+        &self,
+        route_type: &HttpMethod,
+        route: &str,
+        response: Response,
+    ) -> anyhow::Result<()> {
+        let (_, const_router) = self.http_routes.current();
+        const_router.add_response(route_type, route, response)
     }
+
+    /// Answers a request built from the arguments as `index` answers the requests of the server.
+    /// The middlewares of actix, e.g. the metrics, aren't run, but the default response headers
+    /// are added.
+    pub async fn answer_test_request(
+        &self,
+        method: http::Method,
+        uri: &str,
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+    ) -> PyResult<Response> {
+        let request_config = self.config.request.clone();
+        let mut test_request = test::TestRequest::default()
+            .method(method)
+            .uri(uri)
+            // the requests come from the same host as the app
+            .peer_addr(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .insert_header((http::header::HOST, "localhost"))
+            .set_payload(body);
+        for (key, value) in headers {
+            test_request = test_request.insert_header((key, value));
+        }
+        let (req, mut payload) = test_request.to_http_parts();
+        let payload = web::Payload::from_request(&req, &mut payload)
+            .await
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        let mut response = answer_panics(
+            index(
+                web::Data::new(self.http_routes.clone()),
+                web::Data::new(self.middleware_router.clone()),
+                self.global_request_headers.clone(),
+                self.global_response_headers.clone(),
+                web::Data::new(self.error_pages.clone()),
+                web::Data::new(request_config),
+                payload,
+                req,
+            ),
+            &self.error_pages,
+        )
+        .await;
+        if let Some(server_header) = self.config.server_header.clone() {
+            if !response
+                .headers
+                .keys()
+                .any(|key| key.eq_ignore_ascii_case(http::header::SERVER.as_str()))
+            {
+                response
+                    .headers
+                    .insert(http::header::SERVER.to_string(), server_header);
+            }
+        }
+        for header in self.default_response_headers.iter() {
+            if !response
+                .headers
+                .keys()
+                .any(|key| key.eq_ignore_ascii_case(header.key()))
+            {
+                response
+                    .headers
+                    .insert(header.key().clone(), header.value().clone());
+            }
+        }
+        // the test responses carry their whole body
+        response.collect_stream()?;
+        Ok(response)
+    }
+}
+
+/// Answers a CORS preflight request to a path without an `OPTIONS` route, when CORS is enabled
+/// with an `Access-Control-Allow-Origin` header added to the requests (see `ALLOW_CORS`) or to
+/// the responses. The allowed methods are the ones with a route matching the path, `None` is
+/// returned when there is none, so the request is answered as any other.
+fn cors_preflight(
+    req: &HttpRequest,
+    router: &HttpRouter,
+    const_router: &ConstRouter,
+    global_request_headers: &DashMap<String, String>,
+    global_response_headers: &DashMap<String, String>,
+) -> Option<Response> {
+    if req.method() != http::Method::OPTIONS
+        || !req
+            .headers()
+            .contains_key(http::header::ACCESS_CONTROL_REQUEST_METHOD)
+    {
+        return None;
+    }
+    let cors_headers: HashMap<String, String> = global_request_headers
+        .iter()
+        .chain(global_response_headers.iter())
+        // the static list of methods set by `ALLOW_CORS` is replaced with the ones of the path
+        .filter(|header| {
+            let key = header.key().to_ascii_lowercase();
+            key.starts_with("access-control-") && key != "access-control-allow-methods"
+        })
+        .map(|header| (header.key().clone(), header.value().clone()))
+        .collect();
+    if !cors_headers
+        .keys()
+        .any(|key| key.eq_ignore_ascii_case("access-control-allow-origin"))
+    {
+        return None;
+    }
+    let path = req.uri().path();
+    let allowed_methods: Vec<&str> = HttpMethod::ALL
+        .iter()
+        .filter(|method| {
+            router.get_route(method, path).is_some()
+                || const_router.get_route(method, path).is_some()
+        })
+        .map(HttpMethod::as_str)
+        .collect();
+    if allowed_methods.is_empty() {
+        return None;
+    }
+    Some(Response::cors_preflight(
+        cors_headers,
+        &allowed_methods.join(", "),
+    ))
+}
+
+/// Summarizes the head of a request for the debug logs of its rejection, e.g. `GET /users with
+/// 4 headers in 152 bytes`, the size being the one of the head as actix parsed it
+fn request_summary(req: &HttpRequest) -> String {
+    let request_line = format!("{} {} {:?}\r\n", req.method(), req.uri(), req.version());
+    let headers_size: usize = req
+        .headers()
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + 4)
+        .sum();
+    format!(
+        "{} {} with {} headers in {} bytes",
+        req.method(),
+        req.uri(),
+        req.headers().len(),
+        request_line.len() + headers_size + 2
+    )
+}
+
+/// The host a request is sent to, in lowercase and without its port, from its URI when it is in
+/// absolute form, else from its `Host` header. `Err` when the header is given more than once or
+/// isn't a valid host.
+fn request_host(req: &HttpRequest) -> Result<Option<String>, ()> {
+    if let Some(host) = req.uri().host() {
+        return Ok(Some(host.to_ascii_lowercase()));
+    }
+    let mut hosts = req.headers().get_all(http::header::HOST);
+    let host = match hosts.next() {
+        Some(host) => host,
+        None => return Ok(None),
+    };
+    if hosts.next().is_some() {
+        return Err(());
+    }
+    let authority = host
+        .to_str()
+        .map_err(|_| ())?
+        .trim()
+        .parse::<http::uri::Authority>()
+        .map_err(|_| ())?;
+    Ok(Some(authority.host().to_ascii_lowercase()))
+}
+
+/// Answers a `CONNECT` request with a `200 Connection Established` and hands the rest of the
+/// connection to the tunnel handler, both ways, until it returns. The connection is closed then,
+/// as no other request can follow on it. Without a handler the request is refused with a 405.
+fn open_tunnel(
+    tunnel_handler: Option<Arc<FunctionInfo>>,
+    global_request_headers: &DashMap<String, String>,
+    error_pages: &DashMap<u16, String>,
+    request_config: &RequestConfig,
+    task_locals: pyo3_asyncio::TaskLocals,
+    payload: web::Payload,
+    req: HttpRequest,
+) -> HttpResponse {
+    let function = match tunnel_handler {
+        Some(function) => function,
+        None => {
+            debug!("No tunnel handler for the CONNECT to {}", req.uri());
+            // the rest of the connection would be read as the body of the request
+            req.extensions_mut().insert(CloseConnection);
+            let mut response = Response::method_not_allowed(&HashMap::new());
+            response.headers.insert(
+                "Allow".to_string(),
+                SUPPORTED_METHODS.replace(", CONNECT", ""),
+            );
+            return response.with_error_page(error_pages).respond_to(&req);
+        }
+    };
+    let request = Request::from_actix_request(
+        &req,
+        global_request_headers,
+        request_config.trust_proxy_headers,
+    );
+    let (tunnel, body) = Tunnel::open(payload.into_inner());
+    // the handler runs as the body is polled, outside of the scope of the request
+    let body = body.run(pyo3_asyncio::tokio::scope_local(task_locals, async move {
+        if let Err(e) = execute_tunnel_function(&request, tunnel, &function).await {
+            error!(
+                "Error while executing the tunnel handler for {}: {}",
+                request.url.host,
+                get_traceback(&e)
+            );
+        }
+    }));
+    let mut response = HttpResponse::Ok().force_close().streaming(body);
+    response.head_mut().reason = Some("Connection Established");
+    // the bytes of the tunnel are sent as they are written, without any framing
+    response.head_mut().no_chunking(true);
+    response
 }
 
 /// This is our service handler. It receives a Request, routes on it
 /// path, and returns a Future of a Response.
+#[allow(clippy::too_many_arguments)]
 async fn index(
-    router: web::Data<Arc<HttpRouter>>,
-    const_router: web::Data<Arc<ConstRouter>>,
+    http_routes: web::Data<HttpRoutes>,
     middleware_router: web::Data<Arc<MiddlewareRouter>>,
-    global_request_headers: web::Data<Arc<DashMap<String, String>>>,
-    global_response_headers: web::Data<Arc<DashMap<String, String>>>,
-    body: Bytes,
+    global_request_headers: Arc<DashMap<String, String>>,
+    global_response_headers: Arc<DashMap<String, String>>,
+    error_pages: web::Data<Arc<DashMap<u16, String>>>,
+    request_config: web::Data<RequestConfig>,
+    payload: web::Payload,
     req: HttpRequest,
-) -> impl Responder {
-    let mut request = Request::from_actix_request(&req, body, &global_request_headers);
+) -> Response {
+    let mut request = Request::from_actix_request(
+        &req,
+        &global_request_headers,
+        request_config.trust_proxy_headers,
+    );
+    // the guard is kept with the request until the response is sent, see `Response::respond_to`
+    let cancellation = CancellationToken::new();
+    req.extensions_mut()
+        .insert(CancelOnDrop::new(cancellation.clone()));
+    request.cancellation = Some(cancellation);
+    // a malformed deadline is ignored, as if it wasn't sent
+    request.deadline = request_config
+        .deadline_header
+        .as_ref()
+        .and_then(|name| req.headers().get(name))
+        .and_then(|deadline| deadline.to_str().ok())
+        .and_then(|deadline| parse_deadline(deadline, SystemTime::now()));
+
+    // the request head as a whole is already bounded by actix, this rejects long URIs
+    // before they reach the middlewares and the routers
+    let uri_length = req
+        .uri()
+        .path_and_query()
+        .map_or(0, |path_and_query| path_and_query.as_str().len());
+    if uri_length > request_config.max_uri_length {
+        debug!(
+            "URI of length {} is above the limit of {}: {}",
+            uri_length,
+            request_config.max_uri_length,
+            request_summary(&req)
+        );
+        return Response::uri_too_long(&request.headers).with_error_page(&error_pages);
+    }
+    // actix stops parsing the head at its own limit, so a request with many more headers never
+    // gets here, the lower limits are checked once it is parsed
+    if req.headers().len() > request_config.max_headers {
+        debug!(
+            "{} headers are above the limit of {}: {}",
+            req.headers().len(),
+            request_config.max_headers,
+            request_summary(&req)
+        );
+        return Response::too_many_headers().with_error_page(&error_pages);
+    }
+    // actix reads the body from the chunks, a proxy in front of the server may read it from the
+    // `Content-Length` and forward a request smuggled in the body. The connection isn't reused
+    // in either case, as the RFC 9112 requires, and the requests actix already read after it
+    // are refused.
+    let connection = req.conn_data::<ConnectionRequests>();
+    if connection.is_some_and(|connection| connection.conflicting_length.get()) {
+        debug!(
+            "Refused a request read after a conflicting length: {}",
+            request_summary(&req)
+        );
+        req.extensions_mut().insert(CloseConnection);
+        return Response::conflicting_length().with_error_page(&error_pages);
+    }
+    if let Some(ignored) = ignored_length_header(&req) {
+        if let Some(connection) = connection {
+            connection.conflicting_length.set(true);
+        }
+        req.extensions_mut().insert(CloseConnection);
+        if !request_config.strip_conflicting_length {
+            debug!(
+                "Conflicting Content-Length and Transfer-Encoding: {}",
+                request_summary(&req)
+            );
+            return Response::conflicting_length().with_error_page(&error_pages);
+        }
+        request.headers.remove(ignored.as_str());
+    }
+
+    // `100-continue` is answered by actix before the body is read, the other expectations can't
+    // be met. The client may be waiting for an interim response before sending the body, or be
+    // sending it already, so the connection is closed rather than the body read.
+    if let Some(expect) = req.headers().get(http::header::EXPECT) {
+        if !expect
+            .to_str()
+            .is_ok_and(|expect| expect.trim().eq_ignore_ascii_case("100-continue"))
+        {
+            debug!(
+                "Unsupported expectation {:?}: {}",
+                expect,
+                request_summary(&req)
+            );
+            req.extensions_mut().insert(CloseConnection);
+            return Response::expectation_failed().with_error_page(&error_pages);
+        }
+    }
+
+    // the absolute URIs are served like their path on the host they name, unless they are refused
+    if request_config.refuse_absolute_form && req.uri().scheme().is_some() {
+        debug!("Refused the absolute URI: {}", request_summary(&req));
+        return Response::absolute_form_refused().with_error_page(&error_pages);
+    }
+
+    // HTTP/1.1 requires a single `Host` header, the unknown hosts are refused before the routes
+    match request_host(&req) {
+        Err(()) => {
+            let hosts: Vec<_> = req.headers().get_all(http::header::HOST).collect();
+            debug!("Invalid host {:?}: {}", hosts, request_summary(&req));
+            return Response::invalid_host(400).with_error_page(&error_pages);
+        }
+        Ok(None) if req.version() == http::Version::HTTP_11 => {
+            debug!("Missing host: {}", request_summary(&req));
+            return Response::invalid_host(400).with_error_page(&error_pages);
+        }
+        Ok(host) => {
+            let allowed_hosts = &request_config.allowed_hosts;
+            if !allowed_hosts.is_empty() && !host.is_some_and(|host| allowed_hosts.contains(&host))
+            {
+                debug!(
+                    "Unknown host {:?}: {}",
+                    req.headers().get(http::header::HOST),
+                    request_summary(&req)
+                );
+                return Response::invalid_host(request_config.unknown_host_status)
+                    .with_error_page(&error_pages);
+            }
+        }
+    }
+
+    // the route is matched on the path of the actix request, which the middlewares can't change,
+    // so its pattern is already known to them
+    let method = match HttpMethod::from_actix_method(req.method()) {
+        Some(method) => method,
+        None => {
+            debug!("Unknown method: {}", request_summary(&req));
+            return Response::not_implemented().with_error_page(&error_pages);
+        }
+    };
+    let (router, const_router) = http_routes.current();
+    let const_route = const_router.get_route(&method, req.uri().path());
+    let route = match const_route {
+        Some(_) => None,
+        None => router.get_route(&method, req.uri().path()),
+    };
+    request.matched_route = const_route
+        .as_ref()
+        .map(|(_, pattern)| pattern.clone())
+        .or_else(|| route.as_ref().map(|(_, _, pattern)| pattern.clone()));
+    if let Some(matched_route) = &request.matched_route {
+        req.extensions_mut()
+            .insert(MatchedRoute(matched_route.clone()));
+    }
+
+    // the preflight requests are answered before the middlewares, they carry no credentials
+    if route.is_none() && const_route.is_none() {
+        if let Some(response) = cors_preflight(
+            &req,
+            &router,
+            &const_router,
+            &global_request_headers,
+            &global_response_headers,
+        ) {
+            return response;
+        }
+    }
+
+    // the body is read before the middlewares run, unless the handler of the route streams it
+    let mut payload = payload.into_inner();
+    let function = route.as_ref().map(|(function, _, _)| function);
+    let boundary = function
+        .filter(|function| function.stream_files && !function.stream_body)
+        .and_then(|_| form_boundary(&req));
+    // the limit of the route replaces the global one, whether it is lower or higher
+    let limit = function
+        .and_then(|function| function.max_body_size)
+        .unwrap_or(request_config.max_payload_size);
+    // the uploaded files are removed once the request is answered
+    let mut _temp_files = None;
+    if let Some(boundary) = boundary {
+        match read_form(&req, payload.take(), &boundary, limit).await {
+            Ok(form) => {
+                request.form_data = form.fields;
+                request.files = form.files;
+                _temp_files = Some(form.temp_files);
+            }
+            Err(e) => {
+                debug!(
+                    "Error while reading the multipart form: {}: {}",
+                    e,
+                    request_summary(&req)
+                );
+                return Response::payload_error(&e).with_error_page(&error_pages);
+            }
+        }
+    } else if !function.is_some_and(|function| function.stream_body) {
+        match read_body(&req, payload.take(), limit).await {
+            Ok(body) => request.body = body.to_vec(),
+            Err(e) => {
+                debug!(
+                    "Error while reading the request body: {}: {}",
+                    e,
+                    request_summary(&req)
+                );
+                return Response::payload_error(&e).with_error_page(&error_pages);
+            }
+        }
+    }
 
     // Before middleware
     // Global
@@ -405,24 +2217,30 @@ async fn index(
                     req.uri().path(),
                     get_traceback(e.downcast_ref::<PyErr>().unwrap())
                 );
-                return Response::internal_server_error(&request.headers);
+                return Response::internal_server_error(&request.headers)
+                    .with_error_page(&error_pages);
             }
         };
     }
 
     // Route execution
-    let mut response = if let Some(res) = const_router.get_route(
-        &HttpMethod::from_actix_method(req.method()),
-        req.uri().path(),
-    ) {
+    let mut response = if req.method() == http::Method::OPTIONS && req.uri().path() == "*" {
+        Response::server_options(&request.headers)
+    } else if let Some((res, _)) = const_route {
         res
-    } else if let Some((function, route_params)) = router.get_route(
-        &HttpMethod::from_actix_method(req.method()),
-        req.uri().path(),
-    ) {
+    } else if let Some((function, route_params, _)) = route {
         request.path_params = route_params;
-        execute_http_function(&request, &function)
-            .await
+        let output = if function.stream_body {
+            let (stream, feeder) = BodyStream::new(&req, payload);
+            request.stream = Some(Python::with_gil(|py| Py::new(py, stream)).unwrap());
+            feeder
+                .run_until(execute_http_function(&request, &function))
+                .await
+        } else {
+            execute_http_function(&request, &function).await
+        };
+        output
+            .map(|response| response.with_cache_control(function.cache_control.as_deref()))
             .unwrap_or_else(|e| {
                 error!(
                     "Error while executing route function for endpoint `{}`: {}",
@@ -430,17 +2248,54 @@ async fn index(
                     get_traceback(&e)
                 );
 
-                Response::internal_server_error(&request.headers)
+                Response::internal_server_error(&request.headers).with_error_page(&error_pages)
             })
+    } else if req.method() == http::Method::TRACE {
+        // unless a route handles them, TRACE requests are refused as an echo of the request
+        // can be used to read headers from a script
+        if request_config.trace_echo {
+            Response::trace(&req)
+        } else {
+            Response::method_not_allowed(&request.headers).with_error_page(&error_pages)
+        }
     } else {
-        Response::not_found(&request.headers)
+        match execute_fallbacks(&request, &router.get_fallbacks(), &error_pages).await {
+            Some(response) => response,
+            // the browsers ask every site for its icon, which isn't worth a 404
+            None if request_config.favicon_fallback && is_favicon_request(&req) => {
+                match &request_config.favicon {
+                    Some(favicon) => Response::from_file(favicon),
+                    None => Response::no_content(),
+                }
+            }
+            None => Response::not_found(&request.headers).with_error_page(&error_pages),
+        }
     };
 
-    response.headers.extend(
-        global_response_headers
-            .iter()
-            .map(|elt| (elt.key().clone(), elt.value().clone())),
-    );
+    // the client's cached copy of a file is only checked for safe methods, and `If-None-Match`
+    // takes precedence over the date when both are sent, see RFC 9110 13.1.3
+    let if_modified_since = match *req.method() {
+        http::Method::GET | http::Method::HEAD
+            if !req.headers().contains_key(http::header::IF_NONE_MATCH) =>
+        {
+            req.headers().get(http::header::IF_MODIFIED_SINCE)
+        }
+        _ => None,
+    };
+    response = response.with_last_modified(if_modified_since);
+
+    for header in global_response_headers.iter() {
+        // a global `Vary` is merged with the one of the route instead of replacing it
+        if header.key().eq_ignore_ascii_case("vary") {
+            for field in header.value().split(',') {
+                response.add_vary(field.trim());
+            }
+        } else {
+            response
+                .headers
+                .insert(header.key().clone(), header.value().clone());
+        }
+    }
 
     // After middleware
     // Global
@@ -456,7 +2311,8 @@ async fn index(
         response = match execute_middleware_function(&response, &after_middleware).await {
             Ok(MiddlewareReturn::Request(_)) => {
                 error!("After middleware returned a request");
-                return Response::internal_server_error(&request.headers);
+                return Response::internal_server_error(&request.headers)
+                    .with_error_page(&error_pages);
             }
             Ok(MiddlewareReturn::Response(r)) => r,
             Err(e) => {
@@ -465,16 +2321,176 @@ async fn index(
                     req.uri().path(),
                     get_traceback(e.downcast_ref::<PyErr>().unwrap())
                 );
-                return Response::internal_server_error(&request.headers);
+                return Response::internal_server_error(&request.headers)
+                    .with_error_page(&error_pages);
             }
         };
     }
 
+    // the compressed variants are swapped in last, the after middlewares work on the body as is
+    let accept_encoding = req.headers().get(http::header::ACCEPT_ENCODING);
+    response = response
+        .with_precompressed(accept_encoding)
+        .with_brotli(accept_encoding, request_config.brotli_quality);
+
+    debug!(
+        "{} {} matched {} with a {}",
+        req.method(),
+        req.uri().path(),
+        request.matched_route.as_deref().unwrap_or("<not found>"),
+        response.status_code
+    );
     debug!("Response: {:?}", response);
 
     response
 }
 
+/// The number of workers given to `start`, else the `ROBYN_WORKERS` environment variable, as
+/// looked up by `env_var`, else one per core the process may run on, as given by
+/// `available_parallelism`. A number that isn't a positive integer is skipped, with a warning for
+/// the environment variable. The cores can't be known on some platforms or in restricted
+/// containers, `FALLBACK_WORKERS` are used then.
+fn resolve_worker_count(
+    config: Option<usize>,
+    env_var: impl FnOnce(&str) -> Option<String>,
+    available_parallelism: impl FnOnce() -> std::io::Result<std::num::NonZeroUsize>,
+) -> usize {
+    if let Some(workers) = config.filter(|workers| *workers > 0) {
+        return workers;
+    }
+    if let Some(value) = env_var(WORKERS) {
+        match value.trim().parse::<usize>() {
+            Ok(workers) if workers > 0 => return workers,
+            _ => warn!(
+                "Ignoring the environment variable {WORKERS} - `{value}` isn't a positive number \
+                 of workers"
+            ),
+        }
+    }
+    match available_parallelism() {
+        Ok(cores) => cores.get(),
+        Err(e) => {
+            warn!("The number of cores is unknown ({e}), {FALLBACK_WORKERS} workers are started");
+            FALLBACK_WORKERS
+        }
+    }
+}
+
+/// Reads and parses an environment variable, returns the default value if it isn't set
+fn get_env_var<T>(key: &str, default: T) -> PyResult<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match env::var(key) {
+        Ok(value) => value.trim().parse::<T>().map_err(|e| {
+            PyValueError::new_err(format!("Failed to parse environment variable {key} - {e}"))
+        }),
+        Err(_) => Ok(default),
+    }
+}
+
+/// The response of the first fallback answering the request, `None` when they all leave it to
+/// the 404. A fallback raising an exception answers it with a 500.
+async fn execute_fallbacks(
+    request: &Request,
+    fallbacks: &[FunctionInfo],
+    error_pages: &DashMap<u16, String>,
+) -> Option<Response> {
+    for fallback in fallbacks {
+        match execute_fallback_function(request, fallback).await {
+            Ok(Some(response)) => return Some(response),
+            Ok(None) => continue,
+            Err(e) => {
+                error!(
+                    "Error while executing fallback function for endpoint `{}`: {}",
+                    request.url.path,
+                    get_traceback(&e)
+                );
+                return Some(
+                    Response::internal_server_error(&request.headers).with_error_page(error_pages),
+                );
+            }
+        }
+    }
+    None
+}
+
+/// The span of a request, identified by its `X-Request-Id` header when the client sends a
+/// valid one, e.g. as it is forwarded by a proxy, else by a new id
+fn request_span(req: &HttpRequest) -> Span {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LENGTH)
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    tracing::debug_span!(
+        "request",
+        method = %req.method(),
+        path = req.path(),
+        request_id = request_id.as_str(),
+        route = field::Empty,
+        status = field::Empty,
+        ttfb_ms = field::Empty,
+        duration_ms = field::Empty,
+    )
+}
+
+/// The duration in milliseconds, as recorded in the spans and logs
+fn milliseconds(duration: std::time::Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+/// Whether the request is the one of the browsers for the icon of the site
+fn is_favicon_request(req: &HttpRequest) -> bool {
+    matches!(*req.method(), http::Method::GET | http::Method::HEAD) && req.path() == "/favicon.ico"
+}
+
+/// The value of the `Server` header of the responses, which isn't sent when the setting is
+/// empty
+fn server_header(settings: Settings, default: String) -> PyResult<Option<String>> {
+    let value: String = settings.get(SERVER_HEADER, default)?;
+    if value.is_empty() {
+        return Ok(None);
+    }
+    http::header::HeaderValue::from_str(&value).map_err(|e| {
+        PyValueError::new_err(format!(
+            "Invalid {} - {e}",
+            settings.describe(SERVER_HEADER)
+        ))
+    })?;
+    Ok(Some(value))
+}
+
+/// A panic while answering a request is answered with a `500`, rather than by dropping the
+/// connection
+async fn answer_panics(
+    answer: impl Future<Output = Response>,
+    error_pages: &DashMap<u16, String>,
+) -> Response {
+    AssertUnwindSafe(answer)
+        .catch_unwind()
+        .await
+        .unwrap_or_else(|payload| {
+            error!(
+                "The request handling panicked: {}",
+                panic_message(payload.as_ref())
+            );
+            Response::internal_server_error(&HashMap::new()).with_error_page(error_pages)
+        })
+}
+
+/// The message given to `panic!`, as its payload is only known to be `Any`. The other payloads
+/// get a generic message, as they may not even be printable.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("handler panicked")
+}
+
 fn get_traceback(error: &PyErr) -> String {
     Python::with_gil(|py| -> String {
         if let Some(traceback) = error.traceback(py) {
@@ -488,3 +2504,58 @@ fn get_traceback(error: &PyErr) -> String {
         error.value(py).to_string()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_worker_count, FALLBACK_WORKERS, WORKERS};
+    use std::io;
+    use std::num::NonZeroUsize;
+
+    fn three_cores() -> io::Result<NonZeroUsize> {
+        Ok(NonZeroUsize::new(3).unwrap())
+    }
+
+    #[test]
+    fn workers_fall_back_when_the_cores_are_unknown() {
+        let workers = resolve_worker_count(
+            None,
+            |_| None,
+            || Err(io::Error::new(io::ErrorKind::Unsupported, "no cores")),
+        );
+        assert_eq!(workers, FALLBACK_WORKERS);
+    }
+
+    #[test]
+    fn workers_match_the_cores() {
+        let workers = resolve_worker_count(None, |_| None, three_cores);
+        assert_eq!(workers, 3);
+    }
+
+    #[test]
+    fn workers_read_from_the_environment() {
+        let workers = resolve_worker_count(
+            None,
+            |key| (key == WORKERS).then(|| " 5 ".to_string()),
+            three_cores,
+        );
+        assert_eq!(workers, 5);
+    }
+
+    #[test]
+    fn workers_match_the_cores_when_the_environment_is_invalid() {
+        for value in ["many", "0", "-1"] {
+            let workers = resolve_worker_count(None, |_| Some(value.to_string()), three_cores);
+            assert_eq!(workers, 3);
+        }
+    }
+
+    #[test]
+    fn workers_given_to_start_come_first() {
+        let workers = resolve_worker_count(Some(2), |_| Some("5".to_string()), three_cores);
+        assert_eq!(workers, 2);
+        let workers = resolve_worker_count(Some(0), |_| None, three_cores);
+        assert_eq!(workers, 3);
+        let workers = resolve_worker_count(Some(0), |_| Some("5".to_string()), three_cores);
+        assert_eq!(workers, 5);
+    }
+}
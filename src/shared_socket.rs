@@ -1,3 +1,4 @@
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
 use log::debug;
@@ -33,6 +34,15 @@ impl SocketHeld {
         Ok(SocketHeld { socket })
     }
 
+    /// The port the socket is bound to, which is picked by the OS when binding to port 0
+    pub fn local_port(&self) -> PyResult<u16> {
+        let address = self.socket.local_addr()?;
+        address
+            .as_socket()
+            .map(|address| address.port())
+            .ok_or_else(|| PyValueError::new_err("The socket is not bound to an IP address"))
+    }
+
     pub fn try_clone(&self) -> PyResult<SocketHeld> {
         let copied = self.socket.try_clone()?;
         Ok(SocketHeld { socket: copied })
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::thread;
+
+use actix_web::http::Method;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use tokio::sync::oneshot;
+
+use crate::server::Server;
+use crate::types::get_body_from_pyobject;
+
+/// Sends requests to the routes of a server without going over the network, and returns
+/// their responses e.g. to test the handlers.
+/// The requests go through the middlewares and the routers, but the server isn't started,
+/// so the startup handlers aren't run and the directories aren't served.
+#[pyclass]
+pub struct TestClient {
+    server: Server,
+}
+
+#[pymethods]
+impl TestClient {
+    #[new]
+    pub fn new(server: &Server) -> Self {
+        Self {
+            server: server.clone(),
+        }
+    }
+
+    /// The path can have a query string, e.g. `/users?page=2`
+    #[pyo3(signature = (method, path, body = None, headers = None))]
+    pub fn request(
+        &self,
+        py: Python,
+        method: &str,
+        path: String,
+        body: Option<&PyAny>,
+        headers: Option<HashMap<String, String>>,
+    ) -> PyResult<PyObject> {
+        let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let body = body.map(get_body_from_pyobject).transpose()?;
+        let server = self.server.clone();
+
+        // the async handlers run on the event loop driven here, while the request is answered
+        // on a thread of its own as the requests of actix can't be sent across threads
+        pyo3_asyncio::tokio::run(py, async move {
+            let task_locals = Python::with_gil(pyo3_asyncio::tokio::get_current_locals)?;
+            let (sender, receiver) = oneshot::channel();
+            thread::spawn(move || {
+                let response = actix_web::rt::System::new().block_on(
+                    pyo3_asyncio::tokio::scope_local(task_locals, async move {
+                        server
+                            .answer_test_request(
+                                method,
+                                &path,
+                                headers.unwrap_or_default(),
+                                body.unwrap_or_default(),
+                            )
+                            .await
+                    }),
+                );
+                let _ = sender.send(response);
+            });
+            let response = receiver
+                .await
+                .map_err(|_| PyRuntimeError::new_err("The request handling panicked"))??;
+            Ok(Python::with_gil(|py| response.to_object(py)))
+        })
+    }
+
+    #[pyo3(signature = (path, headers = None))]
+    pub fn get(
+        &self,
+        py: Python,
+        path: String,
+        headers: Option<HashMap<String, String>>,
+    ) -> PyResult<PyObject> {
+        self.request(py, "GET", path, None, headers)
+    }
+
+    #[pyo3(signature = (path, body = None, headers = None))]
+    pub fn post(
+        &self,
+        py: Python,
+        path: String,
+        body: Option<&PyAny>,
+        headers: Option<HashMap<String, String>>,
+    ) -> PyResult<PyObject> {
+        self.request(py, "POST", path, body, headers)
+    }
+
+    #[pyo3(signature = (path, body = None, headers = None))]
+    pub fn put(
+        &self,
+        py: Python,
+        path: String,
+        body: Option<&PyAny>,
+        headers: Option<HashMap<String, String>>,
+    ) -> PyResult<PyObject> {
+        self.request(py, "PUT", path, body, headers)
+    }
+
+    #[pyo3(signature = (path, body = None, headers = None))]
+    pub fn patch(
+        &self,
+        py: Python,
+        path: String,
+        body: Option<&PyAny>,
+        headers: Option<HashMap<String, String>>,
+    ) -> PyResult<PyObject> {
+        self.request(py, "PATCH", path, body, headers)
+    }
+
+    #[pyo3(signature = (path, body = None, headers = None))]
+    pub fn delete(
+        &self,
+        py: Python,
+        path: String,
+        body: Option<&PyAny>,
+        headers: Option<HashMap<String, String>>,
+    ) -> PyResult<PyObject> {
+        self.request(py, "DELETE", path, body, headers)
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use actix_web::dev::ServerHandle;
+use log::debug;
+
+/// Limits the connections accepted by the process to a rate, with a bucket refilled with `rate`
+/// tokens a second up to `burst`. Each accepted connection takes a token, and once they are all
+/// taken the accept loop of actix is paused until the next one is refilled, the new connections
+/// waiting in the backlog of the socket meanwhile. actix accepts all the waiting connections as
+/// it resumes, so the ones taken above the bucket are made up for by a longer pause.
+pub struct AcceptRate {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    // set once the server runs, the accept loop can't be paused before
+    server: OnceLock<ServerHandle>,
+}
+
+struct Bucket {
+    // below `0` once more connections were accepted than there were tokens
+    tokens: f64,
+    refilled: Instant,
+    paused: bool,
+}
+
+impl AcceptRate {
+    pub fn new(rate: u32, burst: u32) -> Self {
+        Self {
+            rate: rate as f64,
+            burst: burst as f64,
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                refilled: Instant::now(),
+                paused: false,
+            }),
+            server: OnceLock::new(),
+        }
+    }
+
+    pub fn set_server(&self, server: ServerHandle) {
+        let _ = self.server.set(server);
+    }
+
+    /// The bucket with the tokens refilled since it was last used
+    fn bucket(&self) -> MutexGuard<'_, Bucket> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let refilled = (now - bucket.refilled).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refilled).min(self.burst);
+        bucket.refilled = now;
+        bucket
+    }
+
+    /// The time left until the bucket has a token again
+    fn time_to_token(&self, bucket: &Bucket) -> Duration {
+        Duration::from_secs_f64((1.0 - bucket.tokens).max(0.0) / self.rate)
+    }
+
+    /// Takes the token of a connection as it is accepted, on the worker it is given to. The
+    /// accept loop is paused once the bucket is empty.
+    pub fn accepted(self: &Arc<Self>) {
+        let mut bucket = self.bucket();
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 1.0 || bucket.paused {
+            return;
+        }
+        let server = match self.server.get() {
+            Some(server) => server.clone(),
+            None => return,
+        };
+        bucket.paused = true;
+        debug!(
+            "Reached the accept rate of {}/s, the new connections are accepted in {:?}",
+            self.rate,
+            self.time_to_token(&bucket)
+        );
+        drop(bucket);
+
+        let accept_rate = self.clone();
+        actix_web::rt::spawn(async move {
+            server.pause().await;
+            loop {
+                // the connections accepted while the pause was sent took tokens too
+                let pause = {
+                    let mut bucket = accept_rate.bucket();
+                    let pause = accept_rate.time_to_token(&bucket);
+                    bucket.paused = !pause.is_zero();
+                    pause
+                };
+                if pause.is_zero() {
+                    break;
+                }
+                actix_web::rt::time::sleep(pause).await;
+            }
+            server.resume().await;
+        });
+    }
+
+    /// The number of connections held back while the accept loop is paused, the tokens missing
+    /// until it resumes
+    pub fn throttled_accepts(&self) -> usize {
+        let bucket = self.bucket();
+        if bucket.paused {
+            (1.0 - bucket.tokens).ceil().max(1.0) as usize
+        } else {
+            0
+        }
+    }
+}
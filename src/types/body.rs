@@ -0,0 +1,65 @@
+use actix_http::encoding::Decoder;
+use actix_http::error::PayloadError;
+use actix_web::dev::Payload;
+use actix_web::http::{header, header::HeaderName, Version};
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::HttpRequest;
+use futures_util::StreamExt;
+
+/// Whether the body of the request is sent in chunks. Actix reads a chunked body whatever its
+/// `Content-Length`, and ignores the `Transfer-Encoding` of the HTTP/1.0 requests.
+pub fn is_chunked(req: &HttpRequest) -> bool {
+    req.version() == Version::HTTP_11
+        && req
+            .headers()
+            .get(header::TRANSFER_ENCODING)
+            .is_some_and(|encoding| encoding.as_bytes().eq_ignore_ascii_case(b"chunked"))
+}
+
+/// The header ignored by actix when a request has both a `Content-Length` and a
+/// `Transfer-Encoding`. A proxy reading the body from the other one would see a request
+/// smuggled in the body. `None` when the request has at most one of them.
+pub fn ignored_length_header(req: &HttpRequest) -> Option<HeaderName> {
+    let headers = req.headers();
+    if !headers.contains_key(header::CONTENT_LENGTH)
+        || !headers.contains_key(header::TRANSFER_ENCODING)
+    {
+        return None;
+    }
+    Some(if is_chunked(req) {
+        header::CONTENT_LENGTH
+    } else {
+        header::TRANSFER_ENCODING
+    })
+}
+
+/// Reads the body of a request in memory, decompressing it according to its `Content-Encoding`.
+/// The limit applies to the decompressed body, a `Content-Length` above it is refused before
+/// the body is read.
+pub async fn read_body(
+    req: &HttpRequest,
+    payload: Payload,
+    limit: usize,
+) -> Result<Bytes, PayloadError> {
+    let length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        // a chunked body is read whatever its length, see `ignored_length_header`
+        .filter(|_| !is_chunked(req))
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<usize>().ok());
+    if length.is_some_and(|length| length > limit) {
+        return Err(PayloadError::Overflow);
+    }
+
+    let mut payload = Decoder::from_headers(payload, req.headers());
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > limit {
+            return Err(PayloadError::Overflow);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.freeze())
+}
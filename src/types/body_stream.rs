@@ -0,0 +1,89 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use actix_http::encoding::Decoder;
+use actix_http::error::PayloadError;
+use actix_web::{dev::Payload, web::Bytes, HttpRequest};
+use futures::future::{select, Either};
+use futures::pin_mut;
+use futures_util::StreamExt;
+use pyo3::exceptions::{PyIOError, PyStopAsyncIteration};
+use pyo3::{prelude::*, types::PyBytes};
+use tokio::sync::{mpsc, Mutex};
+
+// the number of chunks read ahead of the handler
+const BUFFERED_CHUNKS: usize = 8;
+
+/// The body of a request, given to the handler in chunks as they are received
+/// instead of being read in memory before the handler is called
+#[pyclass]
+#[derive(Debug)]
+pub struct BodyStream {
+    chunks: Arc<Mutex<mpsc::Receiver<Result<Bytes, PayloadError>>>>,
+}
+
+/// Reads the payload of a request into its `BodyStream`
+pub struct BodyFeeder {
+    payload: Decoder<Payload>,
+    sender: mpsc::Sender<Result<Bytes, PayloadError>>,
+}
+
+impl BodyStream {
+    /// The payload is decompressed according to the `Content-Encoding` of the request
+    pub fn new(req: &HttpRequest, payload: Payload) -> (Self, BodyFeeder) {
+        let (sender, receiver) = mpsc::channel(BUFFERED_CHUNKS);
+        let stream = Self {
+            chunks: Arc::new(Mutex::new(receiver)),
+        };
+        let feeder = BodyFeeder {
+            payload: Decoder::from_headers(payload, req.headers()),
+            sender,
+        };
+        (stream, feeder)
+    }
+}
+
+impl BodyFeeder {
+    /// Reads the payload while the future, the handler reading the stream, runs.
+    /// The payload is read in the task of the request as actix only wakes that one up at
+    /// the end of the payload, so it can't be read from a task of its own.
+    pub async fn run_until<F: Future>(self, future: F) -> F::Output {
+        let feed = self.feed();
+        pin_mut!(future);
+        pin_mut!(feed);
+        match select(future, feed).await {
+            Either::Left((output, _)) => output,
+            Either::Right(((), future)) => future.await,
+        }
+    }
+
+    async fn feed(mut self) {
+        while let Some(chunk) = self.payload.next().await {
+            // the handler has stopped reading the body
+            if self.sender.send(chunk).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl BodyStream {
+    fn __aiter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __anext__<'a>(&self, py: Python<'a>) -> PyResult<Option<&'a PyAny>> {
+        let chunks = self.chunks.clone();
+        let chunk = pyo3_asyncio::tokio::future_into_py(py, async move {
+            match chunks.lock().await.recv().await {
+                Some(Ok(chunk)) => Ok(Python::with_gil(|py| {
+                    PyBytes::new(py, &chunk).to_object(py)
+                })),
+                Some(Err(e)) => Err(PyIOError::new_err(e.to_string())),
+                None => Err(PyStopAsyncIteration::new_err(())),
+            }
+        })?;
+        Ok(Some(chunk))
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering::SeqCst};
+use std::sync::Arc;
+
+use log::debug;
+use pyo3::prelude::*;
+
+const OPEN: u8 = 0;
+const CANCELLED: u8 = 1;
+// the request was answered, its connection may be dropped since
+const FINISHED: u8 = 2;
+
+// set once the process is asked to terminate, while its connections are drained
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+/// Tells the requests in flight, and the next ones, that the process is shutting down
+pub fn start_draining() {
+    DRAINING.store(true, SeqCst);
+}
+
+pub fn is_draining() -> bool {
+    DRAINING.load(SeqCst)
+}
+
+/// Tells a handler whether the client of its request disconnected, so a long computation or a
+/// streamed response can stop early. It is cancelled by `CancelOnDrop` once actix drops the
+/// request, when its connection fails, e.g. is reset, or the body of its response can't be
+/// written. A client only closing its half of the connection isn't gone, it may still read the
+/// response. It also tells whether the server is shutting down, so a handler can answer before
+/// the drain timeout rather than holding the shutdown up.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    state: Arc<AtomicU8>,
+}
+
+#[pymethods]
+impl CancellationToken {
+    #[getter]
+    pub fn cancelled(&self) -> bool {
+        self.state.load(SeqCst) == CANCELLED
+    }
+
+    /// Set once the process got a `SIGTERM`, its connections are closed at the drain timeout
+    #[getter]
+    pub fn shutting_down(&self) -> bool {
+        is_draining()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "CancellationToken(cancelled={}, shutting_down={})",
+            self.cancelled(),
+            self.shutting_down()
+        )
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::default(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        if self
+            .state
+            .compare_exchange(OPEN, CANCELLED, SeqCst, SeqCst)
+            .is_ok()
+        {
+            debug!("The client disconnected before its request was answered");
+        }
+    }
+
+    fn finish(&self) {
+        let _ = self.state.compare_exchange(OPEN, FINISHED, SeqCst, SeqCst);
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cancels the token of a request when it is dropped before the response is sent, as actix
+/// drops the request and the body of its response once the connection is lost
+#[derive(Debug)]
+pub struct CancelOnDrop(Option<CancellationToken>);
+
+impl CancelOnDrop {
+    pub fn new(token: CancellationToken) -> Self {
+        Self(Some(token))
+    }
+
+    pub fn finish(mut self) {
+        if let Some(token) = self.0.take() {
+            token.finish();
+        }
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(token) = self.0.take() {
+            token.cancel();
+        }
+    }
+}
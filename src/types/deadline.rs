@@ -0,0 +1,35 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// The deadline given in a request header, in seconds since the epoch like `time.time()`.
+/// The value is either a timestamp in RFC 3339, e.g. `2023-04-01T12:00:00.5Z`, or a timeout
+/// from `now` in the format of `grpc-timeout`, e.g. `250m`. `None` when it is malformed.
+pub fn parse_deadline(value: &str, now: SystemTime) -> Option<f64> {
+    let value = value.trim();
+    if let Some(timeout) = parse_timeout(value) {
+        let deadline = now.checked_add(timeout)?.duration_since(UNIX_EPOCH).ok()?;
+        return Some(deadline.as_secs_f64());
+    }
+    let deadline = OffsetDateTime::parse(value, &Rfc3339).ok()?;
+    Some(deadline.unix_timestamp_nanos() as f64 / 1e9)
+}
+
+/// A timeout of at most 8 digits followed by its unit, from `H` for hours to `n` for nanoseconds
+fn parse_timeout(value: &str) -> Option<Duration> {
+    let digits = value.get(..value.len().checked_sub(1)?)?;
+    if digits.is_empty() || digits.len() > 8 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = digits.parse().ok()?;
+    let timeout = match &value[digits.len()..] {
+        "H" => Duration::from_secs(amount * 3600),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    };
+    Some(timeout)
+}
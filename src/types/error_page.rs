@@ -0,0 +1,44 @@
+use actix_web::http::StatusCode;
+
+/// The status codes with a built-in error page, used when no page is added for them
+pub const DEFAULT_ERROR_PAGE_STATUSES: [u16; 6] = [400, 403, 404, 413, 500, 503];
+
+// self-contained, so the page renders without any other request to the server
+const DEFAULT_ERROR_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{status} {reason}</title>
+<style>
+body { margin: 0; min-height: 100vh; display: flex; align-items: center; justify-content: center; font-family: system-ui, sans-serif; color: #333; background: #fafafa; }
+main { text-align: center; }
+h1 { margin: 0; font-size: 4em; }
+p { margin: 0.5em 0 0; font-size: 1.25em; }
+</style>
+</head>
+<body>
+<main>
+<h1>{status}</h1>
+<p>{reason}</p>
+</main>
+</body>
+</html>
+"#;
+
+/// The built-in error page of the status code, with its code and reason phrase. `None` for the
+/// status codes without one.
+pub fn default_error_page(status_code: u16) -> Option<String> {
+    if !DEFAULT_ERROR_PAGE_STATUSES.contains(&status_code) {
+        return None;
+    }
+    let reason = StatusCode::from_u16(status_code)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or_default();
+    Some(
+        DEFAULT_ERROR_PAGE
+            .replace("{status}", &status_code.to_string())
+            .replace("{reason}", reason),
+    )
+}
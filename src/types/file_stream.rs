@@ -0,0 +1,203 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::Relaxed};
+
+use actix_web::web::{Bytes, BytesMut};
+use memmap2::Mmap;
+use pyo3::exceptions::PyIOError;
+use pyo3::{prelude::*, types::PyBytes};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::io_helpers::file_store::record_file_read;
+
+pub const DEFAULT_RESPONSE_BUFFER_SIZE: usize = 1_048_576; // 1Mb
+
+// the size of the chunks read from a streamed file
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// set by the server as it is created, the file responses are built by the handlers
+static RESPONSE_BUFFER_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_RESPONSE_BUFFER_SIZE);
+static MMAP_FILES: AtomicBool = AtomicBool::new(false);
+
+/// The files larger than the size are streamed from disk instead of being read in memory,
+/// `0` reads every file in memory
+pub fn set_response_buffer_size(size: usize) {
+    RESPONSE_BUFFER_SIZE.store(size, Relaxed);
+}
+
+/// Whether the streamed files are memory mapped instead of being read a chunk at a time
+pub fn set_mmap_files(mmap_files: bool) {
+    MMAP_FILES.store(mmap_files, Relaxed);
+}
+
+/// A memory mapped file, whose chunks are copied from the page cache without a read call
+#[derive(Debug)]
+pub struct MappedFile {
+    file: File,
+    mapping: Mmap,
+    offset: usize,
+}
+
+impl MappedFile {
+    fn map(file: File) -> io::Result<Self> {
+        // the file can be truncated by another process while it is mapped, which is checked
+        // before each chunk is copied, see `next_chunk`
+        let mapping = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            file,
+            mapping,
+            offset: 0,
+        })
+    }
+
+    /// The next chunk of the file, `None` once it is exhausted. Reading the pages past the end
+    /// of a truncated file would crash the process, so a file shorter than the chunk fails the
+    /// stream instead. A truncation between the check and the copy can't be caught.
+    pub fn next_chunk(&mut self) -> io::Result<Option<Bytes>> {
+        let chunk = self.copy_next_chunk();
+        record_file_read(&chunk, false);
+        chunk
+    }
+
+    fn copy_next_chunk(&mut self) -> io::Result<Option<Bytes>> {
+        if self.offset >= self.mapping.len() {
+            return Ok(None);
+        }
+        let end = self.mapping.len().min(self.offset + CHUNK_SIZE);
+        if self.file.metadata()?.len() < end as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The file was truncated while it was served",
+            ));
+        }
+        let chunk = Bytes::copy_from_slice(&self.mapping[self.offset..end]);
+        self.offset = end;
+        Ok(Some(chunk))
+    }
+}
+
+#[derive(Debug)]
+enum Source {
+    Read(File),
+    Mapped(MappedFile),
+}
+
+/// The chunks of a file, read as they are sent. Given as the stream of the file responses
+/// larger than the response buffer size, so their memory use is bounded by a chunk.
+#[pyclass]
+#[derive(Debug)]
+pub struct FileChunks {
+    // taken by the response once a mapped file is sent without going through python
+    source: Option<Source>,
+    // the size of the file when it was opened, sent as the length of the response
+    pub size: u64,
+}
+
+#[pymethods]
+impl FileChunks {
+    pub fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    pub fn __next__(&mut self, py: Python) -> PyResult<Option<Py<PyBytes>>> {
+        let chunk = match &mut self.source {
+            Some(Source::Read(file)) => {
+                let mut chunk = vec![0; CHUNK_SIZE];
+                let read = py
+                    .allow_threads(|| {
+                        let read = file.read(&mut chunk);
+                        record_file_read(&read, false);
+                        read
+                    })
+                    .map_err(|e| PyIOError::new_err(e.to_string()))?;
+                chunk.truncate(read);
+                Bytes::from(chunk)
+            }
+            Some(Source::Mapped(mapped_file)) => py
+                .allow_threads(|| mapped_file.next_chunk())
+                .map_err(|e| PyIOError::new_err(e.to_string()))?
+                .unwrap_or_default(),
+            None => Bytes::new(),
+        };
+        if chunk.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(PyBytes::new(py, &chunk).into()))
+    }
+}
+
+impl FileChunks {
+    /// Whether a file of the size is larger than the response buffer size, so is streamed
+    pub fn is_streamed(size: u64) -> bool {
+        let buffer_size = RESPONSE_BUFFER_SIZE.load(Relaxed);
+        buffer_size != 0 && size > buffer_size as u64
+    }
+
+    /// The chunks of the file when it is larger than the response buffer size, else `None` as
+    /// it is read in memory
+    pub fn open(py: Python, file_path: &str, size: u64) -> io::Result<Option<Py<Self>>> {
+        if !Self::is_streamed(size) {
+            return Ok(None);
+        }
+        let file = File::open(file_path)?;
+        let source = if MMAP_FILES.load(Relaxed) {
+            Source::Mapped(MappedFile::map(file)?)
+        } else {
+            Source::Read(file)
+        };
+        let chunks = Self {
+            source: Some(source),
+            size,
+        };
+        Ok(Some(Py::new(py, chunks)?))
+    }
+
+    /// The mapped file, if it is one, which can then be sent without acquiring the GIL
+    pub fn take_mapped_file(&mut self) -> Option<MappedFile> {
+        match self.source.take() {
+            Some(Source::Mapped(mapped_file)) => Some(mapped_file),
+            source => {
+                self.source = source;
+                None
+            }
+        }
+    }
+}
+
+/// An async source of bytes streamed as the body of a response
+pub type Reader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// The chunks read from an async reader, given as the stream of the responses built by
+/// `Response::from_stream`. It isn't iterated from python, the response takes the reader and
+/// reads it on the worker.
+#[pyclass]
+pub struct ReaderChunks {
+    // taken by the response once it is sent
+    reader: Option<Reader>,
+}
+
+impl ReaderChunks {
+    pub fn new(py: Python, reader: impl AsyncRead + Send + 'static) -> PyResult<Py<Self>> {
+        Py::new(
+            py,
+            Self {
+                reader: Some(Box::pin(reader)),
+            },
+        )
+    }
+
+    /// The reader, `None` once a response took it
+    pub fn take_reader(&mut self) -> Option<Reader> {
+        self.reader.take()
+    }
+}
+
+/// The next chunk of the reader, `None` once it is exhausted
+pub async fn read_chunk(reader: &mut Reader) -> io::Result<Option<Bytes>> {
+    let mut chunk = BytesMut::with_capacity(CHUNK_SIZE);
+    if reader.read_buf(&mut chunk).await? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(chunk.freeze()))
+}
@@ -6,7 +6,7 @@ use std::{
 use pyo3::prelude::*;
 
 #[pyclass]
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MiddlewareType {
     #[pyo3(name = "BEFORE_REQUEST")]
     BeforeRequest = 0,
@@ -33,16 +33,49 @@ pub struct FunctionInfo {
     pub is_async: bool,
     #[pyo3(get, set)]
     pub number_of_params: u8,
+    // the handler reads the body from `request.stream` instead of `request.body`
+    #[pyo3(get, set)]
+    pub stream_body: bool,
+    // the files of a multipart form are written to disk instead of being read in memory
+    #[pyo3(get, set)]
+    pub stream_files: bool,
+    // the limit of the body of the requests to the route, over the global one
+    #[pyo3(get, set)]
+    pub max_body_size: Option<usize>,
+    // the `Cache-Control` of the responses of the route that don't set one
+    #[pyo3(get, set)]
+    pub cache_control: Option<String>,
 }
 
 #[pymethods]
 impl FunctionInfo {
     #[new]
-    pub fn new(handler: Py<PyAny>, is_async: bool, number_of_params: u8) -> Self {
+    #[pyo3(signature = (
+        handler,
+        is_async,
+        number_of_params,
+        stream_body = false,
+        stream_files = false,
+        max_body_size = None,
+        cache_control = None
+    ))]
+    pub fn new(
+        handler: Py<PyAny>,
+        is_async: bool,
+        number_of_params: u8,
+        stream_body: bool,
+        stream_files: bool,
+        max_body_size: Option<usize>,
+        cache_control: Option<String>,
+    ) -> Self {
         Self {
             handler,
             is_async,
             number_of_params,
+            stream_body,
+            stream_files,
+            max_body_size,
+            cache_control,
         }
     }
 }
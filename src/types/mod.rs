@@ -1,13 +1,27 @@
+use actix_web::http::header;
+use actix_web::HttpRequest;
 use pyo3::{
     exceptions::PyValueError,
     prelude::*,
     types::{PyBytes, PyString},
 };
 
+pub mod accept_rate;
+pub mod body;
+pub mod body_stream;
+pub mod cancellation;
+pub mod deadline;
+pub mod error_page;
+pub mod file_stream;
 pub mod function_info;
 pub mod identity;
+pub mod multipart;
+pub mod negotiation;
+pub mod preconditions;
 pub mod request;
 pub mod response;
+pub mod route_info;
+pub mod tunnel;
 
 #[allow(clippy::large_enum_variant)]
 pub enum MiddlewareReturn {
@@ -15,6 +29,9 @@ pub enum MiddlewareReturn {
     Response(response::Response),
 }
 
+/// All the methods supported by the server, in the format of an `Allow` header
+pub const SUPPORTED_METHODS: &str = "GET, POST, PUT, DELETE, PATCH, HEAD, OPTIONS, CONNECT, TRACE";
+
 #[pyclass]
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -31,8 +48,36 @@ pub enum HttpMethod {
 }
 
 impl HttpMethod {
-    pub fn from_actix_method(method: &actix_web::http::Method) -> Self {
-        match *method {
+    /// All the methods, in the order of `SUPPORTED_METHODS`
+    pub const ALL: [Self; 9] = [
+        Self::GET,
+        Self::POST,
+        Self::PUT,
+        Self::DELETE,
+        Self::PATCH,
+        Self::HEAD,
+        Self::OPTIONS,
+        Self::CONNECT,
+        Self::TRACE,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GET => "GET",
+            Self::POST => "POST",
+            Self::PUT => "PUT",
+            Self::DELETE => "DELETE",
+            Self::PATCH => "PATCH",
+            Self::HEAD => "HEAD",
+            Self::OPTIONS => "OPTIONS",
+            Self::CONNECT => "CONNECT",
+            Self::TRACE => "TRACE",
+        }
+    }
+
+    /// `None` for the extension methods, e.g. `PROPFIND`, which the routers have no table for
+    pub fn from_actix_method(method: &actix_web::http::Method) -> Option<Self> {
+        Some(match *method {
             actix_web::http::Method::GET => Self::GET,
             actix_web::http::Method::POST => Self::POST,
             actix_web::http::Method::PUT => Self::PUT,
@@ -42,8 +87,8 @@ impl HttpMethod {
             actix_web::http::Method::OPTIONS => Self::OPTIONS,
             actix_web::http::Method::CONNECT => Self::CONNECT,
             actix_web::http::Method::TRACE => Self::TRACE,
-            _ => panic!("Unsupported HTTP method"),
-        }
+            _ => return None,
+        })
     }
 }
 
@@ -59,12 +104,65 @@ pub struct Url {
 }
 
 impl Url {
-    fn new(scheme: &str, host: &str, path: &str) -> Self {
+    /// The URL the request was sent to. The `Forwarded` and `X-Forwarded-*` headers set by a
+    /// proxy give its scheme and host only when they are trusted, as any client can send them.
+    /// The host is empty when the request has none, e.g. an HTTP/1.0 request without `Host`.
+    pub fn of_request(req: &HttpRequest, trust_proxy_headers: bool) -> Self {
+        let connection_info = req.connection_info();
+        let scheme = if trust_proxy_headers {
+            connection_info.scheme()
+        } else if req.app_config().secure() {
+            "https"
+        } else {
+            "http"
+        };
+        let headers = req.headers();
+        let host_header = headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok());
+        let forwarded_host = headers.contains_key(header::FORWARDED)
+            || headers.contains_key(header::X_FORWARDED_HOST);
+        // the authority of an absolute URI, e.g. sent to a proxy, takes precedence over the
+        // headers
+        let host = match req.uri().authority() {
+            Some(authority) => authority.as_str().rsplit('@').next().unwrap_or_default(),
+            // the connection info falls back to a made up host without any of the headers
+            None if trust_proxy_headers && (forwarded_host || host_header.is_some()) => {
+                connection_info.host()
+            }
+            None => host_header.unwrap_or_default(),
+        };
         Self {
             scheme: scheme.to_string(),
             host: host.to_string(),
-            path: path.to_string(),
+            path: req.path().to_string(),
+        }
+    }
+}
+
+#[pymethods]
+impl Url {
+    /// The absolute URL of the location on the host of the request, a path, or a reference
+    /// relative to the path of the request. The location is returned as it is when it is
+    /// already absolute, or when the request has no host.
+    pub fn absolute(&self, location: &str) -> String {
+        let is_absolute = location.starts_with("//")
+            || location
+                .find("://")
+                .is_some_and(|end| !location[..end].contains(['/', '?', '#']));
+        if is_absolute || self.host.is_empty() {
+            return location.to_string();
         }
+        let path = if location.starts_with('/') {
+            location.to_string()
+        } else if location.is_empty() || location.starts_with(['?', '#']) {
+            format!("{}{}", self.path, location)
+        } else {
+            // resolved against the directory of the path, as the clients do
+            let directory = &self.path[..self.path.rfind('/').map_or(0, |end| end + 1)];
+            format!("/{}{}", directory.trim_start_matches('/'), location)
+        };
+        format!("{}://{}{}", self.scheme, self.host, path)
     }
 }
 
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::{env, fmt, fs, io};
+
+use actix_http::encoding::Decoder;
+use actix_http::error::PayloadError;
+use actix_web::dev::Payload;
+use actix_web::http::header::{self, ContentDisposition, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::web::BytesMut;
+use actix_web::{HttpMessage, HttpRequest, ResponseError};
+use futures_util::StreamExt;
+use log::debug;
+use pyo3::prelude::*;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+// the size of the headers of a part, above which the form is refused
+const MAX_PART_HEADERS_SIZE: usize = 8 * 1024;
+
+/// A file of a multipart form, written to a temporary file as it is received.
+/// The temporary file is removed once the request is answered, so it has to be moved
+/// by the handler to be kept.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct UploadedFile {
+    // the name of the file on the client, which can't be trusted as a path
+    #[pyo3(get)]
+    pub filename: Option<String>,
+    #[pyo3(get)]
+    pub content_type: Option<String>,
+    #[pyo3(get)]
+    pub path: String,
+    #[pyo3(get)]
+    pub size: usize,
+}
+
+#[pymethods]
+impl UploadedFile {
+    pub fn __repr__(&self) -> String {
+        format!(
+            "UploadedFile(filename={:?}, content_type={:?}, path={:?}, size={})",
+            self.filename, self.content_type, self.path, self.size
+        )
+    }
+}
+
+/// The temporary files of a form, removed when it is dropped
+#[derive(Debug, Default)]
+pub struct TempFiles(Vec<PathBuf>);
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            // the handler may have moved the file
+            if let Err(e) = fs::remove_file(path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    debug!("Error while removing the uploaded file {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+}
+
+/// A multipart form, with the fields read in memory and the files written to disk.
+/// A field sent several times keeps its last value.
+#[derive(Debug, Default)]
+pub struct Form {
+    pub fields: HashMap<String, String>,
+    pub files: HashMap<String, UploadedFile>,
+    pub temp_files: TempFiles,
+}
+
+#[derive(Debug)]
+pub enum MultipartError {
+    Payload(PayloadError),
+    Overflow(usize),
+    Malformed(&'static str),
+    Io(io::Error),
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Payload(e) => e.fmt(f),
+            Self::Overflow(limit) => write!(f, "The form is larger than {} bytes", limit),
+            Self::Malformed(reason) => write!(f, "Malformed multipart form: {}", reason),
+            Self::Io(e) => write!(f, "Error while writing an uploaded file: {}", e),
+        }
+    }
+}
+
+impl ResponseError for MultipartError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Payload(e) => e.status_code(),
+            Self::Overflow(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Malformed(_) => StatusCode::BAD_REQUEST,
+            Self::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<io::Error> for MultipartError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// The boundary of the parts of the body, when the request is a multipart form
+pub fn form_boundary(req: &HttpRequest) -> Option<String> {
+    let mime = req.mime_type().ok()??;
+    if mime.type_() != "multipart" || mime.subtype() != "form-data" {
+        return None;
+    }
+    mime.get_param("boundary")
+        .map(|boundary| boundary.to_string())
+}
+
+enum State {
+    Preamble,
+    AfterBoundary,
+    Headers,
+    Body(Part),
+    End,
+}
+
+enum Part {
+    Field(String, Vec<u8>),
+    File(String, UploadedFile, tokio::fs::File),
+}
+
+/// Reads a multipart form while it is received, decompressing the payload according to the
+/// `Content-Encoding` of the request. The limit applies to the payload as it is received.
+pub async fn read_form(
+    req: &HttpRequest,
+    payload: Payload,
+    boundary: &str,
+    limit: usize,
+) -> Result<Form, MultipartError> {
+    let mut payload = Decoder::from_headers(payload, req.headers());
+    let mut reader = FormReader {
+        // the first boundary can start the body, the next ones start a line
+        delimiter: format!("\r\n--{}", boundary).into_bytes(),
+        buffer: BytesMut::new(),
+        state: State::Preamble,
+        form: Form::default(),
+    };
+    let mut received = 0;
+    loop {
+        while reader.step().await? {}
+        if let State::End = reader.state {
+            return Ok(reader.form);
+        }
+        match payload.next().await {
+            Some(chunk) => {
+                let chunk = chunk.map_err(MultipartError::Payload)?;
+                received += chunk.len();
+                if received > limit {
+                    return Err(MultipartError::Overflow(limit));
+                }
+                reader.buffer.extend_from_slice(&chunk);
+            }
+            None => {
+                return Err(MultipartError::Malformed(
+                    "the form has no closing boundary",
+                ))
+            }
+        }
+    }
+}
+
+struct FormReader {
+    delimiter: Vec<u8>,
+    buffer: BytesMut,
+    state: State,
+    form: Form,
+}
+
+impl FormReader {
+    /// Reads the buffer as far as it can, returns false when it needs more data
+    async fn step(&mut self) -> Result<bool, MultipartError> {
+        match std::mem::replace(&mut self.state, State::End) {
+            State::Preamble => {
+                let boundary = &self.delimiter[2..];
+                if self.buffer.starts_with(boundary) {
+                    let _ = self.buffer.split_to(boundary.len());
+                    self.state = State::AfterBoundary;
+                    return Ok(true);
+                }
+                match find(&self.buffer, &self.delimiter) {
+                    Some(position) => {
+                        let _ = self.buffer.split_to(position + self.delimiter.len());
+                        self.state = State::AfterBoundary;
+                        Ok(true)
+                    }
+                    None => {
+                        self.discard_until_delimiter();
+                        self.state = State::Preamble;
+                        Ok(false)
+                    }
+                }
+            }
+            State::AfterBoundary => {
+                if self.buffer.len() < 2 {
+                    self.state = State::AfterBoundary;
+                    return Ok(false);
+                }
+                match &self.buffer[..2] {
+                    // the epilogue after the closing boundary is ignored
+                    b"--" => Ok(false),
+                    b"\r\n" => {
+                        let _ = self.buffer.split_to(2);
+                        self.state = State::Headers;
+                        Ok(true)
+                    }
+                    _ => Err(MultipartError::Malformed("a boundary is followed by data")),
+                }
+            }
+            State::Headers => {
+                let end = if self.buffer.starts_with(b"\r\n") {
+                    Some(0)
+                } else {
+                    find(&self.buffer, b"\r\n\r\n").map(|position| position + 2)
+                };
+                match end {
+                    Some(end) => {
+                        let headers = self.buffer.split_to(end + 2);
+                        let part = self.start_part(&headers[..end]).await?;
+                        self.state = State::Body(part);
+                        Ok(true)
+                    }
+                    None if self.buffer.len() > MAX_PART_HEADERS_SIZE => Err(
+                        MultipartError::Malformed("the headers of a part are too large"),
+                    ),
+                    None => {
+                        self.state = State::Headers;
+                        Ok(false)
+                    }
+                }
+            }
+            State::Body(mut part) => match find(&self.buffer, &self.delimiter) {
+                Some(position) => {
+                    let data = self.buffer.split_to(position);
+                    let _ = self.buffer.split_to(self.delimiter.len());
+                    part.write(&data).await?;
+                    self.finish_part(part).await?;
+                    self.state = State::AfterBoundary;
+                    Ok(true)
+                }
+                None => {
+                    // the end of the buffer can be the start of the delimiter
+                    let kept = self.delimiter.len() - 1;
+                    if self.buffer.len() > kept {
+                        let data = self.buffer.split_to(self.buffer.len() - kept);
+                        part.write(&data).await?;
+                    }
+                    self.state = State::Body(part);
+                    Ok(false)
+                }
+            },
+            State::End => Ok(false),
+        }
+    }
+
+    fn discard_until_delimiter(&mut self) {
+        let kept = self.delimiter.len() - 1;
+        if self.buffer.len() > kept {
+            let _ = self.buffer.split_to(self.buffer.len() - kept);
+        }
+    }
+
+    async fn start_part(&mut self, headers: &[u8]) -> Result<Part, MultipartError> {
+        let mut content_disposition = None;
+        let mut content_type = None;
+        for line in headers.split(|&byte| byte == b'\n') {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let line = std::str::from_utf8(line)
+                .map_err(|_| MultipartError::Malformed("a header of a part isn't valid utf-8"))?;
+            let (name, value) = match line.split_once(':') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None if line.is_empty() => continue,
+                None => return Err(MultipartError::Malformed("a header of a part has no value")),
+            };
+            if name.eq_ignore_ascii_case(header::CONTENT_DISPOSITION.as_str()) {
+                let value = HeaderValue::from_bytes(value.as_bytes()).map_err(|_| {
+                    MultipartError::Malformed("the content disposition of a part is invalid")
+                })?;
+                content_disposition = Some(ContentDisposition::from_raw(&value).map_err(|_| {
+                    MultipartError::Malformed("the content disposition of a part is invalid")
+                })?);
+            } else if name.eq_ignore_ascii_case(header::CONTENT_TYPE.as_str()) {
+                content_type = Some(value.to_string());
+            }
+        }
+
+        let content_disposition = content_disposition
+            .filter(|content_disposition| content_disposition.is_form_data())
+            .ok_or(MultipartError::Malformed("a part isn't a form field"))?;
+        let name = content_disposition
+            .get_name()
+            .ok_or(MultipartError::Malformed("a part has no name"))?
+            .to_string();
+        match content_disposition.get_filename() {
+            Some(filename) => {
+                let path = env::temp_dir().join(format!("robyn-upload-{}", Uuid::new_v4()));
+                let file = tokio::fs::File::create(&path).await?;
+                self.form.temp_files.0.push(path.clone());
+                let uploaded_file = UploadedFile {
+                    filename: Some(filename.to_string()),
+                    content_type,
+                    path: path.to_string_lossy().into_owned(),
+                    size: 0,
+                };
+                Ok(Part::File(name, uploaded_file, file))
+            }
+            None => Ok(Part::Field(name, vec![])),
+        }
+    }
+
+    async fn finish_part(&mut self, part: Part) -> Result<(), MultipartError> {
+        match part {
+            Part::Field(name, value) => {
+                let value = String::from_utf8_lossy(&value).into_owned();
+                self.form.fields.insert(name, value);
+            }
+            Part::File(name, uploaded_file, mut file) => {
+                // the writes of a tokio file only complete once it is flushed
+                file.flush().await?;
+                self.form.files.insert(name, uploaded_file);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Part {
+    async fn write(&mut self, data: &[u8]) -> Result<(), MultipartError> {
+        match self {
+            Self::Field(_, value) => value.extend_from_slice(data),
+            Self::File(_, uploaded_file, file) => {
+                file.write_all(data).await?;
+                uploaded_file.size += data.len();
+            }
+        }
+        Ok(())
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -0,0 +1,111 @@
+/// A media range of an `Accept` header, e.g. `text/*;q=0.5`
+#[derive(Debug, Clone, PartialEq)]
+struct MediaRange {
+    // in lowercase, `*` for any
+    type_: String,
+    subtype: String,
+    quality: f32,
+}
+
+impl MediaRange {
+    /// Parses the comma separated ranges of an `Accept` header, `None` if one of them is
+    /// malformed. The parameters other than the quality are ignored.
+    fn parse_all(accept: &str) -> Option<Vec<Self>> {
+        let mut ranges = vec![];
+        for range in accept
+            .split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+        {
+            let mut params = range.split(';').map(str::trim);
+            let (type_, subtype) = params.next()?.split_once('/')?;
+            if !is_token(type_) || !is_token(subtype) || (type_ == "*" && subtype != "*") {
+                return None;
+            }
+            let mut quality = 1.0;
+            for param in params {
+                if let Some(value) = param
+                    .strip_prefix("q=")
+                    .or_else(|| param.strip_prefix("Q="))
+                {
+                    quality = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|q| (0.0..=1.0).contains(q))?;
+                }
+            }
+            ranges.push(Self {
+                type_: type_.to_ascii_lowercase(),
+                subtype: subtype.to_ascii_lowercase(),
+                quality,
+            });
+        }
+        Some(ranges)
+    }
+
+    /// How specifically the range matches the media type, `None` if it doesn't
+    fn specificity(&self, type_: &str, subtype: &str) -> Option<u8> {
+        match (self.type_.as_str(), self.subtype.as_str()) {
+            ("*", "*") => Some(0),
+            (range_type, "*") if range_type == type_ => Some(1),
+            (range_type, range_subtype) if range_type == type_ && range_subtype == subtype => {
+                Some(2)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
+/// The quality given by the ranges to a media type, e.g. `application/json`, which is the one
+/// of the most specific range matching it
+fn quality(ranges: &[MediaRange], media_type: &str) -> f32 {
+    let essence = media_type.split(';').next().unwrap_or_default().trim();
+    let (type_, subtype) = essence.split_once('/').unwrap_or((essence, ""));
+    let (type_, subtype) = (type_.to_ascii_lowercase(), subtype.to_ascii_lowercase());
+    ranges
+        .iter()
+        .filter_map(|range| Some((range.specificity(&type_, &subtype)?, range.quality)))
+        .max_by(|(a, a_quality), (b, b_quality)| a.cmp(b).then(a_quality.total_cmp(b_quality)))
+        .map_or(0.0, |(_, quality)| quality)
+}
+
+/// Picks the media type to answer with among the ones offered, by the quality given to them by
+/// the `Accept` header. Between the types accepted as much, e.g. for `*/*`, the default is
+/// picked when it is one of them, else the first one offered. A missing or malformed header
+/// accepts every type. `None` when none of them is acceptable.
+pub fn negotiate<'a>(
+    accept: Option<&str>,
+    media_types: &'a [String],
+    default: Option<&str>,
+) -> Option<&'a str> {
+    let ranges = accept.and_then(MediaRange::parse_all);
+    let mut best: Option<(&str, f32)> = None;
+    for media_type in media_types {
+        let quality = match &ranges {
+            Some(ranges) => quality(ranges, media_type),
+            None => 1.0,
+        };
+        if quality <= 0.0 {
+            continue;
+        }
+        let preferred = match best {
+            None => true,
+            Some((_, best_quality)) if quality > best_quality => true,
+            Some((_, best_quality)) => {
+                quality == best_quality
+                    && default.is_some_and(|default| default.eq_ignore_ascii_case(media_type))
+            }
+        };
+        if preferred {
+            best = Some((media_type, quality));
+        }
+    }
+    best.map(|(media_type, _)| media_type)
+}
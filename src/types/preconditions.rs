@@ -0,0 +1,89 @@
+use actix_web::http::header::EntityTag;
+
+/// The value of an `If-Match` or `If-None-Match` header
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityTags {
+    // `*`, matching any current representation of the resource
+    Any,
+    Tags(Vec<EntityTag>),
+}
+
+impl EntityTags {
+    /// Parses a comma separated list of entity tags, `None` if one of them is malformed.
+    /// The tags are read up to their closing quote, as they can contain commas.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value == "*" {
+            return Some(Self::Any);
+        }
+        let mut tags = vec![];
+        let mut rest = value;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+            if rest.is_empty() {
+                break;
+            }
+            let opening = if rest.starts_with("W/\"") { 3 } else { 1 };
+            let closing = opening + rest.get(opening..)?.find('"')?;
+            tags.push(rest[..=closing].parse().ok()?);
+            rest = &rest[closing + 1..];
+            if !rest.is_empty() && !rest.starts_with([' ', '\t', ',']) {
+                return None;
+            }
+        }
+        if tags.is_empty() {
+            return None;
+        }
+        Some(Self::Tags(tags))
+    }
+
+    /// How the tags are given to python, e.g. `["\"v1\"", "W/\"v2\""]` or `["*"]`
+    pub fn to_strings(&self) -> Vec<String> {
+        match self {
+            Self::Any => vec!["*".to_string()],
+            Self::Tags(tags) => tags.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn matches(&self, etag: Option<&EntityTag>, weak: bool) -> bool {
+        match (self, etag) {
+            (_, None) => false,
+            (Self::Any, Some(_)) => true,
+            (Self::Tags(tags), Some(etag)) => tags.iter().any(|tag| {
+                if weak {
+                    tag.weak_eq(etag)
+                } else {
+                    tag.strong_eq(etag)
+                }
+            }),
+        }
+    }
+}
+
+/// Evaluates the `If-Match` and `If-None-Match` preconditions of a request against the current
+/// entity tag of the resource, `None` when the resource doesn't exist. Returns the status the
+/// request has to be answered with instead of running the method, if any: `412 Precondition
+/// Failed`, or `304 Not Modified` for the `GET` and `HEAD` requests with a matching
+/// `If-None-Match`.
+pub fn evaluate_preconditions(
+    method: &str,
+    if_match: Option<&EntityTags>,
+    if_none_match: Option<&EntityTags>,
+    etag: Option<&EntityTag>,
+) -> Option<u16> {
+    // the comparison of `If-Match` is strong, a weak tag can't guarantee the update is safe
+    if let Some(if_match) = if_match {
+        if !if_match.matches(etag, false) {
+            return Some(412);
+        }
+    }
+    if let Some(if_none_match) = if_none_match {
+        if if_none_match.matches(etag, true) {
+            return match method {
+                "GET" | "HEAD" => Some(304),
+                _ => Some(412),
+            };
+        }
+    }
+    None
+}
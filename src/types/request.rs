@@ -1,11 +1,21 @@
-use actix_web::{web::Bytes, HttpRequest};
+use actix_web::http::header::EntityTag;
+use actix_web::HttpRequest;
 use dashmap::DashMap;
-use pyo3::{prelude::*, types::PyDict};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PyDict, PyString},
+};
 use std::collections::HashMap;
 
 use crate::types::{check_body_type, get_body_from_pyobject, Url};
 
+use super::body_stream::BodyStream;
+use super::cancellation::CancellationToken;
 use super::identity::Identity;
+use super::multipart::UploadedFile;
+use super::negotiation::negotiate;
+use super::preconditions::{evaluate_preconditions, EntityTags};
 
 #[derive(Default, Debug, Clone, FromPyObject)]
 pub struct Request {
@@ -18,6 +28,18 @@ pub struct Request {
     pub url: Url,
     pub ip_addr: Option<String>,
     pub identity: Option<Identity>,
+    pub matched_route: Option<String>,
+    // the data passed from the middlewares to the handler, created with the python request
+    pub extensions: Option<Py<PyDict>>,
+    // the body given to the handlers streaming it, which is then left empty
+    pub stream: Option<Py<BodyStream>>,
+    // the fields and files of the multipart forms sent to the routes streaming their files
+    pub form_data: HashMap<String, String>,
+    pub files: HashMap<String, UploadedFile>,
+    // set when the client disconnects before the request is answered
+    pub cancellation: Option<CancellationToken>,
+    // in seconds since the epoch, read from the deadline header when one is configured
+    pub deadline: Option<f64>,
 }
 
 impl ToPyObject for Request {
@@ -25,11 +47,18 @@ impl ToPyObject for Request {
         let queries = self.queries.clone().into_py(py).extract(py).unwrap();
         let headers = self.headers.clone().into_py(py).extract(py).unwrap();
         let path_params = self.path_params.clone().into_py(py).extract(py).unwrap();
+        let form_data = self.form_data.clone().into_py(py).extract(py).unwrap();
+        let files = self.files.clone().into_py(py).extract(py).unwrap();
         let body = match String::from_utf8(self.body.clone()) {
             Ok(s) => s.into_py(py),
             Err(_) => self.body.clone().into_py(py),
         };
 
+        let extensions = match &self.extensions {
+            Some(extensions) => extensions.clone_ref(py),
+            None => PyDict::new(py).into(),
+        };
+
         let request = PyRequest {
             queries,
             path_params,
@@ -39,6 +68,13 @@ impl ToPyObject for Request {
             url: self.url.clone(),
             ip_addr: self.ip_addr.clone(),
             identity: self.identity.clone(),
+            matched_route: self.matched_route.clone(),
+            extensions,
+            stream: self.stream.as_ref().map(|stream| stream.clone_ref(py)),
+            form_data,
+            files,
+            cancellation: self.cancellation.clone(),
+            deadline: self.deadline,
         };
         Py::new(py, request).unwrap().as_ref(py).into()
     }
@@ -47,8 +83,8 @@ impl ToPyObject for Request {
 impl Request {
     pub fn from_actix_request(
         req: &HttpRequest,
-        body: Bytes,
         global_headers: &DashMap<String, String>,
+        trust_proxy_headers: bool,
     ) -> Self {
         let mut queries = HashMap::new();
         if !req.query_string().is_empty() {
@@ -61,7 +97,13 @@ impl Request {
         let headers = req
             .headers()
             .iter()
-            .map(|(k, v)| (k.to_string(), v.to_str().unwrap().to_string()))
+            // the bytes above ASCII are valid in a value, they are decoded as UTF-8 if they can be
+            .map(|(k, v)| {
+                (
+                    k.to_string(),
+                    String::from_utf8_lossy(v.as_bytes()).into_owned(),
+                )
+            })
             .chain(
                 global_headers
                     .iter()
@@ -69,11 +111,7 @@ impl Request {
             )
             .collect();
 
-        let url = Url::new(
-            req.connection_info().scheme(),
-            req.connection_info().host(),
-            req.path(),
-        );
+        let url = Url::of_request(req, trust_proxy_headers);
         let ip_addr = req.peer_addr().map(|val| val.ip().to_string());
 
         Self {
@@ -81,10 +119,17 @@ impl Request {
             headers,
             method: req.method().as_str().to_owned(),
             path_params: HashMap::new(),
-            body: body.to_vec(),
+            body: vec![],
             url,
             ip_addr,
             identity: None,
+            matched_route: None,
+            extensions: None,
+            stream: None,
+            form_data: HashMap::new(),
+            files: HashMap::new(),
+            cancellation: None,
+            deadline: None,
         }
     }
 }
@@ -108,6 +153,20 @@ pub struct PyRequest {
     pub url: Url,
     #[pyo3(get)]
     pub ip_addr: Option<String>,
+    #[pyo3(get)]
+    pub matched_route: Option<String>,
+    #[pyo3(get, set)]
+    pub extensions: Py<PyDict>,
+    #[pyo3(get)]
+    pub stream: Option<Py<BodyStream>>,
+    #[pyo3(get, set)]
+    pub form_data: Py<PyDict>,
+    #[pyo3(get, set)]
+    pub files: Py<PyDict>,
+    #[pyo3(get)]
+    pub cancellation: Option<CancellationToken>,
+    #[pyo3(get)]
+    pub deadline: Option<f64>,
 }
 
 #[pymethods]
@@ -118,4 +177,132 @@ impl PyRequest {
         self.body = body;
         Ok(())
     }
+
+    /// The entity tags of the `If-Match` header, `["*"]` for any, `None` without a valid header
+    #[getter]
+    pub fn if_match(&self, py: Python) -> PyResult<Option<Vec<String>>> {
+        Ok(self
+            .entity_tags(py, "if-match")?
+            .map(|tags| tags.to_strings()))
+    }
+
+    /// The entity tags of the `If-None-Match` header, like `if_match`
+    #[getter]
+    pub fn if_none_match(&self, py: Python) -> PyResult<Option<Vec<String>>> {
+        Ok(self
+            .entity_tags(py, "if-none-match")?
+            .map(|tags| tags.to_strings()))
+    }
+
+    /// Evaluates the `If-Match` and `If-None-Match` headers against the current entity tag of
+    /// the resource, e.g. `"v2"` or `W/"v2"`, or `None` when it doesn't exist. Returns the status
+    /// to answer with instead of handling the request, 412 or 304, or `None` to handle it.
+    pub fn evaluate_preconditions(&self, py: Python, etag: Option<&str>) -> PyResult<Option<u16>> {
+        let etag = etag
+            .map(|etag| etag.parse::<EntityTag>())
+            .transpose()
+            .map_err(|_| PyValueError::new_err("The entity tag must be a quoted string"))?;
+        Ok(evaluate_preconditions(
+            &self.method,
+            self.entity_tags(py, "if-match")?.as_ref(),
+            self.entity_tags(py, "if-none-match")?.as_ref(),
+            etag.as_ref(),
+        ))
+    }
+
+    /// Picks the media type to answer with among the ones offered by the handler, e.g.
+    /// `["application/json", "text/html"]`, by the `Accept` header. The default is picked between
+    /// the types accepted as much, e.g. for `*/*`, else the first one. `None` when none of them
+    /// is acceptable, for a `406 Not Acceptable`.
+    #[pyo3(signature = (media_types, default = None))]
+    pub fn negotiate(
+        &self,
+        py: Python,
+        media_types: Vec<String>,
+        default: Option<&str>,
+    ) -> PyResult<Option<String>> {
+        if let Some(default) = default {
+            if !media_types
+                .iter()
+                .any(|media_type| media_type.eq_ignore_ascii_case(default))
+            {
+                return Err(PyValueError::new_err(format!(
+                    "The default media type {default} isn't one of the media types offered"
+                )));
+            }
+        }
+        Ok(
+            negotiate(self.header(py, "accept")?.as_deref(), &media_types, default)
+                .map(ToString::to_string),
+        )
+    }
+
+    /// The fields of the form sent in the body: the fields of a multipart form read on the routes
+    /// streaming its files, else the fields of an `application/x-www-form-urlencoded` body. A
+    /// field sent several times has its last value. Empty for the other bodies.
+    pub fn form(&self, py: Python) -> PyResult<Py<PyDict>> {
+        if !self.form_data.as_ref(py).is_empty() {
+            return Ok(self.form_data.as_ref(py).copy()?.into());
+        }
+        let form = PyDict::new(py);
+        if !self.has_urlencoded_body(py)? {
+            return Ok(form.into());
+        }
+        let body = self.body.as_ref(py);
+        let body = match body.downcast::<PyBytes>() {
+            Ok(body) => body.as_bytes(),
+            Err(_) => body.downcast::<PyString>()?.to_str()?.as_bytes(),
+        };
+        for (key, value) in form_urlencoded::parse(body) {
+            form.set_item(key, value)?;
+        }
+        Ok(form.into())
+    }
+
+    /// The value of a parameter wherever it was sent, looked up in order in the path params, the
+    /// query params and the fields of the form, see `form`. `default` when it isn't found.
+    #[pyo3(signature = (name, default = None))]
+    pub fn param(&self, py: Python, name: &str, default: Option<Py<PyAny>>) -> PyResult<Py<PyAny>> {
+        for params in [&self.path_params, &self.queries] {
+            if let Some(value) = params.as_ref(py).get_item(name) {
+                return Ok(value.into());
+            }
+        }
+        if let Some(value) = self.form(py)?.as_ref(py).get_item(name) {
+            return Ok(value.into());
+        }
+        Ok(default.unwrap_or_else(|| py.None()))
+    }
+}
+
+impl PyRequest {
+    // the headers can be changed by the middlewares, so they are read from the python dict.
+    // A malformed header is ignored, as if it wasn't sent.
+    fn entity_tags(&self, py: Python, name: &str) -> PyResult<Option<EntityTags>> {
+        Ok(self
+            .header(py, name)?
+            .and_then(|value| EntityTags::parse(&value)))
+    }
+
+    fn has_urlencoded_body(&self, py: Python) -> PyResult<bool> {
+        Ok(self
+            .header(py, "content-type")?
+            .is_some_and(|content_type| {
+                content_type
+                    .split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+            }))
+    }
+
+    fn header(&self, py: Python, name: &str) -> PyResult<Option<String>> {
+        for (key, value) in self.headers.as_ref(py).iter() {
+            if key.extract::<&str>()?.eq_ignore_ascii_case(name) {
+                return Ok(Some(value.extract()?));
+            }
+        }
+        Ok(None)
+    }
 }
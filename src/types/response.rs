@@ -1,17 +1,45 @@
 use std::collections::HashMap;
+use std::fmt;
 
-use actix_http::{body::BoxBody, StatusCode};
-use actix_web::{HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_http::{
+    body::{BoxBody, SizedStream},
+    StatusCode, Version,
+};
+use actix_web::http::header::{self, EntityTag, HeaderName, HeaderValue, HttpDate};
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::{
+    HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, Responder, ResponseError,
+};
+use dashmap::DashMap;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use log::{debug, error, warn};
 use pyo3::{
-    exceptions::{PyIOError, PyValueError},
+    exceptions::{PyIOError, PyStopIteration, PyValueError},
     prelude::*,
     types::{PyBytes, PyDict, PyString},
 };
+use tokio::io::AsyncRead;
+
+use crate::io_helpers::file_store::read_file_store;
+use crate::io_helpers::{apply_hashmap_headers, content_type_for};
+use crate::logging::LoggedHeaders;
+use crate::types::cancellation::CancelOnDrop;
+use crate::types::error_page::default_error_page;
+use crate::types::file_stream::{read_chunk, FileChunks, MappedFile, Reader, ReaderChunks};
+use crate::types::{check_body_type, get_body_from_pyobject, SUPPORTED_METHODS};
 
-use crate::io_helpers::{apply_hashmap_headers, read_file};
-use crate::types::{check_body_type, get_body_from_pyobject};
+// the bodies below this size aren't compressed
+const MIN_COMPRESSED_SIZE: usize = 256;
+// the log2 of the brotli window, the default of the encoder
+const BROTLI_WINDOW_SIZE: u32 = 22;
 
-#[derive(Debug, Clone, FromPyObject)]
+#[derive(Clone, FromPyObject)]
 pub struct Response {
     pub status_code: u16,
     pub response_type: String,
@@ -19,16 +47,294 @@ pub struct Response {
     #[pyo3(from_py_with = "get_body_from_pyobject")]
     pub body: Vec<u8>,
     pub file_path: Option<String>,
+    // the iterator yielding the chunks of the body, sent as they are yielded instead of `body`
+    pub stream: Option<Py<PyAny>>,
+    // the values sent after a streamed body, for the names declared in its `Trailer` header
+    pub trailers: Option<Py<PyDict>>,
 }
 
+// logged with the values of the sensitive headers redacted
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status_code", &self.status_code)
+            .field("response_type", &self.response_type)
+            .field("headers", &LoggedHeaders(&self.headers))
+            .field("body", &self.body)
+            .field("file_path", &self.file_path)
+            .field("stream", &self.stream)
+            .field("trailers", &self.trailers)
+            .finish()
+    }
+}
+
+/// Stored in the extensions of a request whose connection is closed once it is answered
+#[derive(Debug, Clone, Copy)]
+pub struct CloseConnection;
+
 impl Responder for Response {
     type Body = BoxBody;
 
-    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
         let mut response_builder =
             HttpResponseBuilder::new(StatusCode::from_u16(self.status_code).unwrap());
         apply_hashmap_headers(&mut response_builder, &self.headers);
-        response_builder.body(self.body)
+        if req.extensions_mut().remove::<CloseConnection>().is_some() {
+            response_builder.force_close();
+        }
+        let cancel_on_drop = req.extensions_mut().remove::<CancelOnDrop>();
+        match self.stream {
+            // the length of a streamed file is known, it is sent with a `Content-Length`
+            Some(iterator) => match file_stream(&iterator) {
+                // a mapped file is sent without going through python
+                Some((size, Some(mapped_file))) => response_builder.body(SizedStream::new(
+                    size,
+                    mapped_chunks(mapped_file, cancel_on_drop),
+                )),
+                Some((size, None)) => response_builder.body(SizedStream::new(
+                    size,
+                    iter_chunks(iterator, cancel_on_drop),
+                )),
+                // the length of the body isn't known, so it is sent with a chunked encoding
+                None => {
+                    let chunks = match reader_stream(&iterator) {
+                        Some(reader) => reader_chunks(reader, cancel_on_drop).boxed_local(),
+                        None => iter_chunks(iterator, cancel_on_drop).boxed_local(),
+                    };
+                    let declared = declared_trailers(&self.headers);
+                    match self.trailers {
+                        // actix ends a chunked body without trailers, so it is framed here and
+                        // sent as is. The HTTP/1.0 clients get the body without the trailers.
+                        Some(trailers)
+                            if !declared.is_empty() && req.version() == Version::HTTP_11 =>
+                        {
+                            response_builder.insert_header((header::TRANSFER_ENCODING, "chunked"));
+                            let mut response = response_builder
+                                .streaming(chunked_with_trailers(chunks, trailers, declared));
+                            response.head_mut().no_chunking(true);
+                            response
+                        }
+                        _ => response_builder.streaming(chunks),
+                    }
+                }
+            },
+            None => {
+                if let Some(cancel_on_drop) = cancel_on_drop {
+                    cancel_on_drop.finish();
+                }
+                response_builder.body(self.body)
+            }
+        }
+    }
+}
+
+// the body of a file, or the chunks it is streamed in, and its metadata
+type ServedFile = (Vec<u8>, Option<Py<PyAny>>, fs::Metadata);
+
+/// The precompressed variants of a file that exist next to it, with their encoding, brotli first
+fn precompressed_variants(file_path: &str) -> Vec<(&'static str, String)> {
+    [("br", "br"), ("gzip", "gz")]
+        .iter()
+        .map(|&(encoding, extension)| (encoding, format!("{}.{}", file_path, extension)))
+        .filter(|(_, compressed_path)| Path::new(compressed_path).is_file())
+        .collect()
+}
+
+/// The body of a served file, or the chunks it is streamed in when it is larger than the response
+/// buffer size, and its metadata. The file store is read through its breaker.
+fn read_served_file(file_path: &str) -> Result<io::Result<ServedFile>, Duration> {
+    read_file_store(|| {
+        let metadata = fs::metadata(file_path)?;
+        let stream = Python::with_gil(|py| {
+            let stream = FileChunks::open(py, file_path, metadata.len())?;
+            io::Result::Ok(stream.map(|stream| stream.into_py(py)))
+        })?;
+        let body = match stream {
+            Some(_) => vec![],
+            None => fs::read(file_path)?,
+        };
+        Ok((body, stream, metadata))
+    })
+}
+
+/// The chunks yielded by a python iterator. `next` is called on a blocking thread, so the
+/// worker isn't blocked while the GIL is acquired and the iterator computes the chunk.
+/// The stream is dropped as soon as a chunk can't be written, which cancels the request, so an
+/// iterator polling its cancellation token can stop early.
+fn iter_chunks(
+    iterator: Py<PyAny>,
+    cancel_on_drop: Option<CancelOnDrop>,
+) -> impl Stream<Item = Result<Bytes, io::Error>> {
+    stream::unfold(Some((iterator, cancel_on_drop)), |state| async move {
+        let (iterator, cancel_on_drop) = state?;
+        let next = actix_web::rt::task::spawn_blocking(move || {
+            Python::with_gil(|py| {
+                let chunk = next_chunk(iterator.as_ref(py));
+                (chunk, iterator)
+            })
+        })
+        .await;
+        let next = match next {
+            Ok((Ok(Some(chunk)), iterator)) => {
+                return Some((Ok(Bytes::from(chunk)), Some((iterator, cancel_on_drop))))
+            }
+            Ok((Ok(None), _)) => None,
+            Ok((Err(e), _)) => {
+                error!("Error while streaming the response body: {}", e);
+                // the response is cut short, the client can't take it for a complete one
+                Some((Err(io::Error::other(e.to_string())), None))
+            }
+            Err(e) => Some((Err(io::Error::other(e)), None)),
+        };
+        // the iterator ended, the client didn't leave
+        if let Some(cancel_on_drop) = cancel_on_drop {
+            cancel_on_drop.finish();
+        }
+        next
+    })
+}
+
+/// The names declared in the `Trailer` header of a response, the trailers it sends after its body
+fn declared_trailers(headers: &HashMap<String, String>) -> Vec<HeaderName> {
+    headers
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case(header::TRAILER.as_str()))
+        .flat_map(|(_, value)| value.split(','))
+        .filter_map(|name| name.trim().parse::<HeaderName>().ok())
+        .collect()
+}
+
+/// The chunks of a streamed body in the chunked encoding, followed by the last chunk with the
+/// trailers, which are read once the iterator is exhausted so it can set them
+fn chunked_with_trailers(
+    chunks: impl Stream<Item = Result<Bytes, io::Error>>,
+    trailers: Py<PyDict>,
+    declared: Vec<HeaderName>,
+) -> impl Stream<Item = Result<Bytes, io::Error>> {
+    let last_chunk = stream::once(async move {
+        let trailers = actix_web::rt::task::spawn_blocking(move || {
+            Python::with_gil(|py| read_trailers(trailers.as_ref(py), &declared))
+        })
+        .await
+        .map_err(io::Error::other)?;
+        let mut chunk = BytesMut::from(&b"0\r\n"[..]);
+        for (name, value) in trailers {
+            chunk.extend_from_slice(name.as_str().as_bytes());
+            chunk.extend_from_slice(b": ");
+            chunk.extend_from_slice(value.as_bytes());
+            chunk.extend_from_slice(b"\r\n");
+        }
+        chunk.extend_from_slice(b"\r\n");
+        Ok(chunk.freeze())
+    });
+    chunks
+        // an empty chunk would be taken for the last one
+        .try_filter(|chunk| future::ready(!chunk.is_empty()))
+        .map_ok(|chunk| {
+            let mut framed = BytesMut::with_capacity(chunk.len() + 12);
+            framed.extend_from_slice(format!("{:X}\r\n", chunk.len()).as_bytes());
+            framed.extend_from_slice(&chunk);
+            framed.extend_from_slice(b"\r\n");
+            framed.freeze()
+        })
+        .chain(last_chunk)
+}
+
+/// The trailers set in the dict for the declared names, the others are left out with a warning
+fn read_trailers(trailers: &PyDict, declared: &[HeaderName]) -> Vec<(HeaderName, HeaderValue)> {
+    let mut valid = vec![];
+    for (name, value) in trailers.iter() {
+        let trailer = (|| {
+            let name = name.extract::<&str>().ok()?.parse::<HeaderName>().ok()?;
+            let value = HeaderValue::from_str(value.extract::<&str>().ok()?).ok()?;
+            Some((name, value))
+        })();
+        match trailer {
+            Some((name, value)) if declared.contains(&name) => valid.push((name, value)),
+            Some((name, _)) => warn!("The trailer {} isn't declared in the Trailer header", name),
+            None => warn!("Invalid trailer {}: {}", name, value),
+        }
+    }
+    valid
+}
+
+/// The chunks of a memory mapped file. They are copied on the worker, the pages of a file
+/// served often are already in the page cache.
+fn mapped_chunks(
+    mapped_file: MappedFile,
+    cancel_on_drop: Option<CancelOnDrop>,
+) -> impl Stream<Item = Result<Bytes, io::Error>> {
+    stream::unfold(Some((mapped_file, cancel_on_drop)), |state| async move {
+        let (mut mapped_file, cancel_on_drop) = state?;
+        let next = match mapped_file.next_chunk() {
+            Ok(Some(chunk)) => return Some((Ok(chunk), Some((mapped_file, cancel_on_drop)))),
+            Ok(None) => None,
+            Err(e) => {
+                error!("Error while streaming the file: {}", e);
+                Some((Err(e), None))
+            }
+        };
+        if let Some(cancel_on_drop) = cancel_on_drop {
+            cancel_on_drop.finish();
+        }
+        next
+    })
+}
+
+/// The chunks of an async reader, read on the worker. A read error ends the stream, as it can't
+/// be told to the client in the middle of a chunked body, so the connection is closed before the
+/// last chunk.
+fn reader_chunks(
+    reader: Option<Reader>,
+    cancel_on_drop: Option<CancelOnDrop>,
+) -> impl Stream<Item = Result<Bytes, io::Error>> {
+    stream::unfold(
+        reader.map(|reader| (reader, cancel_on_drop)),
+        |state| async move {
+            let (mut reader, cancel_on_drop) = state?;
+            let next = match read_chunk(&mut reader).await {
+                Ok(Some(chunk)) => return Some((Ok(chunk), Some((reader, cancel_on_drop)))),
+                Ok(None) => None,
+                Err(e) => {
+                    error!("Error while streaming the response body: {}", e);
+                    Some((Err(e), None))
+                }
+            };
+            if let Some(cancel_on_drop) = cancel_on_drop {
+                cancel_on_drop.finish();
+            }
+            next
+        },
+    )
+}
+
+/// The reader streamed by the iterator, `None` once it was sent by another response. `None`
+/// when the iterator isn't a reader.
+fn reader_stream(iterator: &Py<PyAny>) -> Option<Option<Reader>> {
+    Python::with_gil(|py| {
+        let mut chunks = iterator
+            .as_ref(py)
+            .extract::<PyRefMut<ReaderChunks>>()
+            .ok()?;
+        Some(chunks.take_reader())
+    })
+}
+
+/// The size of the file streamed by the iterator, with the file when it is memory mapped.
+/// `None` when the iterator isn't a file.
+fn file_stream(iterator: &Py<PyAny>) -> Option<(u64, Option<MappedFile>)> {
+    Python::with_gil(|py| {
+        let mut chunks = iterator.as_ref(py).extract::<PyRefMut<FileChunks>>().ok()?;
+        Some((chunks.size, chunks.take_mapped_file()))
+    })
+}
+
+/// The next chunk of the iterator, `None` once it is exhausted
+fn next_chunk(iterator: &PyAny) -> PyResult<Option<Vec<u8>>> {
+    match iterator.call_method0("__next__") {
+        Ok(chunk) => get_body_from_pyobject(chunk).map(Some),
+        Err(e) if e.is_instance_of::<PyStopIteration>(iterator.py()) => Ok(None),
+        Err(e) => Err(e),
     }
 }
 
@@ -40,6 +346,8 @@ impl Response {
             headers: headers.clone(),
             body: "Not found".to_owned().into_bytes(),
             file_path: None,
+            stream: None,
+            trailers: None,
         }
     }
 
@@ -50,20 +358,618 @@ impl Response {
             headers: headers.clone(),
             body: "Internal server error".to_owned().into_bytes(),
             file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    pub fn uri_too_long(headers: &HashMap<String, String>) -> Self {
+        Self {
+            status_code: 414,
+            response_type: "text".to_string(),
+            headers: headers.clone(),
+            body: "URI too long".to_owned().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a request with more headers than `ROBYN_MAX_HEADERS`. The headers of the
+    /// request aren't copied, like for the other refused heads.
+    pub fn too_many_headers() -> Self {
+        Self {
+            status_code: 431,
+            response_type: "text".to_string(),
+            headers: HashMap::new(),
+            body: "Request header fields too large".to_owned().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Serves the file at `file_path` with a `Content-Type` derived from its extension and an
+    /// `ETag`. The `Last-Modified` header is added by `with_last_modified`, like for every file
+    /// response. A missing file is answered with a `404 Not Found`. The files larger than the
+    /// response buffer size are streamed from disk instead of being read in memory.
+    pub fn from_file(file_path: &str) -> Self {
+        let (body, stream, metadata) = match read_served_file(file_path) {
+            Ok(Ok(file)) => file,
+            Ok(Err(e)) => {
+                debug!("Could not serve the file `{}`: {}", file_path, e);
+                return Self::not_found(&HashMap::new());
+            }
+            Err(retry_after) => return Self::file_store_unavailable(retry_after),
+        };
+
+        let mut headers =
+            HashMap::from([("Content-Type".to_string(), content_type_for(file_path))]);
+
+        if let Some(modified) = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        {
+            let etag = EntityTag::new_strong(format!(
+                "{:x}:{:x}:{:x}",
+                metadata.len(),
+                modified.as_secs(),
+                modified.subsec_nanos()
+            ));
+            headers.insert("ETag".to_string(), etag.to_string());
+        }
+
+        Self {
+            status_code: 200,
+            response_type: "static_file".to_string(),
+            headers,
+            body,
+            file_path: Some(file_path.to_string()),
+            stream,
+            trailers: None,
+        }
+    }
+
+    /// Streams the bytes read from the reader until its end, with a chunked encoding as their
+    /// length isn't known, like a file streamed through another process
+    pub fn from_stream(reader: impl AsyncRead + Send + 'static) -> PyResult<Self> {
+        let stream = Python::with_gil(|py| ReaderChunks::new(py, reader).map(|s| s.into_py(py)))?;
+        Ok(Self {
+            status_code: 200,
+            response_type: "text".to_string(),
+            headers: HashMap::from([(
+                "Content-Type".to_string(),
+                "application/octet-stream".to_string(),
+            )]),
+            body: vec![],
+            file_path: None,
+            stream: Some(stream),
+            trailers: None,
+        })
+    }
+
+    /// Response to a request for a resource without any content, like the icon of a site
+    /// without one
+    pub fn no_content() -> Self {
+        Self {
+            status_code: 204,
+            response_type: "text".to_string(),
+            headers: HashMap::new(),
+            body: vec![],
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    pub fn method_not_allowed(headers: &HashMap<String, String>) -> Self {
+        let mut headers = headers.clone();
+        headers.insert("Allow".to_string(), SUPPORTED_METHODS.to_string());
+        Self {
+            status_code: 405,
+            response_type: "text".to_string(),
+            headers,
+            body: "Method not allowed".to_owned().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a request with a method the server doesn't know, e.g. `PROPFIND`
+    pub fn not_implemented() -> Self {
+        let mut headers = HashMap::new();
+        headers.insert("Allow".to_string(), SUPPORTED_METHODS.to_string());
+        Self {
+            status_code: 501,
+            response_type: "text".to_string(),
+            headers,
+            body: "Method not implemented".to_owned().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a CORS preflight request, with the CORS headers of the server and the
+    /// methods allowed on the path
+    pub fn cors_preflight(mut headers: HashMap<String, String>, allowed_methods: &str) -> Self {
+        headers.insert(
+            "Access-Control-Allow-Methods".to_string(),
+            allowed_methods.to_string(),
+        );
+        Self {
+            status_code: 204,
+            response_type: "text".to_string(),
+            headers,
+            body: vec![],
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a request with an `Expect` header the server doesn't understand, which is
+    /// anything but `100-continue`. The body of the request isn't read, the connection is closed.
+    pub fn expectation_failed() -> Self {
+        Self {
+            status_code: 417,
+            response_type: "text".to_string(),
+            headers: HashMap::new(),
+            body: "Expectation failed".to_owned().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a request for a file while the breaker of the file store is open, the client
+    /// can retry once its cooldown is over
+    pub fn file_store_unavailable(retry_after: Duration) -> Self {
+        let retry_after = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        Self {
+            status_code: 503,
+            response_type: "text".to_string(),
+            headers: HashMap::from([("Retry-After".to_string(), retry_after.to_string())]),
+            body: "Service unavailable".to_owned().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a request without a valid `Host` header, or for a host the server doesn't
+    /// serve, with the status configured by `ROBYN_UNKNOWN_HOST_STATUS`
+    pub fn invalid_host(status_code: u16) -> Self {
+        let body = match status_code {
+            421 => "Misdirected request",
+            _ => "Invalid host",
+        };
+        Self {
+            status_code,
+            response_type: "text".to_string(),
+            headers: HashMap::new(),
+            body: body.to_owned().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a request with both a `Content-Length` and a `Transfer-Encoding`, when its
+    /// body could be read in two ways
+    pub fn conflicting_length() -> Self {
+        Self {
+            status_code: 400,
+            response_type: "text".to_string(),
+            headers: HashMap::new(),
+            body: "Conflicting Content-Length and Transfer-Encoding"
+                .to_owned()
+                .into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a request with an absolute URI, e.g. `GET http://example.com/ HTTP/1.1`, when
+    /// the server is configured to refuse the requests meant for a proxy
+    pub fn absolute_form_refused() -> Self {
+        Self {
+            status_code: 400,
+            response_type: "text".to_string(),
+            headers: HashMap::new(),
+            body: "Absolute URI not served".to_owned().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a request whose body couldn't be read, e.g. as it is above the size limit.
+    /// The headers of the request aren't copied, they describe the body that was refused.
+    pub fn payload_error(error: &dyn ResponseError) -> Self {
+        Self {
+            status_code: error.status_code().as_u16(),
+            response_type: "text".to_string(),
+            headers: HashMap::new(),
+            body: error.to_string().into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to a `TRACE` request, echoing the request head as it was received by the server.
+    /// The credentials are left out, so the response can't be used to read them from a script.
+    pub fn trace(req: &HttpRequest) -> Self {
+        let mut body = format!("{} {} {:?}\r\n", req.method(), req.uri(), req.version());
+        for (name, value) in req.headers() {
+            if name == header::AUTHORIZATION
+                || name == header::PROXY_AUTHORIZATION
+                || name == header::COOKIE
+            {
+                continue;
+            }
+            body.push_str(name.as_str());
+            body.push_str(": ");
+            body.push_str(&String::from_utf8_lossy(value.as_bytes()));
+            body.push_str("\r\n");
+        }
+        body.push_str("\r\n");
+
+        Self {
+            status_code: 200,
+            response_type: "text".to_string(),
+            headers: HashMap::from([("Content-Type".to_string(), "message/http".to_string())]),
+            body: body.into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Response to `OPTIONS *`, which asks about the capabilities of the server rather than a resource
+    pub fn server_options(headers: &HashMap<String, String>) -> Self {
+        let mut headers = headers.clone();
+        headers.insert("Allow".to_string(), SUPPORTED_METHODS.to_string());
+        Self {
+            status_code: 200,
+            response_type: "text".to_string(),
+            headers,
+            body: vec![],
+            file_path: None,
+            stream: None,
+            trailers: None,
+        }
+    }
+
+    /// Adds the `Last-Modified` header to file responses. If `if_modified_since` is given and the
+    /// file hasn't been modified since then, a `200 OK` is turned into a `304 Not Modified`, the
+    /// other statuses are answered as they are.
+    pub fn with_last_modified(mut self, if_modified_since: Option<&HeaderValue>) -> Self {
+        let modified = match self
+            .file_path
+            .as_ref()
+            .and_then(|file_path| fs::metadata(file_path).ok())
+            .and_then(|metadata| metadata.modified().ok())
+        {
+            Some(modified) => HttpDate::from(modified),
+            None => return self,
+        };
+
+        self.headers
+            .insert("Last-Modified".to_string(), modified.to_string());
+
+        // dates that can't be parsed are ignored and the full response is served
+        let since = if_modified_since
+            .filter(|_| self.status_code == 200)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<HttpDate>().ok());
+        if let Some(since) = since {
+            // the dates of the header have whole seconds, so the file is compared without its
+            // fraction of a second
+            let modified = SystemTime::from(modified)
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |modified| modified.as_secs());
+            let since = SystemTime::from(since)
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs());
+            if modified <= since {
+                self.status_code = 304;
+                self.body = vec![];
+                self.stream = None;
+            }
+        }
+        self
+    }
+
+    /// Serves the precompressed variant of a file response, i.e. the file with a `.br` or `.gz`
+    /// extension next to it, when there is one and the client accepts its encoding. Brotli is
+    /// preferred when the client accepts both. The headers describing the resource, like
+    /// `Content-Type`, keep describing the uncompressed file, but the `ETag` gets the encoding as
+    /// a suffix, e.g. `"abc-gzip"`, so the caches don't mix up the variants.
+    pub fn with_precompressed(mut self, accept_encoding: Option<&HeaderValue>) -> Self {
+        let file_path = match &self.file_path {
+            Some(file_path) => file_path,
+            None => return self,
+        };
+        let variants = precompressed_variants(file_path);
+        if variants.is_empty() {
+            return self;
+        }
+
+        // the response depends on the header as soon as there is a compressed variant
+        self.add_vary("Accept-Encoding");
+
+        let accept_encoding = accept_encoding.and_then(|value| value.to_str().ok());
+        let (encoding, compressed_path) = match variants.into_iter().find(|(encoding, _)| {
+            accept_encoding.is_some_and(|accept_encoding| accepts(accept_encoding, encoding))
+        }) {
+            Some(variant) => variant,
+            None => return self,
+        };
+        match self.status_code {
+            // the uncompressed file is served when the variant can't be read
+            200 => match read_served_file(&compressed_path) {
+                Ok(Ok((body, stream, _))) => {
+                    self.headers
+                        .insert("Content-Encoding".to_string(), encoding.to_string());
+                    self.body = body;
+                    self.stream = stream;
+                    self.suffix_etag(encoding);
+                }
+                Ok(Err(e)) => debug!("Could not read `{}`: {}", compressed_path, e),
+                Err(_) => debug!(
+                    "Could not read `{}`: the file store is unavailable",
+                    compressed_path
+                ),
+            },
+            // a `304 Not Modified` has no body, but has the tag of the variant it stands for
+            304 => self.suffix_etag(encoding),
+            _ => (),
+        }
+        self
+    }
+
+    /// Adds the suffix to the tag of the `ETag` header, keeping it weak or strong. A malformed
+    /// `ETag` is left as is.
+    fn suffix_etag(&mut self, suffix: &str) {
+        for (key, value) in self.headers.iter_mut() {
+            if !key.eq_ignore_ascii_case("etag") {
+                continue;
+            }
+            if let Ok(etag) = value.parse::<EntityTag>() {
+                *value =
+                    EntityTag::new(etag.weak, format!("{}-{}", etag.tag(), suffix)).to_string();
+            }
+        }
+    }
+
+    /// Compresses the body with brotli at the quality, from 1 to 11, when the client accepts it.
+    /// Only the text bodies of at least `MIN_COMPRESSED_SIZE` bytes are compressed, the others
+    /// gain little. A body that is already encoded, e.g. a precompressed file, is left as is. The
+    /// `ETag` of a compressed body gets the `br` suffix, as in `with_precompressed`.
+    pub fn with_brotli(mut self, accept_encoding: Option<&HeaderValue>, quality: u32) -> Self {
+        if quality == 0 || self.stream.is_some() {
+            return self;
+        }
+        let accept_encoding = accept_encoding.and_then(|value| value.to_str().ok());
+        // a `304 Not Modified` has no body, it stands for the file as it would be served
+        let size = match (self.status_code, &self.file_path) {
+            (304, Some(file_path)) => {
+                let size = fs::metadata(file_path).map_or(0, |metadata| metadata.len());
+                // the streamed files aren't compressed, and a precompressed variant already
+                // gave the 304 its tag
+                if FileChunks::is_streamed(size)
+                    || precompressed_variants(file_path)
+                        .iter()
+                        .any(|(encoding, _)| {
+                            accept_encoding
+                                .is_some_and(|accept_encoding| accepts(accept_encoding, encoding))
+                        })
+                {
+                    return self;
+                }
+                size as usize
+            }
+            _ => self.body.len(),
+        };
+        if size < MIN_COMPRESSED_SIZE {
+            return self;
+        }
+        let mut content_type = None;
+        for (key, value) in &self.headers {
+            if key.eq_ignore_ascii_case("content-encoding") {
+                return self;
+            }
+            if key.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.as_str());
+            }
+        }
+        if !content_type.is_some_and(is_compressible) {
+            return self;
+        }
+
+        self.add_vary("Accept-Encoding");
+        if !accept_encoding.is_some_and(|accept_encoding| accepts(accept_encoding, "br")) {
+            return self;
         }
+        // the compressed body is another variant, with its own tag
+        if self.status_code == 304 {
+            self.suffix_etag("br");
+            return self;
+        }
+        let mut writer = brotli::CompressorWriter::new(vec![], 4096, quality, BROTLI_WINDOW_SIZE);
+        // writing to a vec can't fail
+        if writer.write_all(&self.body).is_ok() {
+            self.body = writer.into_inner();
+            self.headers
+                .insert("Content-Encoding".to_string(), "br".to_string());
+            self.suffix_etag("br");
+        }
+        self
+    }
+
+    /// Sets the `Cache-Control` of the route when the handler didn't set one. The error responses
+    /// aren't cached with the policy of the route.
+    pub fn with_cache_control(mut self, cache_control: Option<&str>) -> Self {
+        let cache_control = match cache_control {
+            Some(cache_control) if self.status_code < 400 => cache_control,
+            _ => return self,
+        };
+        if !self
+            .headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case("cache-control"))
+        {
+            self.headers
+                .insert("Cache-Control".to_string(), cache_control.to_string());
+        }
+        self
+    }
+
+    /// Adds a request header the response varies on to the `Vary` header, see `merge_vary`
+    pub fn add_vary(&mut self, field: &str) {
+        let key = self
+            .headers
+            .keys()
+            .find(|key| key.eq_ignore_ascii_case("vary"))
+            .cloned()
+            .unwrap_or_else(|| "Vary".to_string());
+        let vary = merge_vary(self.headers.get(&key).map(String::as_str), field);
+        self.headers.insert(key, vary);
+    }
+
+    /// Reads the whole iterator of a streamed response into its body, for the responses that
+    /// are kept or inspected instead of being sent
+    pub fn collect_stream(&mut self) -> PyResult<()> {
+        if let Some(iterator) = self.stream.take() {
+            self.body = Python::with_gil(|py| {
+                let mut body = vec![];
+                while let Some(chunk) = next_chunk(iterator.as_ref(py))? {
+                    body.extend(chunk);
+                }
+                PyResult::Ok(body)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the body with the error page configured for the status code, else with the
+    /// built-in page of the status code if it has one
+    pub fn with_error_page(mut self, error_pages: &DashMap<u16, String>) -> Self {
+        let (page, content_type) = match error_page(self.status_code, error_pages) {
+            Some(page) => page,
+            None => return self,
+        };
+        self.headers
+            .retain(|key, _| !key.eq_ignore_ascii_case("content-type"));
+        self.headers
+            .insert("Content-Type".to_string(), content_type.to_string());
+        self.body = page.into_bytes();
+        self.stream = None;
+        self
+    }
+
+    /// The error page of a status code, for the responses produced by actix, e.g. for the static
+    /// files. `None` when neither a page is configured nor a built-in one exists for it.
+    pub fn error_page(status_code: u16, error_pages: &DashMap<u16, String>) -> Option<Self> {
+        let (page, content_type) = error_page(status_code, error_pages)?;
+        Some(Self {
+            status_code,
+            response_type: "text".to_string(),
+            headers: HashMap::from([("Content-Type".to_string(), content_type.to_string())]),
+            body: page.into_bytes(),
+            file_path: None,
+            stream: None,
+            trailers: None,
+        })
     }
 }
 
+/// The page configured for the status code, else its built-in one, with its content type
+fn error_page(
+    status_code: u16,
+    error_pages: &DashMap<u16, String>,
+) -> Option<(String, &'static str)> {
+    match error_pages.get(&status_code) {
+        Some(page) => Some((page.value().clone(), "text/html")),
+        None => default_error_page(status_code).map(|page| (page, "text/html; charset=utf-8")),
+    }
+}
+
+/// Adds `field` to the `vary` header value, unless it is already listed or the value is `*`,
+/// so a response always carries a single `Vary` header
+fn merge_vary(vary: Option<&str>, field: &str) -> String {
+    let vary = match vary.map(str::trim) {
+        Some(vary) if !vary.is_empty() => vary,
+        _ => return field.to_string(),
+    };
+    let listed = vary
+        .split(',')
+        .map(str::trim)
+        .any(|listed| listed == "*" || listed.eq_ignore_ascii_case(field));
+    if listed {
+        vary.to_string()
+    } else {
+        format!("{}, {}", vary, field)
+    }
+}
+
+/// Whether an `Accept-Encoding` header value lists the encoding, or `*`, without a quality of 0
+fn accepts(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|coding| {
+        let mut params = coding.split(';').map(str::trim);
+        let name = params.next().unwrap_or_default();
+        let rejected = params.any(|param| {
+            param
+                .strip_prefix("q=")
+                .and_then(|quality| quality.parse::<f32>().ok())
+                == Some(0.0)
+        });
+        (name.eq_ignore_ascii_case(encoding) || name == "*") && !rejected
+    })
+}
+
+/// Whether a body of the content type is text, which compresses well, unlike e.g. images that
+/// are already compressed
+fn is_compressible(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime.as_str(),
+            "application/json" | "application/javascript" | "application/xml"
+        )
+}
+
 impl ToPyObject for Response {
     fn to_object(&self, py: Python) -> PyObject {
         let headers = self.headers.clone().into_py(py).extract(py).unwrap();
-        let body = String::from_utf8(self.body.to_vec()).unwrap().to_object(py);
+        // a body that isn't valid UTF-8, e.g. a binary file, is given to python as bytes
+        let body = match String::from_utf8(self.body.clone()) {
+            Ok(s) => s.to_object(py),
+            Err(_) => PyBytes::new(py, &self.body).to_object(py),
+        };
         let response = PyResponse {
             status_code: self.status_code,
             response_type: self.response_type.clone(),
             headers,
             body,
             file_path: self.file_path.clone(),
+            stream: self.stream.as_ref().map(|stream| stream.clone_ref(py)),
+            trailers: match &self.trailers {
+                Some(trailers) => trailers.clone_ref(py),
+                None => PyDict::new(py).into(),
+            },
         };
         Py::new(py, response).unwrap().as_ref(py).into()
     }
@@ -82,6 +988,11 @@ pub struct PyResponse {
     pub body: Py<PyAny>,
     #[pyo3(get)]
     pub file_path: Option<String>,
+    #[pyo3(get)]
+    pub stream: Option<Py<PyAny>>,
+    // filled by the iterator of the body as it runs, e.g. with a checksum of the chunks
+    #[pyo3(get)]
+    pub trailers: Py<PyDict>,
 }
 
 #[pymethods]
@@ -94,7 +1005,13 @@ impl PyResponse {
         headers: Py<PyDict>,
         body: Py<PyAny>,
     ) -> PyResult<Self> {
-        if body.downcast::<PyString>(py).is_err() && body.downcast::<PyBytes>(py).is_err() {
+        let is_text = body.downcast::<PyString>(py).is_ok() || body.downcast::<PyBytes>(py).is_ok();
+        // an iterator, e.g. a generator, is sent chunk by chunk as it yields them
+        let (body, stream) = if is_text {
+            (body, None)
+        } else if body.as_ref(py).hasattr("__next__")? {
+            (PyBytes::new(py, b"").into(), Some(body))
+        } else {
             return Err(PyValueError::new_err(
                 "Could not convert specified body to bytes",
             ));
@@ -106,6 +1023,49 @@ impl PyResponse {
             headers,
             body,
             file_path: None,
+            stream,
+            trailers: PyDict::new(py).into(),
+        })
+    }
+
+    /// Creates a response serving the file at `file_path`, see `Response::from_file`
+    #[staticmethod]
+    pub fn from_file(py: Python, file_path: &str) -> PyResult<Self> {
+        let response = Response::from_file(file_path);
+        Ok(Self {
+            status_code: response.status_code,
+            response_type: response.response_type,
+            headers: response.headers.into_py(py).extract(py)?,
+            body: PyBytes::new(py, &response.body).into(),
+            file_path: response.file_path,
+            stream: response.stream,
+            trailers: PyDict::new(py).into(),
+        })
+    }
+
+    /// Creates a redirect to the location, sent as it is in the `Location` header. A relative
+    /// location is resolved by the client against the URL of the request,
+    /// `request.url.absolute` gives the absolute one.
+    #[staticmethod]
+    #[pyo3(signature = (location, status_code = 307))]
+    pub fn redirect(py: Python, location: &str, status_code: u16) -> PyResult<Self> {
+        if !(300..400).contains(&status_code) {
+            return Err(PyValueError::new_err(format!(
+                "A redirect has a 3xx status code, not {status_code}"
+            )));
+        }
+        HeaderValue::from_str(location)
+            .map_err(|e| PyValueError::new_err(format!("Invalid location `{location}`: {e}")))?;
+        let headers = PyDict::new(py);
+        headers.set_item("Location", location)?;
+        Ok(Self {
+            status_code,
+            response_type: "text".to_string(),
+            headers: headers.into(),
+            body: PyBytes::new(py, b"").into(),
+            file_path: None,
+            stream: None,
+            trailers: PyDict::new(py).into(),
         })
     }
 
@@ -113,17 +1073,53 @@ impl PyResponse {
     pub fn set_body(&mut self, py: Python, body: Py<PyAny>) -> PyResult<()> {
         check_body_type(py, body.clone())?;
         self.body = body;
+        self.stream = None;
         Ok(())
     }
 
+    /// Adds a request header the response varies on to the `Vary` header
+    pub fn add_vary(&mut self, py: Python, field: &str) -> PyResult<()> {
+        let headers = self.headers.as_ref(py);
+        let mut key = "Vary".to_string();
+        let mut vary = None;
+        for (header, value) in headers.iter() {
+            let header: String = header.extract()?;
+            if header.eq_ignore_ascii_case("vary") {
+                vary = Some(value.extract::<String>()?);
+                key = header;
+            }
+        }
+        headers.set_item(key, merge_vary(vary.as_deref(), field))
+    }
+
     #[setter]
     pub fn set_file_path(&mut self, py: Python, file_path: &str) -> PyResult<()> {
         // we should be handling based on headers but works for now
         self.response_type = "static_file".to_string();
         self.file_path = Some(file_path.to_string());
-        self.body = read_file(file_path)
-            .map_err(|e| PyErr::new::<PyIOError, _>(e.to_string()))?
-            .into_py(py);
+        // the files larger than the response buffer size are streamed from disk
+        let file = read_file_store(|| {
+            let size = fs::metadata(file_path)?.len();
+            let stream = FileChunks::open(py, file_path, size)?.map(|stream| stream.into_py(py));
+            let body = match stream {
+                Some(_) => PyBytes::new(py, b"").into(),
+                None => PyBytes::new(py, &fs::read(file_path)?).into(),
+            };
+            io::Result::Ok((stream, body))
+        });
+        (self.stream, self.body) = match file {
+            Ok(file) => file.map_err(|e| PyErr::new::<PyIOError, _>(e.to_string()))?,
+            // the response becomes a 503 rather than raising, so the failing store isn't logged
+            // by every handler
+            Err(retry_after) => {
+                let response = Response::file_store_unavailable(retry_after);
+                self.status_code = response.status_code;
+                self.response_type = response.response_type;
+                self.headers = response.headers.into_py(py).extract(py)?;
+                self.file_path = None;
+                (None, PyBytes::new(py, &response.body).into())
+            }
+        };
         Ok(())
     }
 }
@@ -0,0 +1,47 @@
+use pyo3::prelude::*;
+
+use crate::types::function_info::MiddlewareType;
+use crate::types::HttpMethod;
+
+/// A route registered in the server, listed by `Server.routes`
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct RouteInfo {
+    #[pyo3(get)]
+    pub method: HttpMethod,
+    // the pattern of the route, e.g. `/users/:id`
+    #[pyo3(get)]
+    pub path: String,
+    #[pyo3(get)]
+    pub is_const: bool,
+    // the types of the middlewares of the routes matching the pattern, the global middlewares
+    // are run with every route
+    #[pyo3(get)]
+    pub middlewares: Vec<MiddlewareType>,
+}
+
+#[pymethods]
+impl RouteInfo {
+    /// The names of the parameters of the path, e.g. `["id"]` for `/users/:id`, `*` parameters
+    /// catching the rest of the path included
+    #[getter]
+    pub fn params(&self) -> Vec<String> {
+        self.path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix([':', '*']))
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[getter]
+    pub fn is_parameterized(&self) -> bool {
+        !self.params().is_empty()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "RouteInfo(method={:?}, path={:?}, is_const={}, middlewares={:?})",
+            self.method, self.path, self.is_const, self.middlewares
+        )
+    }
+}
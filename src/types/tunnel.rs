@@ -0,0 +1,147 @@
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::task::{Context, Poll};
+
+use actix_web::{dev::Payload, web::Bytes};
+use futures::Stream;
+use futures_util::StreamExt;
+use log::debug;
+use pyo3::exceptions::PyIOError;
+use pyo3::{prelude::*, types::PyBytes};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::types::get_body_from_pyobject;
+
+// the number of chunks buffered in each direction of the tunnel
+const BUFFERED_CHUNKS: usize = 8;
+
+type TunnelSender = Arc<StdMutex<Option<mpsc::Sender<Bytes>>>>;
+
+/// The connection of a `CONNECT` request once it is established, given to the tunnel handler.
+/// It reads the bytes sent by the client and writes the ones sent back to it, e.g. from the host
+/// the client asked to connect to.
+#[pyclass]
+#[derive(Debug)]
+pub struct Tunnel {
+    received: Arc<Mutex<mpsc::Receiver<Bytes>>>,
+    // `None` once the tunnel is closed
+    sender: TunnelSender,
+}
+
+/// The body of the response to a `CONNECT` request, the bytes written to its tunnel. Polling it
+/// also reads the connection into the tunnel and runs the handler, as actix only wakes the task
+/// of the request up on its payload.
+pub struct TunnelBody {
+    feed: Option<Pin<Box<dyn Future<Output = ()>>>>,
+    handler: Option<Pin<Box<dyn Future<Output = ()>>>>,
+    sender: TunnelSender,
+    written: mpsc::Receiver<Bytes>,
+}
+
+fn close(sender: &TunnelSender) {
+    sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+impl Tunnel {
+    /// The payload of a `CONNECT` request is the rest of its connection
+    pub fn open(mut payload: Payload) -> (Self, TunnelBody) {
+        let (received_sender, received) = mpsc::channel(BUFFERED_CHUNKS);
+        let (sender, written) = mpsc::channel(BUFFERED_CHUNKS);
+        let sender = Arc::new(StdMutex::new(Some(sender)));
+        let feed = async move {
+            while let Some(chunk) = payload.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        debug!("The connection of the tunnel was interrupted: {}", e);
+                        break;
+                    }
+                };
+                // the handler has stopped reading the tunnel
+                if received_sender.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let tunnel = Self {
+            received: Arc::new(Mutex::new(received)),
+            sender: sender.clone(),
+        };
+        let body = TunnelBody {
+            feed: Some(Box::pin(feed)),
+            handler: None,
+            sender,
+            written,
+        };
+        (tunnel, body)
+    }
+}
+
+impl TunnelBody {
+    /// The tunnel is closed once the handler returns, which ends the response
+    pub fn run(mut self, handler: impl Future<Output = ()> + 'static) -> Self {
+        self.handler = Some(Box::pin(handler));
+        self
+    }
+}
+
+impl Stream for TunnelBody {
+    type Item = Result<Bytes, Infallible>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this
+            .feed
+            .as_mut()
+            .is_some_and(|feed| feed.as_mut().poll(cx).is_ready())
+        {
+            this.feed = None;
+        }
+        if this
+            .handler
+            .as_mut()
+            .is_some_and(|handler| handler.as_mut().poll(cx).is_ready())
+        {
+            this.handler = None;
+            close(&this.sender);
+        }
+        this.written.poll_recv(cx).map(|chunk| chunk.map(Ok))
+    }
+}
+
+#[pymethods]
+impl Tunnel {
+    /// The next bytes sent by the client, empty once it stopped sending
+    fn read<'a>(&self, py: Python<'a>) -> PyResult<&'a PyAny> {
+        let received = self.received.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let chunk = received.lock().await.recv().await;
+            Ok(Python::with_gil(|py| {
+                PyBytes::new(py, chunk.as_deref().unwrap_or_default()).to_object(py)
+            }))
+        })
+    }
+
+    /// Sends the bytes, or the string encoded in UTF-8, to the client
+    fn write<'a>(&self, py: Python<'a>, data: &PyAny) -> PyResult<&'a PyAny> {
+        let data = Bytes::from(get_body_from_pyobject(data)?);
+        let sender = self
+            .sender
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            match sender {
+                Some(sender) if sender.send(data).await.is_ok() => Ok(()),
+                _ => Err(PyIOError::new_err("The tunnel is closed")),
+            }
+        })
+    }
+
+    /// Closes the connection once the bytes written so far are sent
+    fn close(&self) {
+        close(&self.sender);
+    }
+}
@@ -91,10 +91,6 @@ impl Actor for MyWs {
     }
 }
 
-#[derive(Message)]
-#[rtype(result = "Result<(), ()>")]
-struct CommandRunner(String);
-
 /// Handler for ws::Message message
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for MyWs {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {